@@ -3,6 +3,10 @@ pub use sea_orm_migration::prelude::*;
 mod m20250131_084915_create_user_table;
 mod m20250201_132150_create_table_portfolios;
 mod m20250201_132246_create_table_portfolio_asset;
+mod m20250715_090000_add_corporate_actions;
+mod m20250722_090000_create_table_exchange_connection;
+mod m20250801_090000_create_table_symbol_mapping;
+mod m20250805_090000_make_user_email_optional;
 
 pub struct Migrator;
 
@@ -13,6 +17,10 @@ impl MigratorTrait for Migrator {
             Box::new(m20250131_084915_create_user_table::Migration),
             Box::new(m20250201_132150_create_table_portfolios::Migration),
             Box::new(m20250201_132246_create_table_portfolio_asset::Migration),
+            Box::new(m20250715_090000_add_corporate_actions::Migration),
+            Box::new(m20250722_090000_create_table_exchange_connection::Migration),
+            Box::new(m20250801_090000_create_table_symbol_mapping::Migration),
+            Box::new(m20250805_090000_make_user_email_optional::Migration),
         ]
     }
 }
@@ -0,0 +1,130 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioAsset::Table)
+                    .add_column(
+                        ColumnDef::new(PortfolioAsset::QuantityAsOf)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::cust("now()")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PortfolioCashFlow::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("gen_random_uuid()")),
+                    )
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::PortfolioId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PortfolioCashFlow::Symbol).text().not_null())
+                    .col(ColumnDef::new(PortfolioCashFlow::Kind).text().not_null())
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::Amount)
+                            .decimal()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::Currency)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PortfolioCashFlow::ExDate).date().not_null())
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::Reinvested)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(PortfolioCashFlow::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::cust("now()")),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_portfolio_cash_flow_portfolio_id")
+                            .from(PortfolioCashFlow::Table, PortfolioCashFlow::PortfolioId)
+                            .to(Portfolios::Table, Portfolios::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_portfolio_cash_flow_event")
+                    .table(PortfolioCashFlow::Table)
+                    .col(PortfolioCashFlow::PortfolioId)
+                    .col(PortfolioCashFlow::Symbol)
+                    .col(PortfolioCashFlow::Kind)
+                    .col(PortfolioCashFlow::ExDate)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PortfolioCashFlow::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PortfolioAsset::Table)
+                    .drop_column(PortfolioAsset::QuantityAsOf)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum PortfolioAsset {
+    Table,
+    QuantityAsOf,
+}
+
+#[derive(Iden)]
+enum PortfolioCashFlow {
+    Table,
+    Id,
+    PortfolioId,
+    Symbol,
+    Kind,
+    Amount,
+    Currency,
+    ExDate,
+    Reinvested,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Portfolios {
+    Table,
+    Id,
+}
@@ -0,0 +1,119 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExchangeConnection::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExchangeConnection::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .default(Expr::cust("gen_random_uuid()")),
+                    )
+                    .col(ColumnDef::new(ExchangeConnection::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(ExchangeConnection::PortfolioId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExchangeConnection::Exchange)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ExchangeConnection::ApiKey).text().not_null())
+                    .col(
+                        ColumnDef::new(ExchangeConnection::ApiSecret)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExchangeConnection::LastSyncedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ExchangeConnection::LastError).text().null())
+                    .col(
+                        ColumnDef::new(ExchangeConnection::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::cust("now()")),
+                    )
+                    .col(
+                        ColumnDef::new(ExchangeConnection::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::cust("now()")),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_exchange_connection_user_id")
+                            .from(ExchangeConnection::Table, ExchangeConnection::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_exchange_connection_portfolio_id")
+                            .from(ExchangeConnection::Table, ExchangeConnection::PortfolioId)
+                            .to(Portfolios::Table, Portfolios::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_exchange_connection_portfolio_exchange")
+                    .table(ExchangeConnection::Table)
+                    .col(ExchangeConnection::PortfolioId)
+                    .col(ExchangeConnection::Exchange)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExchangeConnection::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ExchangeConnection {
+    Table,
+    Id,
+    UserId,
+    PortfolioId,
+    Exchange,
+    /// Encrypted API key
+    ApiKey,
+    /// Encrypted API secret
+    ApiSecret,
+    LastSyncedAt,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Portfolios {
+    Table,
+    Id,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SymbolMapping::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SymbolMapping::Id)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolMapping::Mapping)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolMapping::DeletedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(SymbolMapping::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::cust("now()")),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SymbolMapping::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SymbolMapping {
+    Table,
+    Id,
+    Mapping,
+    /// Set when an operator deletes the mapping, so that it is not seeded
+    /// again on next import
+    DeletedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,214 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, de};
+
+use crate::DateTime;
+
+/// Trading calendar of an exchange: weekly sessions in the exchange local
+/// time, minus holidays.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradingCalendar {
+    pub id: String,
+    pub timezone: Tz,
+    pub sessions: Vec<Session>,
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    /// Yahoo Finance ticker suffixes of this exchange (e.g. `.L`)
+    #[serde(default)]
+    pub yahoo_suffixes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Session {
+    pub days: Vec<Weekday>,
+    #[serde(deserialize_with = "deserialize_session_time")]
+    pub open: u32,
+    /// Close time, `24:00` being the end of the day
+    #[serde(deserialize_with = "deserialize_session_time")]
+    pub close: u32,
+}
+
+impl TradingCalendar {
+    /// Days ahead searched for the next session
+    const HORIZON_DAYS: u64 = 15;
+
+    pub fn is_open(&self, at: DateTime) -> bool {
+        self.sessions_around(at)
+            .iter()
+            .any(|(open, close)| *open <= at && at < *close)
+    }
+
+    /// Whether any holiday falls in `year`
+    pub fn has_holidays_in(&self, year: i32) -> bool {
+        self.holidays.iter().any(|d| d.year() == year)
+    }
+
+    /// Start of the next session, if the exchange is closed at `at`
+    pub fn next_open(&self, at: DateTime) -> Option<DateTime> {
+        let sessions = self.sessions_around(at);
+        if sessions
+            .iter()
+            .any(|(open, close)| *open <= at && at < *close)
+        {
+            return None;
+        }
+
+        sessions
+            .into_iter()
+            .map(|(open, _)| open)
+            .find(|open| *open > at)
+    }
+
+    /// End of the current session, if the exchange is open at `at`
+    pub fn next_close(&self, at: DateTime) -> Option<DateTime> {
+        self.sessions_around(at)
+            .into_iter()
+            .find(|(open, close)| *open <= at && at < *close)
+            .map(|(_, close)| close)
+    }
+
+    /// Sorted, merged sessions from the day before `at` to
+    /// [`Self::HORIZON_DAYS`] days after
+    fn sessions_around(&self, at: DateTime) -> Vec<(DateTime, DateTime)> {
+        let today = at.with_timezone(&self.timezone).date_naive();
+        let mut sessions = vec![];
+
+        let mut day = today - Days::new(1);
+        while day <= today + Days::new(Self::HORIZON_DAYS) {
+            if !self.holidays.contains(&day) {
+                for s in self
+                    .sessions
+                    .iter()
+                    .filter(|s| s.days.contains(&day.weekday()))
+                {
+                    if let (Some(open), Some(close)) =
+                        (self.to_utc(day, s.open), self.to_utc(day, s.close))
+                    {
+                        sessions.push((open, close));
+                    }
+                }
+            }
+            day = day + Days::new(1);
+        }
+
+        sessions.sort();
+
+        // Merge contiguous sessions (e.g. overnight trading)
+        let mut merged: Vec<(DateTime, DateTime)> = vec![];
+        for (open, close) in sessions {
+            match merged.last_mut() {
+                Some(last) if open <= last.1 => last.1 = last.1.max(close),
+                _ => merged.push((open, close)),
+            }
+        }
+
+        merged
+    }
+
+    fn to_utc(&self, day: NaiveDate, minutes: u32) -> Option<DateTime> {
+        let day = day + Days::new((minutes / (24 * 60)) as u64);
+        let minutes = minutes % (24 * 60);
+        let time = NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0)?;
+
+        self.timezone
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|t| t.to_utc())
+    }
+}
+
+/// Parse `HH:MM` into minutes since midnight
+fn deserialize_session_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    let parsed = s
+        .split_once(':')
+        .and_then(|(h, m)| Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)));
+
+    match parsed {
+        Some((h, m)) if m < 60 && h * 60 + m <= 24 * 60 => Ok(h * 60 + m),
+        _ => Err(de::Error::custom(format!("invalid session time: '{s}'"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn nyse() -> TradingCalendar {
+        TradingCalendar {
+            id: "xnys".to_string(),
+            timezone: chrono_tz::America::New_York,
+            sessions: vec![Session {
+                days: vec![
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri,
+                ],
+                open: 9 * 60 + 30,
+                close: 16 * 60,
+            }],
+            holidays: vec![NaiveDate::from_ymd_opt(2025, 7, 4).unwrap()],
+            yahoo_suffixes: vec![],
+        }
+    }
+
+    fn utc(s: &str) -> DateTime {
+        s.parse::<chrono::DateTime<Utc>>().unwrap()
+    }
+
+    #[test]
+    fn closed_over_weekends_and_holidays() {
+        let cal = nyse();
+
+        // Friday 2025-07-11 15:00 EDT
+        let friday = utc("2025-07-11T19:00:00Z");
+        assert!(cal.is_open(friday));
+        assert_eq!(cal.next_close(friday), Some(utc("2025-07-11T20:00:00Z")));
+
+        // Saturday: next open on Monday 09:30 EDT
+        let saturday = utc("2025-07-12T12:00:00Z");
+        assert!(!cal.is_open(saturday));
+        assert_eq!(cal.next_open(saturday), Some(utc("2025-07-14T13:30:00Z")));
+
+        // Independence Day, Friday 2025-07-04
+        let holiday = utc("2025-07-04T15:00:00Z");
+        assert!(!cal.is_open(holiday));
+        assert_eq!(cal.next_open(holiday), Some(utc("2025-07-07T13:30:00Z")));
+    }
+
+    #[test]
+    fn contiguous_sessions_are_merged() {
+        let cal = TradingCalendar {
+            id: "forex".to_string(),
+            timezone: chrono_tz::America::New_York,
+            sessions: vec![
+                Session {
+                    days: vec![Weekday::Sun],
+                    open: 17 * 60,
+                    close: 24 * 60,
+                },
+                Session {
+                    days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu],
+                    open: 0,
+                    close: 24 * 60,
+                },
+                Session {
+                    days: vec![Weekday::Fri],
+                    open: 0,
+                    close: 17 * 60,
+                },
+            ],
+            holidays: vec![],
+            yahoo_suffixes: vec![],
+        };
+
+        // Monday 2025-07-14: open until Friday 17:00 EDT
+        let monday = utc("2025-07-14T12:00:00Z");
+        assert_eq!(cal.next_close(monday), Some(utc("2025-07-18T21:00:00Z")));
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use crate::DateTime;
+
+/// Time span of each candle of a [`Chart`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, strum_macros::Display)]
+pub enum ChartResolution {
+    #[serde(rename = "5m")]
+    #[strum(serialize = "5m")]
+    Minutes5,
+    #[serde(rename = "1h")]
+    #[strum(serialize = "1h")]
+    Hour1,
+    #[serde(rename = "1d")]
+    #[strum(serialize = "1d")]
+    Daily,
+    #[serde(rename = "1w")]
+    #[strum(serialize = "1w")]
+    Weekly,
+}
+
+impl ChartResolution {
+    /// Finest resolution keeping a chart from `start` to `end` within ~2000
+    /// candles
+    pub fn for_range(start: DateTime, end: DateTime) -> Self {
+        let span = end - start;
+        if span <= TimeDelta::days(5) {
+            Self::Minutes5
+        } else if span <= TimeDelta::days(60) {
+            Self::Hour1
+        } else if span <= TimeDelta::days(5 * 365) {
+            Self::Daily
+        } else {
+            Self::Weekly
+        }
+    }
+
+    pub fn candle_span(&self) -> TimeDelta {
+        match self {
+            Self::Minutes5 => TimeDelta::minutes(5),
+            Self::Hour1 => TimeDelta::hours(1),
+            Self::Daily => TimeDelta::days(1),
+            Self::Weekly => TimeDelta::weeks(1),
+        }
+    }
+
+    /// Longest range served at this resolution, if limited
+    pub fn max_range(&self) -> Option<TimeDelta> {
+        match self {
+            Self::Minutes5 => Some(TimeDelta::days(60)),
+            Self::Hour1 => Some(TimeDelta::days(730)),
+            Self::Daily | Self::Weekly => None,
+        }
+    }
+
+    /// How long a chart at this resolution is cached for
+    pub fn cache_ttl(&self) -> Duration {
+        match self {
+            Self::Minutes5 => Duration::from_secs(5 * 60),
+            Self::Hour1 => Duration::from_secs(15 * 60),
+            Self::Daily => Duration::from_secs(60 * 60),
+            Self::Weekly => Duration::from_secs(6 * 60 * 60),
+        }
+    }
+
+    /// Widen [`start`, `end`] to whole cache periods, so that close requests
+    /// share the same cached chart
+    pub fn align(&self, start: DateTime, end: DateTime) -> (i64, i64) {
+        let period = self.cache_ttl().as_secs() as i64;
+        let start = start.timestamp().div_euclid(period) * period;
+        let end = (end.timestamp() + period - 1).div_euclid(period) * period;
+
+        (start, end)
+    }
+}
+
+/// Normalized OHLC series of a symbol
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chart {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub currency: Option<String>,
+    pub exchange: Option<String>,
+    pub timezone: Option<String>,
+    pub instrument_type: Option<String>,
+    pub resolution: ChartResolution,
+    pub candles: Vec<Candle>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Candle {
+    /// Candle open time, as Unix timestamp in seconds
+    pub ts: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+}
+
+/// Symbol matching a search query
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub name: String,
+    pub quote_type: String,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isin: Option<String>,
+    #[serde(default)]
+    pub source: SymbolSource,
+}
+
+/// Where a [`SymbolInfo`] comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolSource {
+    /// Asset known by DCA-Pal
+    Local,
+    #[default]
+    Yahoo,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn utc(s: &str) -> DateTime {
+        s.parse::<chrono::DateTime<Utc>>().unwrap()
+    }
+
+    #[test]
+    fn resolution_depends_on_range() {
+        let end = utc("2025-07-14T12:00:00Z");
+
+        let res = |days| ChartResolution::for_range(end - TimeDelta::days(days), end);
+        assert_eq!(res(4), ChartResolution::Minutes5);
+        assert_eq!(res(30), ChartResolution::Hour1);
+        assert_eq!(res(365), ChartResolution::Daily);
+        assert_eq!(res(10 * 365), ChartResolution::Weekly);
+    }
+
+    #[test]
+    fn close_requests_share_aligned_range() {
+        let res = ChartResolution::Minutes5;
+        let start = utc("2025-07-10T00:00:00Z");
+
+        let a = res.align(start, utc("2025-07-14T12:01:10Z"));
+        let b = res.align(start, utc("2025-07-14T12:04:59Z"));
+        assert_eq!(a, b);
+        assert_eq!(a.1, utc("2025-07-14T12:05:00Z").timestamp());
+
+        let c = res.align(start, utc("2025-07-14T12:05:01Z"));
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::PriceProvider;
+
+/// Exchange whose accounts can be linked to a portfolio
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Exchange {
+    Kraken,
+}
+
+impl Exchange {
+    /// Provider whose symbol mappings translate the exchange asset tickers
+    pub fn ticker_provider(&self) -> PriceProvider {
+        match self {
+            Exchange::Kraken => PriceProvider::Kraken,
+        }
+    }
+}
+
+/// Read-only API credentials of an exchange account
+#[derive(Clone)]
+pub struct ApiCredentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl Debug for ApiCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiCredentials")
+            .field("api_key", &"<redacted>")
+            .field("api_secret", &"<redacted>")
+            .finish()
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap, HashSet, btree_map::Entry};
+
+use super::entity::{AssetId, Market, MarketId, Price};
+use crate::DateTime;
+
+/// Graph of all known markets, where assets are nodes and each market links
+/// its base and quote in both directions.
+#[derive(Debug, Default)]
+pub struct MarketGraph {
+    edges: HashMap<AssetId, Vec<Edge>>,
+}
+
+#[derive(Debug)]
+struct Edge {
+    to: AssetId,
+    market: MarketId,
+    inverse: bool,
+}
+
+/// Conversion rate found in a [`MarketGraph`], along with the markets it was
+/// computed from.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub price: Price,
+    pub markets: Vec<MarketId>,
+}
+
+#[derive(Debug, Clone)]
+struct Path {
+    rate: f64,
+    ts: Option<DateTime>,
+    valid_until: Option<DateTime>,
+    markets: Vec<MarketId>,
+}
+
+impl MarketGraph {
+    pub fn new<'a>(markets: impl IntoIterator<Item = &'a Market>) -> Self {
+        let mut edges: HashMap<AssetId, Vec<Edge>> = HashMap::new();
+        for m in markets {
+            let (base, quote) = (m.base.id(), m.quote.id());
+            edges.entry(base.clone()).or_default().push(Edge {
+                to: quote.clone(),
+                market: m.id.clone(),
+                inverse: false,
+            });
+            edges.entry(quote.clone()).or_default().push(Edge {
+                to: base.clone(),
+                market: m.id.clone(),
+                inverse: true,
+            });
+        }
+
+        Self { edges }
+    }
+
+    /// Find the `base`/`quote` conversion rate crossing at most `max_hops`
+    /// markets. Paths with fewer hops win; among those, the one whose oldest
+    /// price is the most recent is picked. Markets without a price, as
+    /// returned by `price_of`, are skipped.
+    pub fn find_rate(
+        &self,
+        base: &AssetId,
+        quote: &AssetId,
+        max_hops: usize,
+        price_of: impl Fn(&MarketId) -> Option<Price>,
+    ) -> Option<Conversion> {
+        let mut visited = HashSet::from([base.clone()]);
+        let mut frontier = BTreeMap::from([(
+            base.clone(),
+            Path {
+                rate: 1.,
+                ts: None,
+                valid_until: None,
+                markets: vec![],
+            },
+        )]);
+
+        for _ in 0..max_hops {
+            let mut next: BTreeMap<AssetId, Path> = BTreeMap::new();
+            for (node, path) in &frontier {
+                for edge in self.edges.get(node).into_iter().flatten() {
+                    if visited.contains(&edge.to) {
+                        continue;
+                    }
+
+                    let Some(px) = price_of(&edge.market) else {
+                        continue;
+                    };
+                    if !px.price.is_normal() || px.price < 0. {
+                        continue;
+                    }
+
+                    let rate = if edge.inverse {
+                        1. / px.price
+                    } else {
+                        px.price
+                    };
+                    let ts = path.ts.map_or(px.ts, |ts| ts.min(px.ts));
+                    let valid_until = path
+                        .valid_until
+                        .map_or(px.expires_at(), |v| v.min(px.expires_at()));
+
+                    let candidate = Path {
+                        rate: path.rate * rate,
+                        ts: Some(ts),
+                        valid_until: Some(valid_until),
+                        markets: path
+                            .markets
+                            .iter()
+                            .cloned()
+                            .chain(std::iter::once(edge.market.clone()))
+                            .collect(),
+                    };
+
+                    match next.entry(edge.to.clone()) {
+                        Entry::Vacant(e) => {
+                            e.insert(candidate);
+                        }
+                        Entry::Occupied(mut e) => {
+                            if candidate.ts > e.get().ts {
+                                e.insert(candidate);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(path) = next.remove(quote) {
+                return Some(Conversion {
+                    price: Price::new(path.rate, path.ts.unwrap())
+                        .with_valid_until(path.valid_until.unwrap()),
+                    markets: path.markets,
+                });
+            }
+
+            if next.is_empty() {
+                break;
+            }
+
+            visited.extend(next.keys().cloned());
+            frontier = next;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::app::domain::entity::{Asset, Crypto, Fiat};
+
+    fn crypto(id: &str) -> Asset {
+        Asset::Crypto(Crypto::new_with_id(id.to_string()))
+    }
+
+    fn fiat(id: &str) -> Asset {
+        Asset::Fiat(Fiat::new(id.to_string(), id.to_uppercase()))
+    }
+
+    fn market(base: Asset, quote: Asset, price: f64, age_mins: i64) -> Market {
+        let id = format!("{}{}", base.id(), quote.id());
+        let px = Price::new(price, Utc::now() - Duration::minutes(age_mins));
+        Market::new(id, base, quote, Some(px))
+    }
+
+    fn find(markets: &[Market], base: &str, quote: &str, max_hops: usize) -> Option<Conversion> {
+        let graph = MarketGraph::new(markets);
+        let prices: HashMap<_, _> = markets
+            .iter()
+            .map(|m| (m.id.clone(), m.price().unwrap()))
+            .collect();
+
+        graph.find_rate(&base.to_string(), &quote.to_string(), max_hops, |id| {
+            prices.get(id).copied()
+        })
+    }
+
+    #[test]
+    fn finds_multi_hop_rate_through_inverse_markets() {
+        let markets = vec![
+            market(crypto("ada"), crypto("btc"), 0.00001, 0),
+            market(crypto("btc"), fiat("eur"), 50000., 0),
+            market(fiat("gbp"), fiat("eur"), 1.25, 0),
+        ];
+
+        let conv = find(&markets, "ada", "gbp", 3).unwrap();
+        assert!((conv.price.price - 0.4).abs() < 1e-9);
+        assert_eq!(conv.markets, vec!["adabtc", "btceur", "gbpeur"]);
+
+        assert!(find(&markets, "ada", "gbp", 2).is_none());
+    }
+
+    #[test]
+    fn prefers_fewer_hops_then_fresher_prices() {
+        let markets = vec![
+            market(crypto("ada"), fiat("usd"), 0.5, 10),
+            market(fiat("usd"), fiat("eur"), 0.9, 10),
+            market(crypto("ada"), crypto("btc"), 0.00001, 0),
+            market(crypto("btc"), fiat("eur"), 45000., 0),
+            market(crypto("ada"), crypto("eth"), 0.0002, 20),
+            market(crypto("eth"), fiat("eur"), 2500., 0),
+        ];
+
+        let conv = find(&markets, "ada", "eur", 3).unwrap();
+        assert_eq!(conv.markets, vec!["adabtc", "btceur"]);
+
+        let conv = find(&markets, "ada", "usd", 3).unwrap();
+        assert_eq!(conv.markets, vec!["adausd"]);
+    }
+}
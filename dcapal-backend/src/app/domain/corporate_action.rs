@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Event changing the value of an equity holding, effective from `ex_date`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorporateAction {
+    /// Yahoo Finance ticker of the equity (e.g. `AAPL`)
+    pub symbol: String,
+    pub ex_date: NaiveDate,
+    #[serde(flatten)]
+    pub kind: CorporateActionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, strum_macros::Display)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CorporateActionKind {
+    /// Each share becomes `numerator / denominator` shares
+    Split { numerator: f64, denominator: f64 },
+    /// Cash paid per share, in the listing currency
+    Dividend {
+        amount: f64,
+        currency: Option<String>,
+    },
+}
+
+impl CorporateAction {
+    /// Identifies the action among the ones of the same symbol
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.kind, self.ex_date)
+    }
+
+    /// Shares held after the split for each share held before, if this is a
+    /// valid split
+    pub fn split_ratio(&self) -> Option<f64> {
+        match self.kind {
+            CorporateActionKind::Split {
+                numerator,
+                denominator,
+            } if numerator > 0. && denominator > 0. => Some(numerator / denominator),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(date: &str, numerator: f64, denominator: f64) -> CorporateAction {
+        CorporateAction {
+            symbol: "AAPL".to_string(),
+            ex_date: date.parse().unwrap(),
+            kind: CorporateActionKind::Split {
+                numerator,
+                denominator,
+            },
+        }
+    }
+
+    #[test]
+    fn invalid_splits_are_ignored() {
+        assert_eq!(split("2020-08-31", 0., 1.).split_ratio(), None);
+        assert_eq!(split("2020-08-31", 1., 10.).split_ratio(), Some(0.1));
+        assert_eq!(split("2020-08-31", 1., 10.).key(), "split:2020-08-31");
+    }
+}
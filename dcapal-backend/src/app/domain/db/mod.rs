@@ -2,6 +2,9 @@
 
 pub mod prelude;
 
+pub mod exchange_connection;
 pub mod portfolio_asset;
+pub mod portfolio_cash_flow;
 pub mod portfolios;
+pub mod symbol_mapping;
 pub mod users;
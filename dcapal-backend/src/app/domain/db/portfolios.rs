@@ -32,8 +32,12 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::exchange_connection::Entity")]
+    ExchangeConnection,
     #[sea_orm(has_many = "super::portfolio_asset::Entity")]
     PortfolioAsset,
+    #[sea_orm(has_many = "super::portfolio_cash_flow::Entity")]
+    PortfolioCashFlow,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -44,12 +48,24 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::exchange_connection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExchangeConnection.def()
+    }
+}
+
 impl Related<super::portfolio_asset::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PortfolioAsset.def()
     }
 }
 
+impl Related<super::portfolio_cash_flow::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PortfolioCashFlow.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
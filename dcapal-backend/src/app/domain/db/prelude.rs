@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::{
-    portfolio_asset::Entity as PortfolioAsset, portfolios::Entity as Portfolios,
-    users::Entity as Users,
+    exchange_connection::Entity as ExchangeConnection, portfolio_asset::Entity as PortfolioAsset,
+    portfolio_cash_flow::Entity as PortfolioCashFlow, portfolios::Entity as Portfolios,
+    symbol_mapping::Entity as SymbolMapping, users::Entity as Users,
 };
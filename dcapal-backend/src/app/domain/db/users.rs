@@ -9,8 +9,8 @@ pub struct Model {
     pub id: Uuid,
     #[sea_orm(column_type = "Text", nullable)]
     pub username: Option<String>,
-    #[sea_orm(column_type = "Text", unique)]
-    pub email: String,
+    #[sea_orm(column_type = "Text", nullable, unique)]
+    pub email: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub role: String,
     pub created_at: DateTimeWithTimeZone,
@@ -19,10 +19,18 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::exchange_connection::Entity")]
+    ExchangeConnection,
     #[sea_orm(has_many = "super::portfolios::Entity")]
     Portfolios,
 }
 
+impl Related<super::exchange_connection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExchangeConnection.def()
+    }
+}
+
 impl Related<super::portfolios::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Portfolios.def()
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use chrono::{Duration, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{DateTime, app::infra::utils::Expiring};
+use crate::{DateTime, app::infra::utils::Expiring, config::PriceProvider};
 
 pub type AssetId = String;
 pub type MarketId = String;
@@ -31,17 +33,37 @@ impl Fiat {
     }
 }
 
+/// Stock or fund listed on an exchange, identified by its Yahoo Finance
+/// ticker (e.g. `VWCE.DE`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Equity {
+    pub id: AssetId,
+    pub symbol: String,
+}
+
+impl Equity {
+    pub fn new_with_ticker(ticker: &str) -> Self {
+        Self {
+            id: ticker.to_lowercase(),
+            symbol: ticker.to_uppercase(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum Asset {
     Crypto(Crypto),
     Fiat(Fiat),
+    Equity(Equity),
 }
 
-#[derive(Debug, Clone, Copy, strum_macros::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, strum_macros::Display)]
+#[serde(rename_all = "lowercase")]
 pub enum AssetKind {
     Crypto,
     Fiat,
+    Equity,
 }
 
 impl Asset {
@@ -49,6 +71,7 @@ impl Asset {
         match self {
             Asset::Crypto(a) => &a.id,
             Asset::Fiat(a) => &a.id,
+            Asset::Equity(a) => &a.id,
         }
     }
 
@@ -60,6 +83,7 @@ impl Asset {
         match self {
             Asset::Crypto(_) => AssetKind::Crypto,
             Asset::Fiat(_) => AssetKind::Fiat,
+            Asset::Equity(_) => AssetKind::Equity,
         }
     }
 }
@@ -69,34 +93,59 @@ pub struct Price {
     pub price: f64,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub ts: DateTime,
+    /// End of price validity. If unset, the price is valid until the end of
+    /// the 5-minute range `ts` falls in
+    #[serde(
+        rename = "validUntil",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrono::serde::ts_seconds_option"
+    )]
+    pub valid_until: Option<DateTime>,
 }
 
 impl Price {
     const VALIDITY_MINS: u32 = 5;
 
     pub fn new(price: f64, ts: DateTime) -> Self {
-        Self { price, ts }
+        Self {
+            price,
+            ts,
+            valid_until: None,
+        }
     }
-}
 
-impl Expiring for Price {
-    fn is_outdated(&self) -> bool {
-        let now = Utc::now();
-        if now.date_naive() > self.ts.date_naive() || now.time().hour() > self.ts.time().hour() {
-            return true;
-        }
+    pub fn with_valid_until(mut self, valid_until: DateTime) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
 
-        let now_mins = now.time().minute();
-        let ts_mins = self.ts.time().minute();
+    /// Instant this price becomes outdated
+    pub fn expires_at(&self) -> DateTime {
+        self.valid_until
+            .unwrap_or_else(|| Self::default_validity(self.ts))
+    }
 
-        let now_validity_range = (now_mins / Self::VALIDITY_MINS) * Self::VALIDITY_MINS;
-        let ts_validity_range = (ts_mins / Self::VALIDITY_MINS) * Self::VALIDITY_MINS;
+    /// End of the 5-minute range `ts` falls in
+    pub fn default_validity(ts: DateTime) -> DateTime {
+        let range_mins = (ts.minute() / Self::VALIDITY_MINS) * Self::VALIDITY_MINS;
+        let range_start = ts
+            .with_minute(range_mins)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap();
+
+        range_start + Duration::minutes(Self::VALIDITY_MINS as i64)
+    }
+}
 
-        now_validity_range > ts_validity_range
+impl Expiring for Price {
+    fn is_outdated(&self) -> bool {
+        Utc::now() >= self.expires_at()
     }
 
     fn time_to_live(&self) -> std::time::Duration {
-        (self.ts + Duration::minutes(Self::VALIDITY_MINS as i64) - Utc::now())
+        (self.expires_at() - Utc::now())
             .to_std()
             .unwrap_or_else(|_| std::time::Duration::from_secs(0))
     }
@@ -151,12 +200,46 @@ impl OHLCFrequency {
     }
 }
 
+/// Lifecycle of a market on the provider listing it
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, strum_macros::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum MarketStatus {
+    #[default]
+    Active,
+    /// Listed, but not trading (e.g. Kraken `cancel_only` pairs)
+    Halted,
+    /// No longer listed. Delisted markets are not priced anymore and are
+    /// eventually purged
+    Delisted,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Market {
     pub id: MarketId,
     pub pair: String,
     pub base: Asset,
     pub quote: Asset,
+    /// Trading calendar of the exchange this market is listed on. Markets
+    /// trade 24/7 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<String>,
+    /// Provider pricing this market, overriding the configured
+    /// `priceProvider` (e.g. Yahoo for equities tracked from portfolios)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<PriceProvider>,
+    #[serde(default)]
+    pub status: MarketStatus,
+    /// Last time `status` changed
+    #[serde(
+        rename = "statusSince",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrono::serde::ts_seconds_option"
+    )]
+    pub status_since: Option<DateTime>,
     #[serde(flatten)]
     price: Option<Price>,
 }
@@ -168,10 +251,57 @@ impl Market {
             pair: format!("{}/{}", base.id().to_uppercase(), quote.id().to_uppercase()),
             base,
             quote,
+            exchange: None,
+            provider: None,
+            status: MarketStatus::Active,
+            status_since: None,
             price,
         }
     }
 
+    pub fn with_exchange(mut self, exchange: Option<String>) -> Self {
+        self.exchange = exchange;
+        self
+    }
+
+    pub fn with_provider(mut self, provider: Option<PriceProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    pub fn with_status(mut self, status: MarketStatus, since: Option<DateTime>) -> Self {
+        self.status = status;
+        self.status_since = since;
+        self
+    }
+
+    /// Move to `status` at `now`. Returns `true` if the status changed
+    pub fn update_status(&mut self, status: MarketStatus, now: DateTime) -> bool {
+        if self.status == status {
+            return false;
+        }
+
+        self.status = status;
+        self.status_since = Some(now);
+        true
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == MarketStatus::Active
+    }
+
+    pub fn is_listed(&self) -> bool {
+        self.status != MarketStatus::Delisted
+    }
+
+    /// Whether the market has been delisted for longer than `retention`
+    pub fn is_purgeable(&self, now: DateTime, retention: Duration) -> bool {
+        self.status == MarketStatus::Delisted
+            && self
+                .status_since
+                .is_none_or(|since| now - since > retention)
+    }
+
     pub fn price(&self) -> &Option<Price> {
         &self.price
     }
@@ -184,6 +314,16 @@ impl Market {
         self.base.is_fiat() && self.quote.is_fiat()
     }
 
+    /// Provider pricing this market: its own one if set, else `fiat` for
+    /// fiat/fiat markets and `default` for any other
+    pub fn price_provider(&self, default: PriceProvider, fiat: PriceProvider) -> PriceProvider {
+        match self.provider {
+            Some(p) => p,
+            None if self.is_fiat() => fiat,
+            None => default,
+        }
+    }
+
     pub fn is_price_outdated(&self) -> bool {
         let last_price = self.price();
         last_price.is_some() && last_price.as_ref().unwrap().is_outdated()
@@ -202,3 +342,69 @@ impl Expiring for Market {
             .unwrap_or_else(|| std::time::Duration::from_secs(0))
     }
 }
+
+/// Maps an internal [`AssetId`] to the tickers used by each [`PriceProvider`]
+/// and to its security identifiers, if any
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolMapping {
+    pub id: AssetId,
+    /// Asset whose markets are used to price this one (e.g. `eth2` -> `eth`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias_of: Option<AssetId>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tickers: HashMap<PriceProvider, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cusip: Option<String>,
+}
+
+impl SymbolMapping {
+    pub fn ticker(&self, provider: PriceProvider) -> Option<&String> {
+        self.tickers.get(&provider)
+    }
+}
+
+/// Market referenced by user portfolios or recent price requests, kept up to
+/// date until no longer referenced
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedMarket {
+    pub id: MarketId,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub referenced_at: DateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market() -> Market {
+        Market::new(
+            "btceur".to_string(),
+            Asset::Crypto(Crypto::new_with_id("btc".to_string())),
+            Asset::Fiat(Fiat::new("eur".to_string(), "Euro".to_string())),
+            None,
+        )
+    }
+
+    #[test]
+    fn delisted_markets_are_purged_after_retention() {
+        let now = Utc::now();
+        let retention = Duration::days(30);
+        let mut m = market();
+
+        assert!(!m.update_status(MarketStatus::Active, now));
+        assert!(m.update_status(MarketStatus::Delisted, now));
+        assert!(!m.update_status(MarketStatus::Delisted, now + Duration::days(1)));
+        assert_eq!(m.status_since, Some(now));
+
+        assert!(!m.is_purgeable(now + Duration::days(29), retention));
+        assert!(m.is_purgeable(now + Duration::days(31), retention));
+
+        // Relisted markets are kept
+        assert!(m.update_status(MarketStatus::Active, now + Duration::days(31)));
+        assert!(!m.is_purgeable(now + Duration::days(90), retention));
+    }
+}
@@ -1,23 +1,36 @@
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use tracing::{error, warn};
 
 use super::entity::{Market, Price};
-use crate::{config::PriceProvider, ports::outbound::adapter::PriceProviders};
+use crate::{
+    app::{infra::utils::day_start, services::calendar::TradingCalendars},
+    config::PriceProvider,
+    error::DcaError,
+    ports::outbound::adapter::PriceProviders,
+};
 
 pub async fn fetch_market_price(
     market: &Market,
     providers: &PriceProviders,
     provider: PriceProvider,
+    calendars: &TradingCalendars,
 ) -> Option<Price> {
     let now = Utc::now();
     let price = match provider {
-        PriceProvider::CryptoWatch => providers.cw.fetch_market_price(market, now).await,
+        PriceProvider::Binance => providers.binance.fetch_market_price(market, now).await,
+        PriceProvider::CryptoWatch => match &providers.cw {
+            Some(cw) => cw.fetch_market_price(market, now).await,
+            None => Err(cw_not_configured()),
+        },
+        PriceProvider::Ecb => providers.ecb.fetch_market_price(market, now).await,
         PriceProvider::Kraken => providers.kraken.fetch_market_price(market, now).await,
         PriceProvider::Yahoo => providers.yahoo.fetch_market_price(market, now).await,
     };
 
     match price {
-        Ok(Some(px)) => Some(Price::new(px, now)),
+        Ok(Some(px)) => {
+            Some(Price::new(px, now).with_valid_until(calendars.price_validity(market, now)))
+        }
         Ok(None) => {
             warn!(
                 "Cannot fetch {} price for any frequency (ts={now})",
@@ -34,3 +47,42 @@ pub async fn fetch_market_price(
         }
     }
 }
+
+/// Fetch the close price of `market` on `date`, timestamped at the start of
+/// the day
+pub async fn fetch_daily_price(
+    market: &Market,
+    providers: &PriceProviders,
+    provider: PriceProvider,
+    date: NaiveDate,
+) -> Option<Price> {
+    let price = match provider {
+        PriceProvider::Binance => providers.binance.fetch_daily_price(market, date).await,
+        PriceProvider::CryptoWatch => match &providers.cw {
+            Some(cw) => cw.fetch_daily_price(market, date).await,
+            None => Err(cw_not_configured()),
+        },
+        PriceProvider::Ecb => providers.ecb.fetch_daily_price(market, date).await,
+        PriceProvider::Kraken => providers.kraken.fetch_daily_price(market, date).await,
+        PriceProvider::Yahoo => providers.yahoo.fetch_daily_price(market, date).await,
+    };
+
+    match price {
+        Ok(Some(px)) => Some(Price::new(px, day_start(date))),
+        Ok(None) => {
+            warn!("Cannot fetch {} daily price (date={date})", market.id);
+            None
+        }
+        Err(e) => {
+            error!(
+                "Cannot fetch {} daily price (date={date}): {e:?}",
+                market.id
+            );
+            None
+        }
+    }
+}
+
+fn cw_not_configured() -> DcaError {
+    DcaError::Generic("CryptoWatch provider is not configured".to_string())
+}
@@ -1,3 +1,9 @@
+pub mod calendar;
+pub mod chart;
+pub mod connector;
+pub mod conversion;
+pub mod corporate_action;
 pub mod db;
 pub mod entity;
 pub mod market_data_utils;
+pub mod search;
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use super::{
+    chart::{SymbolInfo, SymbolSource},
+    entity::{Asset, AssetId, AssetKind, SymbolMapping},
+};
+
+/// In-memory search index over known assets, matching queries against asset
+/// ids, names, provider tickers and ISINs
+#[derive(Debug, Default)]
+pub struct AssetIndex {
+    entries: Vec<IndexEntry>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    kind: AssetKind,
+    info: SymbolInfo,
+    /// Lowercase identifiers: asset id, symbol and provider tickers
+    keys: Vec<String>,
+    /// Lowercase name words
+    words: Vec<String>,
+    name: String,
+}
+
+impl AssetIndex {
+    const ISIN_MATCH: u32 = 1000;
+    const EXACT_KEY: u32 = 900;
+    const EXACT_NAME: u32 = 800;
+    const PREFIX_KEY: u32 = 700;
+    const PREFIX_WORD: u32 = 500;
+    const FUZZY_KEY: u32 = 300;
+    const FUZZY_WORD: u32 = 200;
+    const SUBSTRING: u32 = 100;
+
+    pub fn new<'a>(
+        assets: impl IntoIterator<Item = &'a Asset>,
+        mappings: impl IntoIterator<Item = SymbolMapping>,
+    ) -> Self {
+        let mappings: HashMap<AssetId, SymbolMapping> =
+            mappings.into_iter().map(|m| (m.id.clone(), m)).collect();
+
+        let entries = assets
+            .into_iter()
+            .map(|a| {
+                let mapping = mappings.get(a.id());
+                let (symbol, name, quote_type) = match a {
+                    Asset::Crypto(c) => (c.id.to_uppercase(), c.symbol.clone(), "CRYPTOCURRENCY"),
+                    Asset::Fiat(f) => (f.id.to_uppercase(), f.symbol.clone(), "CURRENCY"),
+                    Asset::Equity(e) => (e.symbol.clone(), e.symbol.clone(), "EQUITY"),
+                };
+
+                let mut keys = vec![a.id().to_lowercase(), symbol.to_lowercase()];
+                keys.extend(
+                    mapping
+                        .into_iter()
+                        .flat_map(|m| m.tickers.values())
+                        .map(|t| t.to_lowercase()),
+                );
+                keys.sort();
+                keys.dedup();
+
+                let name_lc = name.to_lowercase();
+                let words = name_lc
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_string)
+                    .collect();
+
+                IndexEntry {
+                    kind: a.kind(),
+                    info: SymbolInfo {
+                        symbol,
+                        name,
+                        quote_type: quote_type.to_string(),
+                        exchange: None,
+                        exchange_name: None,
+                        isin: mapping.and_then(|m| m.isin.clone()),
+                        source: SymbolSource::Local,
+                    },
+                    keys,
+                    words,
+                    name: name_lc,
+                }
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Best `limit` assets matching `query`, optionally restricted to assets
+    /// of `kind`
+    pub fn search(&self, query: &str, kind: Option<AssetKind>, limit: usize) -> Vec<SymbolInfo> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let mut hits = self
+            .entries
+            .iter()
+            .filter(|e| kind.is_none_or(|k| e.kind == k))
+            .filter_map(|e| Some((e.score(&query)?, e)))
+            .collect::<Vec<_>>();
+
+        // Best score first, then shorter and alphabetically sorted symbols
+        hits.sort_by(|(sa, a), (sb, b)| {
+            sb.cmp(sa)
+                .then(a.info.symbol.len().cmp(&b.info.symbol.len()))
+                .then(a.info.symbol.cmp(&b.info.symbol))
+        });
+
+        hits.into_iter()
+            .take(limit)
+            .map(|(_, e)| e.info.clone())
+            .collect()
+    }
+}
+
+impl IndexEntry {
+    fn score(&self, query: &str) -> Option<u32> {
+        if self
+            .info
+            .isin
+            .as_ref()
+            .is_some_and(|isin| isin.eq_ignore_ascii_case(query))
+        {
+            return Some(AssetIndex::ISIN_MATCH);
+        }
+
+        if self.keys.iter().any(|k| k == query) {
+            return Some(AssetIndex::EXACT_KEY);
+        }
+
+        if self.name == query {
+            return Some(AssetIndex::EXACT_NAME);
+        }
+
+        // Prefer prefixes covering most of the key
+        let prefix_score = |base: u32, candidates: &[String]| {
+            candidates
+                .iter()
+                .filter(|c| c.starts_with(query))
+                .map(|c| base.saturating_sub((c.len() - query.len()).min(99) as u32))
+                .max()
+        };
+
+        if let Some(score) = prefix_score(AssetIndex::PREFIX_KEY, &self.keys) {
+            return Some(score);
+        }
+
+        if let Some(score) = prefix_score(AssetIndex::PREFIX_WORD, &self.words) {
+            return Some(score);
+        }
+
+        let max_typos = max_typos(query);
+        if max_typos > 0 {
+            let fuzzy_score = |base: u32, candidates: &[String]| {
+                candidates
+                    .iter()
+                    .map(|c| edit_distance(query, c))
+                    .filter(|d| *d <= max_typos)
+                    .min()
+                    .map(|d| base - 50 * d as u32)
+            };
+
+            if let Some(score) = fuzzy_score(AssetIndex::FUZZY_KEY, &self.keys) {
+                return Some(score);
+            }
+
+            if let Some(score) = fuzzy_score(AssetIndex::FUZZY_WORD, &self.words) {
+                return Some(score);
+            }
+        }
+
+        self.name.contains(query).then_some(AssetIndex::SUBSTRING)
+    }
+}
+
+/// Typos tolerated in a query: none for short ones, where fuzzy matches are
+/// mostly noise
+fn max_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..4 => 0,
+        4..8 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::domain::entity::{Crypto, Equity, Fiat},
+        config::PriceProvider,
+    };
+
+    fn index() -> AssetIndex {
+        let assets = vec![
+            Asset::Crypto(Crypto {
+                id: "btc".to_string(),
+                symbol: "Bitcoin".to_string(),
+            }),
+            Asset::Crypto(Crypto {
+                id: "bch".to_string(),
+                symbol: "Bitcoin Cash".to_string(),
+            }),
+            Asset::Crypto(Crypto {
+                id: "eth".to_string(),
+                symbol: "Ethereum".to_string(),
+            }),
+            Asset::Fiat(Fiat::new("eur".to_string(), "Euro".to_string())),
+            Asset::Equity(Equity::new_with_ticker("VWCE.DE")),
+        ];
+
+        let mappings = vec![
+            SymbolMapping {
+                id: "btc".to_string(),
+                alias_of: None,
+                tickers: HashMap::from([(PriceProvider::Kraken, "XBT".to_string())]),
+                isin: None,
+                cusip: None,
+            },
+            SymbolMapping {
+                id: "vwce.de".to_string(),
+                alias_of: None,
+                tickers: HashMap::new(),
+                isin: Some("IE00BK5BQT80".to_string()),
+                cusip: None,
+            },
+        ];
+
+        AssetIndex::new(&assets, mappings)
+    }
+
+    fn symbols(results: Vec<SymbolInfo>) -> Vec<String> {
+        results.into_iter().map(|r| r.symbol).collect()
+    }
+
+    #[test]
+    fn ranks_exact_then_prefix_matches() {
+        let index = index();
+
+        assert_eq!(symbols(index.search("BTC", None, 10)), vec!["BTC"]);
+        assert_eq!(symbols(index.search("xbt", None, 10)), vec!["BTC"]);
+        assert_eq!(
+            symbols(index.search("bitcoin", None, 10)),
+            vec!["BTC", "BCH"]
+        );
+        assert_eq!(symbols(index.search("vwce", None, 10)), vec!["VWCE.DE"]);
+        assert_eq!(
+            symbols(index.search("ie00bk5bqt80", None, 10)),
+            vec!["VWCE.DE"]
+        );
+    }
+
+    #[test]
+    fn tolerates_typos_in_longer_queries() {
+        let index = index();
+
+        assert_eq!(symbols(index.search("etherum", None, 10)), vec!["ETH"]);
+        assert!(index.search("etx", None, 10).is_empty());
+    }
+
+    #[test]
+    fn filters_by_asset_kind() {
+        let index = index();
+
+        assert_eq!(
+            symbols(index.search("e", Some(AssetKind::Fiat), 10)),
+            vec!["EUR"]
+        );
+        assert!(index.search("btc", Some(AssetKind::Equity), 10).is_empty());
+    }
+}
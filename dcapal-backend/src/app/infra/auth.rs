@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    AppContext,
+    app::infra::claim::Claims,
+    error::{DcaError, Result},
+};
+
+/// Role of an authenticated caller, from the `role` claim of its token
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Role {
+    User,
+    Admin,
+    /// Read-only access for internal services (e.g. monitoring)
+    Service,
+}
+
+/// Actions guarded by the authorization layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum_macros::Display)]
+pub enum Permission {
+    /// Manage the caller's own portfolios and exchange connections
+    ManagePortfolios,
+    /// Inspect market data and background jobs through the admin API
+    ReadAdmin,
+    /// Fix market data and run background jobs through the admin API
+    WriteAdmin,
+}
+
+impl Role {
+    /// Role of a token `role` claim. Users tokens issued by Supabase carry
+    /// the `authenticated` role
+    pub fn from_claim(role: &str) -> Option<Self> {
+        match role {
+            "authenticated" | "user" => Some(Self::User),
+            "admin" => Some(Self::Admin),
+            "service" => Some(Self::Service),
+            _ => None,
+        }
+    }
+
+    pub fn permissions(self) -> &'static [Permission] {
+        match self {
+            Self::User => &[Permission::ManagePortfolios],
+            Self::Admin => &[
+                Permission::ManagePortfolios,
+                Permission::ReadAdmin,
+                Permission::WriteAdmin,
+            ],
+            Self::Service => &[Permission::ReadAdmin],
+        }
+    }
+
+    pub fn can(self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+/// Requirement of the routes guarded by [`authorize`]
+#[derive(Clone)]
+pub struct Authorization {
+    ctx: AppContext,
+    permission: Permission,
+}
+
+impl Authorization {
+    pub fn new(ctx: AppContext, permission: Permission) -> Self {
+        Self { ctx, permission }
+    }
+}
+
+/// Middleware rejecting requests without valid credentials (401) or whose
+/// role lacks the required permission (403). Users of authorized [`Claims`]
+/// are registered, then claims are handed over to handlers through request
+/// extensions.
+pub async fn authorize(
+    State(auth): State<Authorization>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let (mut parts, body) = req.into_parts();
+    let claims = Claims::from_bearer(&mut parts, &auth.ctx).await?;
+
+    let role = match claims.role.as_deref() {
+        Some(role) => Role::from_claim(role)
+            .ok_or_else(|| DcaError::Forbidden(format!("Unknown role '{role}'")))?,
+        None => return Err(DcaError::Forbidden("Missing role".to_string())),
+    };
+    if !role.can(auth.permission) {
+        return Err(DcaError::Forbidden(format!(
+            "Role '{role}' is missing permission {}",
+            auth.permission
+        )));
+    }
+
+    auth.ctx
+        .repos
+        .user
+        .save_user_if_not_present(&claims)
+        .await?;
+    parts.extensions.insert(claims);
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_claims_to_roles() {
+        assert_eq!(Role::from_claim("authenticated"), Some(Role::User));
+        assert_eq!(Role::from_claim("admin"), Some(Role::Admin));
+        assert_eq!(Role::from_claim("service"), Some(Role::Service));
+        assert_eq!(Role::from_claim("anon"), None);
+    }
+
+    #[test]
+    fn grants_permissions_by_role() {
+        assert!(Role::User.can(Permission::ManagePortfolios));
+        assert!(!Role::User.can(Permission::ReadAdmin));
+
+        assert!(Role::Service.can(Permission::ReadAdmin));
+        assert!(!Role::Service.can(Permission::WriteAdmin));
+        assert!(!Role::Service.can(Permission::ManagePortfolios));
+
+        assert!(Role::Admin.can(Permission::WriteAdmin));
+        assert!(Role::Admin.can(Permission::ManagePortfolios));
+    }
+}
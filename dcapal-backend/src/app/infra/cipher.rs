@@ -0,0 +1,99 @@
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    rand::{SecureRandom, SystemRandom},
+};
+
+use crate::error::{DcaError, Result};
+
+/// AES-256-GCM encryption of secrets stored at rest, e.g. exchange API
+/// credentials. Ciphertexts are base64 encoded `nonce || sealed data`, bound
+/// to the associated data they were encrypted with.
+pub struct CredentialCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl CredentialCipher {
+    /// Build a cipher from a base64 encoded 256-bit key
+    pub fn new(key: &str) -> Result<Self> {
+        let key = BASE64
+            .decode(key.trim())
+            .map_err(|e| DcaError::Generic(format!("Malformed encryption key: {e}")))?;
+        let key = UnboundKey::new(&AES_256_GCM, &key)
+            .map_err(|_| DcaError::Generic("Encryption key must be 256 bits long".to_string()))?;
+
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str, aad: &[u8]) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| DcaError::Generic("Failed to generate nonce".to_string()))?;
+
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(aad),
+                &mut sealed,
+            )
+            .map_err(|_| DcaError::Generic("Failed to encrypt secret".to_string()))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(sealed);
+        Ok(BASE64.encode(data))
+    }
+
+    pub fn decrypt(&self, ciphertext: &str, aad: &[u8]) -> Result<String> {
+        let failure = || DcaError::Generic("Failed to decrypt secret".to_string());
+
+        let mut data = BASE64.decode(ciphertext).map_err(|_| failure())?;
+        if data.len() < NONCE_LEN {
+            return Err(failure());
+        }
+
+        let (nonce, sealed) = data.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| failure())?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(aad), sealed)
+            .map_err(|_| failure())?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| failure())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+    #[test]
+    fn round_trips_secrets() {
+        let cipher = CredentialCipher::new(KEY).unwrap();
+
+        let sealed = cipher.encrypt("s3cr3t", b"conn-1").unwrap();
+        assert_ne!(sealed, cipher.encrypt("s3cr3t", b"conn-1").unwrap());
+        assert_eq!(cipher.decrypt(&sealed, b"conn-1").unwrap(), "s3cr3t");
+    }
+
+    #[test]
+    fn rejects_tampered_secrets() {
+        let cipher = CredentialCipher::new(KEY).unwrap();
+        let sealed = cipher.encrypt("s3cr3t", b"conn-1").unwrap();
+
+        assert!(cipher.decrypt(&sealed, b"conn-2").is_err());
+
+        let mut data = BASE64.decode(&sealed).unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt(&BASE64.encode(data), b"conn-1").is_err());
+
+        assert!(CredentialCipher::new("c2hvcnQ=").is_err());
+    }
+}
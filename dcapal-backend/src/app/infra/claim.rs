@@ -3,53 +3,80 @@ use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
-use jsonwebtoken::{Algorithm, DecodingKey, TokenData, Validation};
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use ring::digest;
+use serde_json::Value;
 use uuid::Uuid;
 
-use crate::{AppContext, error::DcaError};
+use crate::{AppContext, config, error::DcaError};
 
-const JWT_AUDIENCE_DOMAIN: &str = "authenticated";
-pub static DECODE_HEADER: Lazy<Validation> = Lazy::new(|| {
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.set_audience(&[JWT_AUDIENCE_DOMAIN.to_string()]);
-    validation
-});
-
-#[derive(Debug, Serialize, Deserialize)]
+/// Authenticated caller, mapped from the claims of its token. Only `sub` is
+/// required, since identity providers lay out user details differently (see
+/// [`config::ClaimNames`])
+#[derive(Debug, Clone, PartialEq)]
 pub struct Claims {
-    // issued at
-    pub iat: i64,
-    // expiration
-    pub exp: usize,
-    // subject
+    /// Id of the user: `sub` itself if a UUID, otherwise derived from it
     pub sub: Uuid,
-    // session id
-    pub session_id: Uuid,
-    // role user
-    pub role: String,
-    // audience
-    pub aud: String,
-    // user metadata
-    pub user_metadata: UserMetadataClaim,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UserMetadataClaim {
-    pub email: String,
+    pub session_id: Option<Uuid>,
+    pub role: Option<String>,
+    pub email: Option<String>,
     pub full_name: Option<String>,
 }
 
 impl Claims {
-    pub fn decode(
-        token: &str,
-        key: &DecodingKey,
-    ) -> Result<TokenData<Self>, jsonwebtoken::errors::Error> {
-        jsonwebtoken::decode::<Claims>(token, key, &DECODE_HEADER)
+    /// Map the verified `claims` of a token
+    pub fn from_token(claims: &Value, names: &config::ClaimNames) -> Result<Self, DcaError> {
+        let sub = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or_else(|| DcaError::Unauthorized("Missing token subject".to_string()))?;
+        let iss = claims.get("iss").and_then(Value::as_str);
+        let string = |path: &str| {
+            path.split('.')
+                .try_fold(claims, |claim, key| claim.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        Ok(Self {
+            sub: user_id(iss, sub),
+            session_id: string("session_id").and_then(|id| id.parse().ok()),
+            role: string(&names.role).or_else(|| names.default_role.clone()),
+            email: string(&names.email),
+            full_name: string(&names.full_name),
+        })
+    }
+
+    /// Claims of the bearer token of a request, without registering its user
+    pub async fn from_bearer(parts: &mut Parts, ctx: &AppContext) -> Result<Self, DcaError> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| DcaError::Unauthorized("Missing bearer token".to_string()))?;
+
+        ctx.services.jwt.decode(bearer.token()).await
     }
 }
 
+/// Id of the user of subject `sub`. Subjects other than UUIDs, as issued by
+/// most identity providers, get the UUID v5 of `{iss}#{sub}`
+fn user_id(iss: Option<&str>, sub: &str) -> Uuid {
+    if let Ok(id) = sub.parse() {
+        return id;
+    }
+
+    let name = match iss {
+        Some(iss) => format!("{iss}#{sub}"),
+        None => sub.to_string(),
+    };
+    let mut sha1 = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    sha1.update(Uuid::NAMESPACE_URL.as_bytes());
+    sha1.update(name.as_bytes());
+
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&sha1.finish().as_ref()[..16]);
+    uuid::Builder::from_sha1_bytes(bytes).into_uuid()
+}
+
 impl FromRequestParts<AppContext> for Claims {
     type Rejection = DcaError;
 
@@ -57,15 +84,12 @@ impl FromRequestParts<AppContext> for Claims {
         parts: &mut Parts,
         state: &AppContext,
     ) -> Result<Self, Self::Rejection> {
-        let TypedHeader(Authorization(bearer)) = parts
-            .extract::<TypedHeader<Authorization<Bearer>>>()
-            .await?;
-        let jwt_secret = state.config.app.auth.jwt_secret.clone();
-        let user_claims = Claims::decode(
-            bearer.token(),
-            &DecodingKey::from_secret(jwt_secret.as_ref()),
-        )?
-        .claims;
+        // Already authorized by the authorization layer
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(claims.clone());
+        }
+
+        let user_claims = Self::from_bearer(parts, state).await?;
         let _ = state
             .repos
             .user
@@ -74,3 +98,60 @@ impl FromRequestParts<AppContext> for Claims {
         Ok(user_claims)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn maps_claims_of_other_identity_providers() {
+        let names = config::ClaimNames {
+            role: "app.role".into(),
+            default_role: Some("user".into()),
+            email: "email".into(),
+            full_name: "name".into(),
+        };
+
+        let claims = json!({
+            "sub": "auth0|42",
+            "iss": "https://idp.example.com/",
+            "email": "user@example.com",
+            "app": { "role": "admin" },
+        });
+        let mapped = Claims::from_token(&claims, &names).unwrap();
+        assert_eq!(mapped.role.as_deref(), Some("admin"));
+        assert_eq!(mapped.email.as_deref(), Some("user@example.com"));
+        assert_eq!(mapped.full_name, None);
+        assert_eq!(mapped.session_id, None);
+        // Stable across tokens, distinct across issuers
+        assert_eq!(mapped.sub, Claims::from_token(&claims, &names).unwrap().sub);
+        assert_eq!(mapped.sub.get_version_num(), 5);
+        let other_issuer = json!({ "sub": "auth0|42", "iss": "https://other.com/" });
+        assert_ne!(
+            mapped.sub,
+            Claims::from_token(&other_issuer, &names).unwrap().sub
+        );
+
+        let no_role = json!({ "sub": "auth0|42" });
+        let mapped = Claims::from_token(&no_role, &names).unwrap();
+        assert_eq!(mapped.role.as_deref(), Some("user"));
+
+        assert!(Claims::from_token(&json!({ "email": "user@example.com" }), &names).is_err());
+    }
+
+    #[test]
+    fn derives_uuid_v5_of_non_uuid_subjects() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            user_id(Some("https://idp.example.com"), &id.to_string()),
+            id
+        );
+        // As of Python `uuid.uuid5(uuid.NAMESPACE_URL, "http://python.org/")`
+        assert_eq!(
+            user_id(None, "http://python.org/").to_string(),
+            "4c565f0d-3f5a-5890-b41b-20cf47701c5e"
+        );
+    }
+}
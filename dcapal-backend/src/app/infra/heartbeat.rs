@@ -0,0 +1,84 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::Utc;
+use parking_lot::RwLock;
+
+use crate::DateTime;
+
+/// Runs of each periodic worker, telling whether workers are still alive
+#[derive(Default)]
+pub struct WorkerHeartbeats {
+    workers: RwLock<BTreeMap<&'static str, Heartbeat>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    pub period: Duration,
+    pub started_at: DateTime,
+    pub last_run_at: Option<DateTime>,
+}
+
+impl Heartbeat {
+    /// Runs missed before a worker is considered stuck
+    const MISSED_RUNS: u32 = 2;
+
+    /// Whether the worker has not completed a run for longer than expected
+    pub fn is_stale(&self, now: DateTime) -> bool {
+        let Ok(grace) = chrono::Duration::from_std(self.period * Self::MISSED_RUNS) else {
+            return false;
+        };
+
+        now - self.last_run_at.unwrap_or(self.started_at) > grace
+    }
+}
+
+impl WorkerHeartbeats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track worker `name`, expected to run every `period`
+    pub fn register(&self, name: &'static str, period: Duration) {
+        self.workers.write().insert(
+            name,
+            Heartbeat {
+                period,
+                started_at: Utc::now(),
+                last_run_at: None,
+            },
+        );
+    }
+
+    /// Record a completed run of worker `name`
+    pub fn beat(&self, name: &'static str) {
+        if let Some(hb) = self.workers.write().get_mut(name) {
+            hb.last_run_at = Some(Utc::now());
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, Heartbeat> {
+        self.workers.read().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workers_missing_runs_are_stale() {
+        let now = Utc::now();
+        let mut hb = Heartbeat {
+            period: Duration::from_secs(60),
+            started_at: now - chrono::Duration::minutes(10),
+            last_run_at: None,
+        };
+        assert!(hb.is_stale(now));
+
+        hb.last_run_at = Some(now - chrono::Duration::seconds(90));
+        assert!(!hb.is_stale(now));
+
+        hb.last_run_at = Some(now - chrono::Duration::seconds(121));
+        assert!(hb.is_stale(now));
+    }
+}
@@ -1,3 +1,9 @@
+pub mod auth;
+pub mod cipher;
 pub mod claim;
+pub mod heartbeat;
+pub mod jwt;
+pub mod rate_limiter;
+pub mod scheduler;
 pub mod stats;
 pub mod utils;
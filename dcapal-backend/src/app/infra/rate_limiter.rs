@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::config::RateLimit;
+
+/// Token bucket rate limiter. The bucket holds up to `burst` tokens and is
+/// refilled at `requests_per_second`; each request consumes a token.
+pub struct TokenBucket {
+    capacity: f64,
+    refill_rate: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(config: &RateLimit) -> Self {
+        let capacity = config.burst.max(1) as f64;
+
+        Self {
+            capacity,
+            refill_rate: config.requests_per_second,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a token is available and consume it
+    pub async fn acquire(&self) {
+        loop {
+            match self.try_acquire(Instant::now()) {
+                None => return,
+                Some(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Consume a token if available at `now`. Otherwise, return how long to
+    /// wait for the next one
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock();
+
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        state.last_refill = now;
+
+        if state.tokens >= 1. {
+            state.tokens -= 1.;
+            return None;
+        }
+
+        if self.refill_rate <= 0. {
+            return Some(Duration::from_secs(1));
+        }
+
+        Some(Duration::from_secs_f64(
+            (1. - state.tokens) / self.refill_rate,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_refills_at_configured_rate() {
+        let bucket = TokenBucket::new(&RateLimit {
+            requests_per_second: 2.,
+            burst: 2,
+        });
+        let start = Instant::now();
+
+        assert!(bucket.try_acquire(start).is_none());
+        assert!(bucket.try_acquire(start).is_none());
+        assert_eq!(bucket.try_acquire(start), Some(Duration::from_millis(500)));
+
+        assert!(
+            bucket
+                .try_acquire(start + Duration::from_millis(500))
+                .is_none()
+        );
+        assert!(
+            bucket
+                .try_acquire(start + Duration::from_millis(500))
+                .is_some()
+        );
+
+        // Never more than `burst` tokens, however long the bucket sits idle
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.try_acquire(later).is_none());
+        assert!(bucket.try_acquire(later).is_none());
+        assert!(bucket.try_acquire(later).is_some());
+    }
+}
@@ -0,0 +1,223 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+
+use crate::{
+    DateTime,
+    error::{DcaError, Result},
+};
+
+/// Five fields cron expression (`minute hour day-of-month month
+/// day-of-week`), evaluated in UTC. Each field is either `*`, a value, a range
+/// `a-b` or a comma separated list of them, optionally stepped by `/n`.
+/// Sunday is day `0` (or `7`) of the week.
+///
+/// As in classic cron, if both day-of-month and day-of-week are restricted, a
+/// day matches if it matches either of them.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronExpr {
+    /// Days looked ahead for a matching time, covering leap days
+    const MAX_LOOKAHEAD_DAYS: u32 = 5 * 366;
+
+    /// First time matching the expression strictly after `after`, if any
+    pub fn next_after(&self, after: DateTime) -> Option<DateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        let mut date = start.date_naive();
+        for _ in 0..Self::MAX_LOOKAHEAD_DAYS {
+            if self.matches_day(date) {
+                let is_first = date == start.date_naive();
+                let first_hour = if is_first { start.hour() } else { 0 };
+                for hour in (first_hour..24).filter(|h| bit(self.hours, *h)) {
+                    let first_minute = if is_first && hour == first_hour {
+                        start.minute()
+                    } else {
+                        0
+                    };
+                    if let Some(minute) = (first_minute..60).find(|m| bit(self.minutes, *m)) {
+                        let time = date.and_hms_opt(hour, minute, 0)?;
+                        return Some(Utc.from_utc_datetime(&time));
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+impl FromStr for CronExpr {
+    type Err = DcaError;
+
+    fn from_str(expr: &str) -> Result<Self> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(DcaError::BadRequest(format!(
+                "Cron expression '{expr}' must have 5 fields"
+            )));
+        };
+
+        let parse = |field, min, max| {
+            parse_field(field, min, max).ok_or_else(|| {
+                DcaError::BadRequest(format!(
+                    "Invalid field '{field}' of cron expression '{expr}'"
+                ))
+            })
+        };
+
+        let mut weekdays_bits = parse(weekdays, 0, 7)?;
+        // Both 0 and 7 stand for Sunday
+        if bit(weekdays_bits, 7) {
+            weekdays_bits |= 1;
+        }
+
+        Ok(Self {
+            expr: fields.join(" "),
+            minutes: parse(minutes, 0, 59)?,
+            hours: parse(hours, 0, 23)?,
+            days: parse(days, 1, 31)?,
+            months: parse(months, 1, 12)?,
+            weekdays: weekdays_bits,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl fmt::Debug for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CronExpr({})", self.expr)
+    }
+}
+
+fn bit(bits: u64, n: u32) -> bool {
+    bits & (1 << n) != 0
+}
+
+/// Bitset of the values in `[min, max]` matched by `field`
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+                // A stepped value runs up to the end of the range (e.g. `5/15`)
+                None if step > 1 => (range.parse().ok()?, max),
+                None => {
+                    let value = range.parse().ok()?;
+                    (value, value)
+                }
+            },
+        };
+        if first < min || last > max || first > last {
+            return None;
+        }
+
+        for n in (first..=last).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+
+    Some(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime {
+        s.parse().unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> DateTime {
+        expr.parse::<CronExpr>()
+            .unwrap()
+            .next_after(at(after))
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_next_matching_time() {
+        assert_eq!(
+            next("*/15 * * * *", "2025-03-01T10:07:30Z"),
+            at("2025-03-01T10:15:00Z")
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2025-03-01T10:15:00Z"),
+            at("2025-03-01T10:30:00Z")
+        );
+        assert_eq!(
+            next("0 3 * * *", "2025-03-01T10:00:00Z"),
+            at("2025-03-02T03:00:00Z")
+        );
+        assert_eq!(
+            next("30 8-10 * * 1-5", "2025-03-01T09:00:00Z"),
+            at("2025-03-03T08:30:00Z")
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2025-03-01T00:00:00Z"),
+            at("2028-02-29T00:00:00Z")
+        );
+        assert_eq!(
+            next("0 12 1 * 0", "2025-03-01T13:00:00Z"),
+            at("2025-03-02T12:00:00Z")
+        );
+        assert_eq!(
+            next("0 12 * * 7", "2025-03-01T13:00:00Z"),
+            at("2025-03-02T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(expr.parse::<CronExpr>().is_err(), "{expr}");
+        }
+    }
+}
@@ -0,0 +1,509 @@
+pub mod cron;
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use metrics::{counter, histogram};
+use parking_lot::RwLock;
+use serde::Serialize;
+use tokio::{sync::Notify, task::JoinHandle};
+use tracing::{debug, error, info, warn};
+
+use self::cron::CronExpr;
+use crate::{
+    DateTime,
+    app::infra::{
+        heartbeat::WorkerHeartbeats,
+        stats,
+        utils::{StopToken, should_stop},
+    },
+    config,
+    error::{DcaError, Result},
+};
+
+/// Background job run by the [`Scheduler`]
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Name of the job, also the key of its schedule in `app.jobs` config
+    fn name(&self) -> &'static str;
+
+    /// Schedule of the job, unless overridden by config
+    fn schedule(&self) -> Schedule;
+
+    async fn run(&self, run: RunContext) -> Result<()>;
+}
+
+/// Context of a job run
+#[derive(Debug, Clone, Copy)]
+pub struct RunContext {
+    pub trigger: JobTrigger,
+    /// Expected time between two runs of the effective schedule
+    pub period: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Run on start, then once the given time elapsed since the end of the
+    /// previous run
+    Every(Duration),
+    /// Run at the times matched by a cron expression
+    Cron(CronExpr),
+}
+
+impl Schedule {
+    /// Schedule configured by `config`, if any
+    pub fn from_config(config: &config::Job) -> Result<Option<Self>> {
+        match (config.every_secs, &config.cron) {
+            (Some(_), Some(_)) => Err(DcaError::BadRequest(
+                "Job schedule must be either everySecs or cron".to_string(),
+            )),
+            (Some(0), None) => Err(DcaError::BadRequest(
+                "Job everySecs must be positive".to_string(),
+            )),
+            (Some(secs), None) => Ok(Some(Self::Every(Duration::from_secs(secs)))),
+            (None, Some(expr)) => Ok(Some(Self::Cron(expr.parse()?))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Time of the next run, if any, as of `now`. `is_first` tells whether
+    /// the job has not run yet since startup
+    fn next_run(&self, now: DateTime, is_first: bool) -> Option<DateTime> {
+        match self {
+            Self::Every(_) if is_first => Some(now),
+            Self::Every(period) => chrono::Duration::from_std(*period).ok().map(|p| now + p),
+            Self::Cron(expr) => expr.next_after(now),
+        }
+    }
+
+    /// Expected time between two runs
+    fn period(&self) -> Duration {
+        match self {
+            Self::Every(period) => *period,
+            Self::Cron(expr) => {
+                let now = Utc::now();
+                expr.next_after(now)
+                    .and_then(|first| Some((first, expr.next_after(first)?)))
+                    .and_then(|(first, second)| (second - first).to_std().ok())
+                    .unwrap_or(Duration::from_secs(24 * 60 * 60))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(period) => write!(f, "every {}s", period.as_secs()),
+            Self::Cron(expr) => write!(f, "cron {expr}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    Schedule,
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRun {
+    pub trigger: JobTrigger,
+    pub started_at: DateTime,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub schedule: String,
+    pub running: bool,
+    pub next_run_at: Option<DateTime>,
+    /// Most recent runs first
+    pub runs: Vec<JobRun>,
+}
+
+/// Scheduler of background jobs. Each job runs on its own task, so runs of a
+/// job never overlap: scheduled times elapsed while the job is running are
+/// skipped. Jobs can also be triggered on demand.
+pub struct Scheduler {
+    config: HashMap<String, config::Job>,
+    heartbeats: Arc<WorkerHeartbeats>,
+    jobs: RwLock<BTreeMap<&'static str, Arc<ScheduledJob>>>,
+}
+
+impl Scheduler {
+    pub fn new(config: HashMap<String, config::Job>, heartbeats: Arc<WorkerHeartbeats>) -> Self {
+        Self {
+            config,
+            heartbeats,
+            jobs: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Fail if the config schedules any job other than the `known` ones,
+    /// e.g. a misspelled job name that would otherwise be silently ignored
+    pub fn check_config(&self, known: &[&str]) -> Result<()> {
+        let mut unknown = self
+            .config
+            .keys()
+            .filter(|name| !known.contains(&name.as_str()))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        unknown.sort_unstable();
+        Err(DcaError::Config(::config::ConfigError::Message(format!(
+            "Unknown jobs in app.jobs config: {}",
+            unknown.join(", ")
+        ))))
+    }
+
+    /// Schedule `job` until `stop_token` is signaled. Returns `None` if the
+    /// job is disabled by config
+    pub fn spawn(
+        &self,
+        job: impl Job + 'static,
+        stop_token: StopToken,
+    ) -> Result<Option<JoinHandle<()>>> {
+        let name = job.name();
+        let config = self.config.get(name);
+        if config.is_some_and(|c| !c.enabled) {
+            info!("Job {name} is disabled");
+            return Ok(None);
+        }
+
+        let schedule = match config.map(Schedule::from_config).transpose()?.flatten() {
+            Some(schedule) => schedule,
+            None => job.schedule(),
+        };
+        let jitter = Duration::from_secs(config.map_or(0, |c| c.jitter_secs));
+        info!("Scheduling job {name} ({schedule})");
+
+        let scheduled = Arc::new(ScheduledJob {
+            job: Box::new(job),
+            schedule,
+            jitter,
+            running: AtomicBool::new(false),
+            triggered: Notify::new(),
+            state: RwLock::new(JobState::default()),
+        });
+        self.heartbeats.register(name, scheduled.schedule.period());
+        self.jobs.write().insert(name, scheduled.clone());
+
+        let heartbeats = self.heartbeats.clone();
+        Ok(Some(tokio::spawn(async move {
+            scheduled.run(heartbeats, stop_token).await;
+        })))
+    }
+
+    /// Run job `name` as soon as possible, regardless of its schedule
+    pub fn trigger(&self, name: &str) -> Result<()> {
+        let job = self
+            .jobs
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| DcaError::JobNotFound(name.to_string()))?;
+
+        if job.running.load(Ordering::Acquire) {
+            return Err(DcaError::BadRequest(format!(
+                "Job '{name}' is already running"
+            )));
+        }
+
+        info!("Triggering job {name}");
+        job.triggered.notify_one();
+        Ok(())
+    }
+
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs
+            .read()
+            .iter()
+            .map(|(name, job)| {
+                let state = job.state.read();
+                JobStatus {
+                    name,
+                    schedule: job.schedule.to_string(),
+                    running: job.running.load(Ordering::Acquire),
+                    next_run_at: state.next_run_at,
+                    runs: state.runs.iter().rev().cloned().collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+struct ScheduledJob {
+    job: Box<dyn Job>,
+    schedule: Schedule,
+    jitter: Duration,
+    running: AtomicBool,
+    triggered: Notify,
+    state: RwLock<JobState>,
+}
+
+#[derive(Default)]
+struct JobState {
+    next_run_at: Option<DateTime>,
+    runs: VecDeque<JobRun>,
+}
+
+impl ScheduledJob {
+    /// Runs kept in the history of each job
+    const HISTORY_LEN: usize = 20;
+
+    async fn run(&self, heartbeats: Arc<WorkerHeartbeats>, mut stop_token: StopToken) {
+        let name = self.job.name();
+
+        let mut is_first = true;
+        loop {
+            let now = Utc::now();
+            let next_run_at = self
+                .schedule
+                .next_run(now, is_first)
+                .map(|t| t + self.random_jitter());
+            self.state.write().next_run_at = next_run_at;
+            match next_run_at {
+                Some(t) => debug!("Next {name} run: {t}"),
+                None => warn!("Job {name} has no next run. Waiting for triggers"),
+            }
+
+            let wait = async {
+                match next_run_at.and_then(|t| (t - now).to_std().ok()) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None if next_run_at.is_some() => {}
+                    None => std::future::pending().await,
+                }
+            };
+
+            let trigger = tokio::select! {
+                _ = wait => JobTrigger::Schedule,
+                _ = self.triggered.notified() => JobTrigger::Manual,
+                _ = should_stop(&mut stop_token) => break,
+            };
+
+            self.execute(trigger).await;
+            heartbeats.beat(name);
+            is_first = false;
+        }
+    }
+
+    async fn execute(&self, trigger: JobTrigger) {
+        let name = self.job.name();
+
+        self.running.store(true, Ordering::Release);
+        let started_at = Utc::now();
+        let start = Instant::now();
+        let run = RunContext {
+            trigger,
+            period: self.schedule.period(),
+        };
+        let res = self.job.run(run).await;
+        let duration = start.elapsed();
+        self.running.store(false, Ordering::Release);
+
+        let outcome = if res.is_ok() { "success" } else { "failure" };
+        counter!(
+            stats::JOB_RUNS_TOTAL,
+            &[("job", name), ("outcome", outcome)]
+        )
+        .increment(1);
+        histogram!(stats::JOB_DURATION_SUMMARY, &[("job", name)]).record(duration.as_secs_f64());
+
+        let error = match res {
+            Ok(()) => {
+                debug!("Job {name} completed in {}ms", duration.as_millis());
+                None
+            }
+            Err(e) => {
+                error!("Job {name} failed: {e:?}");
+                Some(e.to_string())
+            }
+        };
+
+        let mut state = self.state.write();
+        state.runs.push_back(JobRun {
+            trigger,
+            started_at,
+            duration_ms: duration.as_millis() as u64,
+            error,
+        });
+        if state.runs.len() > Self::HISTORY_LEN {
+            state.runs.pop_front();
+        }
+    }
+
+    fn random_jitter(&self) -> chrono::Duration {
+        if self.jitter.is_zero() {
+            return chrono::Duration::zero();
+        }
+
+        let millis = rand::random_range(0..=self.jitter.as_millis() as i64);
+        chrono::Duration::milliseconds(millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::sync::watch;
+
+    use super::*;
+
+    struct CountingJob {
+        runs: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Job for CountingJob {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn schedule(&self) -> Schedule {
+            Schedule::Every(Duration::from_secs(60 * 60))
+        }
+
+        async fn run(&self, _: RunContext) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(DcaError::Generic("boom".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    fn scheduler(config: HashMap<String, config::Job>) -> Scheduler {
+        Scheduler::new(config, Arc::new(WorkerHeartbeats::new()))
+    }
+
+    async fn wait_for_runs(scheduler: &Scheduler, n: usize) -> JobStatus {
+        for _ in 0..100 {
+            let status = scheduler.jobs().remove(0);
+            if status.runs.len() >= n && !status.running {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job did not run {n} times");
+    }
+
+    #[test]
+    fn parses_configured_schedules() {
+        let job = |every_secs, cron: Option<&str>| config::Job {
+            every_secs,
+            cron: cron.map(str::to_string),
+            jitter_secs: 0,
+            enabled: true,
+        };
+
+        assert!(Schedule::from_config(&job(None, None)).unwrap().is_none());
+        assert!(matches!(
+            Schedule::from_config(&job(Some(30), None)).unwrap(),
+            Some(Schedule::Every(d)) if d == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            Schedule::from_config(&job(None, Some("0 3 * * *"))).unwrap(),
+            Some(Schedule::Cron(_))
+        ));
+        assert!(Schedule::from_config(&job(Some(30), Some("0 3 * * *"))).is_err());
+        assert!(Schedule::from_config(&job(Some(0), None)).is_err());
+        assert!(Schedule::from_config(&job(None, Some("0 3 * *"))).is_err());
+    }
+
+    #[tokio::test]
+    async fn runs_on_start_and_on_demand() {
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = scheduler(HashMap::new());
+
+        let handle = scheduler
+            .spawn(
+                CountingJob {
+                    runs: runs.clone(),
+                    fail: true,
+                },
+                stop_rx,
+            )
+            .unwrap()
+            .unwrap();
+
+        let status = wait_for_runs(&scheduler, 1).await;
+        assert_eq!(status.runs[0].trigger, JobTrigger::Schedule);
+        assert_eq!(status.runs[0].error.as_deref(), Some("boom"));
+        assert!(status.next_run_at.is_some());
+
+        scheduler.trigger("counting").unwrap();
+        let status = wait_for_runs(&scheduler, 2).await;
+        assert_eq!(status.runs[0].trigger, JobTrigger::Manual);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        assert!(matches!(
+            scheduler.trigger("unknown"),
+            Err(DcaError::JobNotFound(_))
+        ));
+
+        stop_tx.send(true).unwrap();
+        handle.await.unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_jobs_in_config() {
+        let job = config::Job {
+            every_secs: Some(60),
+            cron: None,
+            jitter_secs: 0,
+            enabled: true,
+        };
+        let config = HashMap::from([
+            ("counting".to_string(), job.clone()),
+            ("countnig".to_string(), job),
+        ]);
+
+        let scheduler = scheduler(config);
+        assert!(scheduler.check_config(&["counting", "other"]).is_err());
+        assert!(scheduler.check_config(&["counting", "countnig"]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn skips_disabled_jobs() {
+        let config = HashMap::from([(
+            "counting".to_string(),
+            config::Job {
+                every_secs: None,
+                cron: None,
+                jitter_secs: 0,
+                enabled: false,
+            },
+        )]);
+        let (_stop_tx, stop_rx) = watch::channel(false);
+        let scheduler = scheduler(config);
+
+        let job = CountingJob {
+            runs: Arc::new(AtomicUsize::new(0)),
+            fail: false,
+        };
+        assert!(scheduler.spawn(job, stop_rx).unwrap().is_none());
+        assert!(scheduler.jobs().is_empty());
+    }
+}
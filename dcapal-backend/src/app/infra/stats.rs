@@ -24,10 +24,17 @@ pub const VISITORS_TOTAL: &str = concatcp!(BASE, '_', "visitors_total");
 pub const REQUESTS_TOTAL: &str = concatcp!(BASE, '_', "requests_total");
 pub const LATENCY_SUMMARY: &str = concatcp!(BASE, '_', "latency_summary");
 pub const IMPORTED_PORTFOLIOS_TOTAL: &str = concatcp!(BASE, '_', "imported_portfolios_total");
+pub const JOB_RUNS_TOTAL: &str = concatcp!(BASE, '_', "job_runs_total");
+pub const JOB_DURATION_SUMMARY: &str = concatcp!(BASE, '_', "job_duration_summary");
+
+/// Greetings and health probes are not accounted in requests stats
+fn is_untracked(path: &str) -> bool {
+    path == "/" || path.starts_with("/health/")
+}
 
 pub async fn latency_stats(req: Request, next: Next) -> Response {
     let path = req.uri().path().to_string();
-    if path == "/" {
+    if is_untracked(&path) {
         return next.run(req).await;
     }
 
@@ -47,7 +54,7 @@ pub async fn requests_stats(
     next: Next,
 ) -> Result<Response> {
     let path = req.uri().path().to_string();
-    if path == "/" {
+    if is_untracked(&path) {
         return Ok(next.run(req).await);
     }
 
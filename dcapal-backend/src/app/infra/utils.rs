@@ -3,9 +3,12 @@ use std::{
     time::{Duration, Instant},
 };
 
-use futures::Future;
+use chrono::{NaiveDate, NaiveTime};
+use futures::{Future, Stream, StreamExt};
 use tokio::sync::{OnceCell, RwLock, watch};
 
+use crate::DateTime;
+
 pub struct ExpiringOnceCellValue<T> {
     pub value: T,
     pub is_expired: bool,
@@ -128,8 +131,24 @@ impl<T: Expiring> Expiring for ExpiringOption<T> {
     }
 }
 
+/// Midnight UTC of `date`, timestamping daily prices and candlesticks
+pub fn day_start(date: NaiveDate) -> DateTime {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
 pub type StopToken = watch::Receiver<bool>;
 
+/// End `stream` as soon as `stop_token` is signalled, e.g. to close
+/// long-lived responses on shutdown
+pub fn until_stopped<S: Stream>(
+    stream: S,
+    mut stop_token: StopToken,
+) -> impl Stream<Item = S::Item> {
+    stream.take_until(async move {
+        let _ = stop_token.wait_for(|stop| *stop).await;
+    })
+}
+
 pub async fn should_stop(stop_rx: &mut StopToken) {
     while stop_rx.changed().await.is_ok() {
         if *stop_rx.borrow_and_update() {
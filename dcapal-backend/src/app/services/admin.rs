@@ -0,0 +1,319 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::{
+    app::{
+        domain::{
+            entity::{Asset, AssetId, AssetKind, Market, MarketId, SymbolMapping},
+            market_data_utils::fetch_market_price,
+        },
+        services::{
+            calendar::TradingCalendars, market_data::MarketDataService, symbols::SymbolRegistry,
+        },
+    },
+    config::{self, PriceProvider},
+    error::{DcaError, Result},
+    ports::{
+        inbound::rest::response::AdminMarketResponse,
+        outbound::{adapter::PriceProviders, repository::market_data::MarketDataRepository},
+    },
+};
+
+/// Service backing the admin API, letting operators inspect and fix market
+/// data at runtime. Reads go straight to the repository, bypassing the caches
+/// of [`MarketDataService`].
+pub struct AdminService {
+    mkt_data: Arc<MarketDataService>,
+    mkt_data_repo: Arc<MarketDataRepository>,
+    symbols: Arc<SymbolRegistry>,
+    providers: Arc<PriceProviders>,
+    calendars: Arc<TradingCalendars>,
+    price_provider: PriceProvider,
+    fiat_provider: PriceProvider,
+}
+
+impl AdminService {
+    pub fn new(
+        mkt_data: Arc<MarketDataService>,
+        mkt_data_repo: Arc<MarketDataRepository>,
+        symbols: Arc<SymbolRegistry>,
+        providers: Arc<PriceProviders>,
+        calendars: Arc<TradingCalendars>,
+        config: &config::Providers,
+    ) -> Self {
+        Self {
+            mkt_data,
+            mkt_data_repo,
+            symbols,
+            providers,
+            calendars,
+            price_provider: config.price_provider,
+            fiat_provider: config.fiat_provider,
+        }
+    }
+
+    /// Stored assets of `kind`, or of any kind if `None`
+    pub async fn get_assets(&self, kind: Option<AssetKind>) -> Result<Vec<Asset>> {
+        let kinds = match kind {
+            Some(kind) => vec![kind],
+            None => vec![AssetKind::Crypto, AssetKind::Fiat, AssetKind::Equity],
+        };
+
+        let mut assets = vec![];
+        for kind in kinds {
+            assets.extend(self.mkt_data_repo.load_assets_by_type(kind).await?);
+        }
+        assets.sort_by(|a, b| a.id().cmp(b.id()));
+
+        Ok(assets)
+    }
+
+    pub async fn get_markets(&self) -> Result<Vec<AdminMarketResponse>> {
+        let mut markets = self
+            .mkt_data_repo
+            .load_markets()
+            .await?
+            .into_iter()
+            .map(|m| self.market_response(m))
+            .collect::<Vec<_>>();
+        markets.sort_by(|a, b| a.market.id.cmp(&b.market.id));
+
+        Ok(markets)
+    }
+
+    pub async fn get_market(&self, id: &MarketId) -> Result<AdminMarketResponse> {
+        let market = self.find_market(id).await?;
+        Ok(self.market_response(market))
+    }
+
+    /// Fetch the price of market `id` from its provider right away, whether
+    /// or not it is outdated
+    pub async fn refresh_price(&self, id: &MarketId) -> Result<AdminMarketResponse> {
+        let mut market = self.find_market(id).await?;
+
+        let provider = market.price_provider(self.price_provider, self.fiat_provider);
+        let price = fetch_market_price(&market, &self.providers, provider, &self.calendars)
+            .await
+            .ok_or_else(|| DcaError::PriceNotAvailableId(market.id.clone()))?;
+
+        market.set_price(price);
+        self.mkt_data_repo.update_mkt_price(&market).await?;
+        self.mkt_data.set_price(&market.id, price);
+        info!(
+            "Refreshed price of market '{}' from {provider}: {price:?}",
+            market.id
+        );
+
+        Ok(self.market_response(market))
+    }
+
+    /// Drop every cached asset, market and conversion rate
+    pub fn invalidate_caches(&self) {
+        self.mkt_data.invalidate_caches();
+        info!("Invalidated market data caches");
+    }
+
+    pub fn get_symbols(&self) -> Vec<SymbolMapping> {
+        let mut mappings = self.symbols.all();
+        mappings.sort_by(|a, b| a.id.cmp(&b.id));
+        mappings
+    }
+
+    /// Store `mapping` of asset `id`, replacing the existing one, if any
+    pub async fn put_symbol(&self, id: &AssetId, mut mapping: SymbolMapping) -> Result<()> {
+        let id = id.to_lowercase();
+        if mapping.id.to_lowercase() != id {
+            return Err(DcaError::BadRequest(format!(
+                "Symbol id '{}' does not match '{id}'",
+                mapping.id
+            )));
+        }
+
+        mapping.id = id;
+        if mapping.alias_of.as_ref() == Some(&mapping.id) {
+            return Err(DcaError::BadRequest(format!(
+                "Symbol '{}' cannot be an alias of itself",
+                mapping.id
+            )));
+        }
+
+        info!("Storing symbol mapping {mapping:?}");
+        self.symbols.upsert(mapping).await?;
+        // Aliases change the markets pricing conversions
+        self.mkt_data.invalidate_market_graph();
+
+        Ok(())
+    }
+
+    pub async fn delete_symbol(&self, id: &AssetId) -> Result<()> {
+        let id = id.to_lowercase();
+        if !self.symbols.delete(&id).await? {
+            return Err(DcaError::SymbolNotFound(id));
+        }
+
+        info!("Deleted symbol mapping '{id}'");
+        self.mkt_data.invalidate_market_graph();
+
+        Ok(())
+    }
+
+    async fn find_market(&self, id: &MarketId) -> Result<Market> {
+        let id = id.to_lowercase();
+        self.mkt_data_repo
+            .find_market(&id)
+            .await?
+            .ok_or(DcaError::MarketNotFound(id))
+    }
+
+    fn market_response(&self, market: Market) -> AdminMarketResponse {
+        let price_source = market.price_provider(self.price_provider, self.fiat_provider);
+        AdminMarketResponse {
+            price_outdated: market.price().is_none() || market.is_price_outdated(),
+            price_source,
+            market,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        app::domain::entity::{Crypto, Fiat, Price},
+        ports::outbound::{
+            adapter::{BinanceProvider, EcbProvider, IpApi, KrakenProvider, YahooProvider},
+            repository::symbol::SymbolRepository,
+        },
+    };
+
+    /// Service whose Redis storage is unreachable
+    fn service(db: DatabaseConnection) -> AdminService {
+        let config: config::Providers = serde_json::from_value(json!({
+            "priceProvider": "kraken",
+            "ipApiKey": "",
+        }))
+        .unwrap();
+
+        let redis = deadpool_redis::Config::from_url("redis://127.0.0.1:1/")
+            .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+            .unwrap();
+        let repo = Arc::new(MarketDataRepository::new(redis));
+        let symbols = Arc::new(SymbolRegistry::new(Arc::new(SymbolRepository {
+            db_conn: db,
+        })));
+        let http = reqwest::Client::new();
+        let providers = Arc::new(PriceProviders {
+            binance: Arc::new(BinanceProvider::new(http.clone(), &config, symbols.clone())),
+            cw: None,
+            ecb: Arc::new(EcbProvider::new(http.clone(), &config)),
+            kraken: Arc::new(KrakenProvider::new(http.clone(), &config, symbols.clone())),
+            yahoo: Arc::new(YahooProvider::new(
+                rquest::Client::new(),
+                &config,
+                symbols.clone(),
+            )),
+            ipapi: Arc::new(IpApi::new(http, &config)),
+        });
+        let mkt_data = Arc::new(MarketDataService::new(
+            repo.clone(),
+            symbols.clone(),
+            &config::MarketData::default(),
+        ));
+
+        AdminService::new(
+            mkt_data,
+            repo,
+            symbols,
+            providers,
+            Arc::new(TradingCalendars::load(None).unwrap()),
+            &config,
+        )
+    }
+
+    fn mapping(id: &str, alias_of: Option<&str>) -> SymbolMapping {
+        SymbolMapping {
+            id: id.to_string(),
+            alias_of: alias_of.map(str::to_string),
+            tickers: Default::default(),
+            isin: None,
+            cusip: None,
+        }
+    }
+
+    fn rows_affected(n: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected: n,
+        }
+    }
+
+    #[tokio::test]
+    async fn put_symbol_rejects_inconsistent_mappings() {
+        let admin = service(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+
+        let res = admin.put_symbol(&"eth".into(), mapping("btc", None)).await;
+        assert!(matches!(res, Err(DcaError::BadRequest(_))));
+
+        let res = admin
+            .put_symbol(&"ETH".into(), mapping("eth", Some("eth")))
+            .await;
+        assert!(matches!(res, Err(DcaError::BadRequest(_))));
+
+        assert!(admin.get_symbols().is_empty());
+    }
+
+    #[tokio::test]
+    async fn symbols_are_stored_and_deleted() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([
+                rows_affected(1),
+                rows_affected(1),
+                rows_affected(1),
+                rows_affected(0),
+            ])
+            .into_connection();
+        let admin = service(db);
+
+        admin
+            .put_symbol(&"ETH2".into(), mapping("eth2", Some("eth")))
+            .await
+            .unwrap();
+        admin
+            .put_symbol(&"btc".into(), mapping("BTC", None))
+            .await
+            .unwrap();
+        assert_eq!(
+            admin.get_symbols(),
+            vec![mapping("btc", None), mapping("eth2", Some("eth"))]
+        );
+
+        admin.delete_symbol(&"ETH2".into()).await.unwrap();
+        assert_eq!(admin.get_symbols(), vec![mapping("btc", None)]);
+
+        let res = admin.delete_symbol(&"eth2".into()).await;
+        assert!(matches!(res, Err(DcaError::SymbolNotFound(id)) if id == "eth2"));
+    }
+
+    #[tokio::test]
+    async fn markets_without_fresh_price_are_outdated() {
+        let admin = service(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+        let eur = Asset::Fiat(Fiat::new("eur".into(), "Euro".into()));
+        let btc = Asset::Crypto(Crypto::new_with_id("btc".into()));
+        let market = |price| Market::new("btceur".into(), btc.clone(), eur.clone(), price);
+
+        let res = admin.market_response(market(None));
+        assert!(res.price_outdated);
+        assert_eq!(res.price_source, PriceProvider::Kraken);
+
+        let stale = Price::new(1., Utc::now() - TimeDelta::days(7));
+        assert!(admin.market_response(market(Some(stale))).price_outdated);
+
+        let fresh = Price::new(1., Utc::now());
+        assert!(!admin.market_response(market(Some(fresh))).price_outdated);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, path::Path};
+
+use chrono::{Datelike, Utc};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    DateTime,
+    app::domain::{
+        calendar::TradingCalendar,
+        entity::{Market, Price},
+    },
+    error::{DcaError, Result},
+};
+
+static DEFAULT_CALENDARS_STR: &str = include_str!("../../../config/dcapal/calendars.yml");
+
+/// Trading calendars of known exchanges, deciding how long market prices stay
+/// fresh
+pub struct TradingCalendars {
+    calendars: HashMap<String, TradingCalendar>,
+    yahoo_unsuffixed: Option<String>,
+}
+
+impl TradingCalendars {
+    /// Load calendars from `path` (or the bundled ones, if `None`)
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let file = match path {
+            Some(p) => {
+                info!("Loading trading calendars from '{p}'");
+                let content = std::fs::read_to_string(Path::new(p))
+                    .map_err(|e| DcaError::InvalidCalendarsPath(p.to_string(), e))?;
+                parse_calendars_file(&content)?
+            }
+            None => parse_calendars_file(DEFAULT_CALENDARS_STR)?,
+        };
+
+        Self::from_file(file)
+    }
+
+    fn from_file(file: CalendarsFile) -> Result<Self> {
+        let year = Utc::now().year();
+        for c in &file.calendars {
+            if !c.holidays.is_empty() && !c.has_holidays_in(year) {
+                warn!(
+                    "Trading calendar '{}' lists no holidays in {year}: markets are considered open on them",
+                    c.id
+                );
+            }
+        }
+
+        if let Some(ref id) = file.yahoo_unsuffixed {
+            if !file.calendars.iter().any(|c| &c.id == id) {
+                return Err(DcaError::Config(::config::ConfigError::Message(format!(
+                    "Unknown calendar for unsuffixed Yahoo tickers: '{id}'"
+                ))));
+            }
+        }
+
+        Ok(Self {
+            calendars: file
+                .calendars
+                .into_iter()
+                .map(|c| (c.id.clone(), c))
+                .collect(),
+            yahoo_unsuffixed: file.yahoo_unsuffixed,
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TradingCalendar> {
+        self.calendars.get(id)
+    }
+
+    /// Calendar of the exchange a Yahoo Finance equity ticker is listed on.
+    /// Tickers without an exchange suffix are US listings
+    pub fn find_by_yahoo_equity(&self, ticker: &str) -> Option<&TradingCalendar> {
+        let Some(suffix) = ticker.rfind('.').map(|i| ticker[i..].to_uppercase()) else {
+            return self
+                .yahoo_unsuffixed
+                .as_ref()
+                .and_then(|id| self.calendars.get(id));
+        };
+
+        self.calendars
+            .values()
+            .find(|c| c.yahoo_suffixes.iter().any(|s| s.to_uppercase() == suffix))
+    }
+
+    /// Instant a price of `market` fetched at `ts` becomes outdated. While the
+    /// exchange is open, prices last 5 minutes at most; once closed, they last
+    /// until the next session opens
+    pub fn price_validity(&self, market: &Market, ts: DateTime) -> DateTime {
+        let default = Price::default_validity(ts);
+        let Some(calendar) = self.calendar_of(market) else {
+            return default;
+        };
+
+        if let Some(close) = calendar.next_close(ts) {
+            default.min(close)
+        } else {
+            calendar.next_open(ts).unwrap_or(default)
+        }
+    }
+
+    fn calendar_of(&self, market: &Market) -> Option<&TradingCalendar> {
+        let exchange = market.exchange.as_ref()?;
+        let calendar = self.calendars.get(exchange);
+        if calendar.is_none() {
+            warn!(mkt = market.id, "Unknown trading calendar '{exchange}'");
+        }
+
+        calendar
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CalendarsFile {
+    #[serde(default)]
+    yahoo_unsuffixed: Option<String>,
+    calendars: Vec<TradingCalendar>,
+}
+
+fn parse_calendars_file(content: &str) -> Result<CalendarsFile> {
+    let file = config::Config::builder()
+        .add_source(config::File::from_str(content, config::FileFormat::Yaml))
+        .build()?
+        .try_deserialize()?;
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_calendars_are_valid() {
+        let calendars = TradingCalendars::load(None).unwrap();
+
+        let xmil = calendars.find_by_yahoo_equity("ENI.MI").unwrap();
+        assert_eq!(xmil.id, "xmil");
+        assert_eq!(xmil.timezone, chrono_tz::Europe::Rome);
+
+        let xams = calendars.get("xams").unwrap();
+        assert!(!xams.holidays.is_empty());
+
+        assert_eq!(calendars.find_by_yahoo_equity("AAPL").unwrap().id, "xnys");
+        assert!(calendars.find_by_yahoo_equity("SAP.XX").is_none());
+
+        for c in calendars.calendars.values() {
+            for year in 2025..=2027 {
+                assert!(c.has_holidays_in(year), "{} lacks {year} holidays", c.id);
+            }
+        }
+    }
+
+    #[test]
+    fn unsuffixed_calendar_must_exist() {
+        let file = parse_calendars_file(
+            "yahooUnsuffixed: xnys\ncalendars:\n  - { id: xlon, timezone: Europe/London, sessions: [] }",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            TradingCalendars::from_file(file),
+            Err(DcaError::Config(_))
+        ));
+    }
+}
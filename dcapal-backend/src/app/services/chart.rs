@@ -0,0 +1,95 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::{debug, error};
+
+use crate::{
+    DateTime,
+    app::domain::chart::{Chart, ChartResolution, SymbolInfo},
+    error::{DcaError, Result},
+    ports::outbound::{adapter::YahooProvider, repository::CacheRepository},
+};
+
+/// Service serving symbol charts and search results from Yahoo Finance,
+/// cached to spare upstream requests on repeated loads
+pub struct ChartService {
+    yahoo: Arc<YahooProvider>,
+    cache: Arc<CacheRepository>,
+}
+
+impl ChartService {
+    const SEARCH_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn new(yahoo: Arc<YahooProvider>, cache: Arc<CacheRepository>) -> Self {
+        Self { yahoo, cache }
+    }
+
+    /// Get `symbol` chart in range [`start`, `end`], at `resolution` or the
+    /// finest one fitting the range
+    pub async fn get_chart(
+        &self,
+        symbol: &str,
+        start: DateTime,
+        end: DateTime,
+        resolution: Option<ChartResolution>,
+    ) -> Result<Chart> {
+        if start >= end {
+            return Err(DcaError::BadRequest(format!(
+                "Invalid chart range: [{start}, {end}]"
+            )));
+        }
+
+        let resolution = resolution.unwrap_or_else(|| ChartResolution::for_range(start, end));
+        if resolution.max_range().is_some_and(|max| end - start > max) {
+            return Err(DcaError::BadRequest(format!(
+                "Chart range too wide for {resolution} resolution"
+            )));
+        }
+
+        let symbol = symbol.to_uppercase();
+        let (start, end) = resolution.align(start, end);
+        let key = format!("chart:{symbol}:{resolution}:{start}:{end}");
+
+        match self.cache.get::<Chart>(&key).await {
+            Ok(Some(chart)) => {
+                debug!("Serving cached {resolution} chart of '{symbol}'");
+                return Ok(chart);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to read cached chart '{key}': {e:?}"),
+        }
+
+        let chart = self
+            .yahoo
+            .fetch_chart(&symbol, resolution, start, end)
+            .await?
+            .ok_or(DcaError::SymbolNotFound(symbol))?;
+
+        if let Err(e) = self.cache.set(&key, &chart, resolution.cache_ttl()).await {
+            error!("Failed to cache chart '{key}': {e:?}");
+        }
+
+        Ok(chart)
+    }
+
+    /// Search symbols matching `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<SymbolInfo>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let key = format!("search:{query}");
+        match self.cache.get::<Vec<SymbolInfo>>(&key).await {
+            Ok(Some(results)) => return Ok(results),
+            Ok(None) => {}
+            Err(e) => error!("Failed to read cached search '{key}': {e:?}"),
+        }
+
+        let results = self.yahoo.search(&query).await?;
+        if let Err(e) = self.cache.set(&key, &results, Self::SEARCH_TTL).await {
+            error!("Failed to cache search '{key}': {e:?}");
+        }
+
+        Ok(results)
+    }
+}
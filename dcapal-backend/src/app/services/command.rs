@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use jsonschema::Validator;
 
 use crate::{
@@ -6,6 +8,7 @@ use crate::{
     ports::outbound::repository::market_data::MarketDataRepository,
 };
 
+#[derive(Clone)]
 pub struct ConversionRateQuery {
     pub base: Asset,
     pub quote: Asset,
@@ -38,6 +41,86 @@ impl ConversionRateQuery {
     }
 }
 
+/// Conversion rates of many assets into one or more quote currencies. Assets
+/// are looked up in a single round trip; unknown assets are reported per
+/// entry rather than failing the whole query.
+pub struct BatchConversionRateQuery {
+    pub entries: Vec<(AssetId, AssetId, Result<ConversionRateQuery>)>,
+}
+
+impl BatchConversionRateQuery {
+    const MAX_ASSETS: usize = 100;
+    const MAX_QUOTES: usize = 10;
+
+    pub async fn try_new(
+        bases: &[AssetId],
+        quotes: &[AssetId],
+        repo: &MarketDataRepository,
+    ) -> Result<Self> {
+        Self::check_limits(bases, quotes)?;
+
+        let ids: Vec<&AssetId> = bases.iter().chain(quotes).collect();
+        let assets: HashMap<&AssetId, Asset> = ids
+            .iter()
+            .copied()
+            .zip(repo.find_assets(&ids).await?)
+            .filter_map(|(id, a)| a.map(|a| (id, a)))
+            .collect();
+
+        Ok(Self::from_assets(bases, quotes, &assets))
+    }
+
+    fn check_limits(bases: &[AssetId], quotes: &[AssetId]) -> Result<()> {
+        if bases.is_empty() || quotes.is_empty() {
+            return Err(DcaError::BadRequest(
+                "At least one asset and one quote are required".to_string(),
+            ));
+        }
+        if bases.len() > Self::MAX_ASSETS || quotes.len() > Self::MAX_QUOTES {
+            return Err(DcaError::BadRequest(format!(
+                "Too many assets or quotes (max {} assets, {} quotes)",
+                Self::MAX_ASSETS,
+                Self::MAX_QUOTES
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Pair every base with every quote, reporting pairs with an asset
+    /// missing from `assets`
+    fn from_assets(
+        bases: &[AssetId],
+        quotes: &[AssetId],
+        assets: &HashMap<&AssetId, Asset>,
+    ) -> Self {
+        let entries = bases
+            .iter()
+            .flat_map(|base| quotes.iter().map(move |quote| (base, quote)))
+            .map(|(base, quote)| {
+                let query = match (assets.get(base), assets.get(quote)) {
+                    (None, _) => Err(DcaError::BadRequest(format!(
+                        "Unknown base asset: {}",
+                        base
+                    ))),
+                    (_, None) => Err(DcaError::BadRequest(format!(
+                        "Unknown quote asset: {}",
+                        quote
+                    ))),
+                    (Some(b), Some(q)) => Ok(ConversionRateQuery {
+                        base: b.clone(),
+                        quote: q.clone(),
+                    }),
+                };
+
+                (base.clone(), quote.clone(), query)
+            })
+            .collect();
+
+        Self { entries }
+    }
+}
+
 pub struct ImportPortfolioCmd {
     pub pfolio: serde_json::Value,
 }
@@ -53,3 +136,79 @@ impl ImportPortfolioCmd {
         Ok(Self { pfolio: payload })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::entity::{Crypto, Fiat};
+
+    fn ids(prefix: &str, n: usize) -> Vec<AssetId> {
+        (0..n).map(|i| format!("{prefix}{i}")).collect()
+    }
+
+    #[test]
+    fn batch_query_enforces_limits() {
+        let check = BatchConversionRateQuery::check_limits;
+
+        assert!(check(&ids("a", 100), &ids("q", 10)).is_ok());
+        assert!(matches!(
+            check(&ids("a", 101), &ids("q", 1)),
+            Err(DcaError::BadRequest(_))
+        ));
+        assert!(matches!(
+            check(&ids("a", 1), &ids("q", 11)),
+            Err(DcaError::BadRequest(_))
+        ));
+        assert!(matches!(
+            check(&[], &ids("q", 1)),
+            Err(DcaError::BadRequest(_))
+        ));
+        assert!(matches!(
+            check(&ids("a", 1), &[]),
+            Err(DcaError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn batch_query_reports_unknown_assets_per_pair() {
+        let (btc, xyz, eur, abc) = (
+            "btc".to_string(),
+            "xyz".to_string(),
+            "eur".to_string(),
+            "abc".to_string(),
+        );
+        let assets = HashMap::from([
+            (&btc, Asset::Crypto(Crypto::new_with_id(btc.clone()))),
+            (
+                &eur,
+                Asset::Fiat(Fiat::new(eur.clone(), "Euro".to_string())),
+            ),
+        ]);
+
+        let query = BatchConversionRateQuery::from_assets(
+            &[btc.clone(), xyz.clone()],
+            &[eur.clone(), abc.clone()],
+            &assets,
+        );
+        assert_eq!(query.entries.len(), 4);
+
+        let result = |base: &str, quote: &str| {
+            query
+                .entries
+                .iter()
+                .find(|(b, q, _)| b == base && q == quote)
+                .map(|(_, _, r)| r)
+                .unwrap()
+        };
+        assert!(result("btc", "eur").is_ok());
+        assert!(
+            matches!(result("btc", "abc"), Err(DcaError::BadRequest(e)) if e.contains("quote asset: abc"))
+        );
+        assert!(
+            matches!(result("xyz", "eur"), Err(DcaError::BadRequest(e)) if e.contains("base asset: xyz"))
+        );
+        assert!(
+            matches!(result("xyz", "abc"), Err(DcaError::BadRequest(e)) if e.contains("base asset: xyz"))
+        );
+    }
+}
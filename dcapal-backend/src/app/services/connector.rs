@@ -0,0 +1,368 @@
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use rust_decimal::Decimal;
+use sea_orm::ActiveValue::Set;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        domain::{
+            connector::{ApiCredentials, Exchange},
+            db::exchange_connection,
+            entity::{Asset, AssetId},
+        },
+        infra::cipher::CredentialCipher,
+        services::symbols::SymbolRegistry,
+    },
+    error::{DcaError, Result},
+    ports::{
+        inbound::rest::{request::ConnectionRequest, response::ConnectionResponse},
+        outbound::{
+            adapter::KrakenAccountConnector,
+            repository::{
+                connection::ConnectionRepository,
+                market_data::MarketDataRepository,
+                portfolio::{ExchangeHolding, PortfolioRepository},
+            },
+        },
+    },
+};
+
+/// Service keeping portfolio quantities in line with the balances of linked
+/// exchange accounts, read through user-supplied read-only API keys. Keys are
+/// encrypted at rest, bound to the id of their connection.
+pub struct ConnectorService {
+    repo: Arc<ConnectionRepository>,
+    portfolio_repo: Arc<PortfolioRepository>,
+    mkt_data_repo: Arc<MarketDataRepository>,
+    symbols: Arc<SymbolRegistry>,
+    cipher: CredentialCipher,
+    kraken: Arc<KrakenAccountConnector>,
+}
+
+impl ConnectorService {
+    pub fn new(
+        repo: Arc<ConnectionRepository>,
+        portfolio_repo: Arc<PortfolioRepository>,
+        mkt_data_repo: Arc<MarketDataRepository>,
+        symbols: Arc<SymbolRegistry>,
+        cipher: CredentialCipher,
+        kraken: Arc<KrakenAccountConnector>,
+    ) -> Self {
+        Self {
+            repo,
+            portfolio_repo,
+            mkt_data_repo,
+            symbols,
+            cipher,
+            kraken,
+        }
+    }
+
+    /// Link an exchange account to `user_id` portfolio and sync its holdings.
+    /// Credentials are only stored if the exchange accepts them
+    pub async fn link(&self, user_id: Uuid, req: ConnectionRequest) -> Result<ConnectionResponse> {
+        if req.api_key.trim().is_empty() || req.api_secret.trim().is_empty() {
+            return Err(DcaError::BadRequest(
+                "API key and secret are required".to_string(),
+            ));
+        }
+
+        self.portfolio_repo
+            .find_user_portfolio(user_id, req.portfolio_id)
+            .await?
+            .ok_or(DcaError::PortfolioNotFound(req.portfolio_id))?;
+
+        let exchange = req.exchange.to_string();
+        let linked = self.repo.find_by_user(user_id).await?;
+        if linked
+            .iter()
+            .any(|c| c.portfolio_id == req.portfolio_id && c.exchange == exchange)
+        {
+            return Err(DcaError::BadRequest(format!(
+                "Portfolio '{}' is already linked to a {exchange} account",
+                req.portfolio_id
+            )));
+        }
+
+        let credentials = ApiCredentials {
+            api_key: req.api_key.trim().to_string(),
+            api_secret: req.api_secret.trim().to_string(),
+        };
+        let holdings = self
+            .fetch_holdings(req.exchange, &credentials)
+            .await
+            .map_err(|e| {
+                DcaError::BadRequest(format!("Failed to fetch {exchange} balances: {e}"))
+            })?;
+
+        let id = Uuid::new_v4();
+        let connection = self
+            .repo
+            .insert(exchange_connection::ActiveModel {
+                id: Set(id),
+                user_id: Set(user_id),
+                portfolio_id: Set(req.portfolio_id),
+                exchange: Set(exchange),
+                api_key: Set(self.cipher.encrypt(&credentials.api_key, id.as_bytes())?),
+                api_secret: Set(self
+                    .cipher
+                    .encrypt(&credentials.api_secret, id.as_bytes())?),
+                ..Default::default()
+            })
+            .await?;
+        info!(
+            "Linked {} account to portfolio '{}' of user_id: {user_id}",
+            connection.exchange, connection.portfolio_id
+        );
+
+        let outcome = self
+            .portfolio_repo
+            .sync_holdings(connection.user_id, connection.portfolio_id, holdings)
+            .await;
+        Ok(self.record_sync(connection, outcome).await?.into())
+    }
+
+    pub async fn get_connections(&self, user_id: Uuid) -> Result<Vec<ConnectionResponse>> {
+        let connections = self.repo.find_by_user(user_id).await?;
+        Ok(connections
+            .into_iter()
+            .map(ConnectionResponse::from)
+            .collect())
+    }
+
+    /// Unlink an exchange account, erasing its credentials. Portfolio
+    /// holdings are left as last synced
+    pub async fn unlink(&self, user_id: Uuid, connection_id: Uuid) -> Result<()> {
+        if !self.repo.delete(user_id, connection_id).await? {
+            return Err(DcaError::ConnectionNotFound(connection_id));
+        }
+
+        info!("Unlinked exchange connection '{connection_id}' of user_id: {user_id}");
+        Ok(())
+    }
+
+    /// Sync `user_id` connection right away. Sync failures are reported by
+    /// the returned connection `lastError`
+    pub async fn sync_connection(
+        &self,
+        user_id: Uuid,
+        connection_id: Uuid,
+    ) -> Result<ConnectionResponse> {
+        let connection = self
+            .repo
+            .find(user_id, connection_id)
+            .await?
+            .ok_or(DcaError::ConnectionNotFound(connection_id))?;
+
+        Ok(self.sync(connection).await?.into())
+    }
+
+    /// Sync every connection linked to a portfolio not deleted. Returns the
+    /// number of connections synced successfully, out of the total
+    pub async fn sync_all(&self) -> Result<(usize, usize)> {
+        let connections = self.repo.find_active().await?;
+        let total = connections.len();
+
+        let mut synced = 0;
+        for connection in connections {
+            let id = connection.id;
+            match self.sync(connection).await {
+                Ok(c) if c.last_error.is_none() => synced += 1,
+                Ok(_) => {}
+                Err(e) => error!("Failed to record sync of exchange connection '{id}': {e:?}"),
+            }
+        }
+
+        Ok((synced, total))
+    }
+
+    async fn sync(
+        &self,
+        connection: exchange_connection::Model,
+    ) -> Result<exchange_connection::Model> {
+        let outcome = self.sync_holdings(&connection).await;
+        self.record_sync(connection, outcome).await
+    }
+
+    async fn sync_holdings(&self, connection: &exchange_connection::Model) -> Result<u64> {
+        let (exchange, credentials) = self.load_exchange(connection)?;
+        let holdings = self.fetch_holdings(exchange, &credentials).await?;
+
+        self.portfolio_repo
+            .sync_holdings(connection.user_id, connection.portfolio_id, holdings)
+            .await
+    }
+
+    async fn record_sync(
+        &self,
+        connection: exchange_connection::Model,
+        outcome: Result<u64>,
+    ) -> Result<exchange_connection::Model> {
+        let error = match outcome {
+            Ok(updated) => {
+                debug!(
+                    "Synced {updated} holdings of portfolio '{}' from {}",
+                    connection.portfolio_id, connection.exchange
+                );
+                None
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to sync exchange connection '{}': {e:?}",
+                    connection.id
+                );
+                Some(e.to_string())
+            }
+        };
+
+        self.repo.record_sync(connection, error).await
+    }
+
+    fn load_exchange(
+        &self,
+        connection: &exchange_connection::Model,
+    ) -> Result<(Exchange, ApiCredentials)> {
+        let exchange = connection.exchange.parse::<Exchange>().map_err(|_| {
+            DcaError::Generic(format!("Unsupported exchange '{}'", connection.exchange))
+        })?;
+
+        let aad = connection.id.as_bytes();
+        let credentials = ApiCredentials {
+            api_key: self.cipher.decrypt(&connection.api_key, aad)?,
+            api_secret: self.cipher.decrypt(&connection.api_secret, aad)?,
+        };
+
+        Ok((exchange, credentials))
+    }
+
+    /// Balances of the exchange account, mapped to portfolio assets. Balances
+    /// of assets without any market are skipped
+    async fn fetch_holdings(
+        &self,
+        exchange: Exchange,
+        credentials: &ApiCredentials,
+    ) -> Result<Vec<ExchangeHolding>> {
+        let balances = match exchange {
+            Exchange::Kraken => self.kraken.fetch_balances(credentials).await?,
+        };
+
+        let repo = &self.mkt_data_repo;
+        map_holdings(&self.symbols, exchange, balances, |id| async move {
+            repo.find_asset(&id).await
+        })
+        .await
+    }
+}
+
+/// Map `exchange` balances, keyed by ticker, to portfolio assets. Balances of
+/// tickers of the same asset are summed, while balances of assets unknown to
+/// `find_asset` are skipped
+async fn map_holdings<F, Fut>(
+    symbols: &SymbolRegistry,
+    exchange: Exchange,
+    balances: HashMap<String, Decimal>,
+    mut find_asset: F,
+) -> Result<Vec<ExchangeHolding>>
+where
+    F: FnMut(AssetId) -> Fut,
+    Fut: Future<Output = Result<Option<Asset>>>,
+{
+    let mut by_asset = HashMap::<AssetId, Decimal>::new();
+    for (ticker, qty) in balances {
+        let id = symbols.from_provider(&ticker, exchange.ticker_provider());
+        *by_asset.entry(symbols.normalize(&id)).or_default() += qty;
+    }
+
+    let mut holdings = Vec::with_capacity(by_asset.len());
+    for (id, quantity) in by_asset {
+        let (name, asset_class) = match find_asset(id.clone()).await? {
+            Some(Asset::Crypto(c)) => (c.symbol, PortfolioRepository::CRYPTO_CLASS),
+            Some(Asset::Fiat(f)) => (f.symbol, PortfolioRepository::CURRENCY_CLASS),
+            _ => {
+                debug!("Skipping {exchange} balance of unknown asset '{id}'");
+                continue;
+            }
+        };
+
+        holdings.push(ExchangeHolding {
+            symbol: id,
+            name,
+            asset_class: asset_class.to_string(),
+            quantity,
+        });
+    }
+
+    Ok(holdings)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use super::*;
+    use crate::{
+        app::domain::entity::{Crypto, Fiat},
+        ports::outbound::repository::symbol::SymbolRepository,
+    };
+
+    #[tokio::test]
+    async fn holdings_map_tickers_and_skip_unknown_assets() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let symbols = SymbolRegistry::new(Arc::new(SymbolRepository { db_conn: db }));
+        symbols.import(None).await.unwrap();
+
+        let known = HashMap::from([
+            (
+                "btc".to_string(),
+                Asset::Crypto(Crypto::new_with_id("btc".into())),
+            ),
+            (
+                "eth".to_string(),
+                Asset::Crypto(Crypto::new_with_id("eth".into())),
+            ),
+            (
+                "eur".to_string(),
+                Asset::Fiat(Fiat::new("eur".into(), "Euro".into())),
+            ),
+        ]);
+        let balances = HashMap::from([
+            ("XBT".to_string(), Decimal::new(5, 1)),
+            ("ETH".to_string(), Decimal::from(2)),
+            // Staked ether is priced as ether
+            ("ETH2.S".to_string(), Decimal::new(15, 1)),
+            ("EUR".to_string(), Decimal::from(100)),
+            ("FOO".to_string(), Decimal::from(7)),
+        ]);
+
+        let mut holdings = map_holdings(&symbols, Exchange::Kraken, balances, |id| {
+            let asset = known.get(&id).cloned();
+            async move { Ok(asset) }
+        })
+        .await
+        .unwrap();
+        holdings.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        let summary = holdings
+            .iter()
+            .map(|h| (h.symbol.as_str(), h.asset_class.as_str(), h.quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("btc", PortfolioRepository::CRYPTO_CLASS, Decimal::new(5, 1)),
+                (
+                    "eth",
+                    PortfolioRepository::CRYPTO_CLASS,
+                    Decimal::new(35, 1)
+                ),
+                (
+                    "eur",
+                    PortfolioRepository::CURRENCY_CLASS,
+                    Decimal::from(100)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use tracing::info;
+
+use crate::{
+    app::domain::{
+        corporate_action::{CorporateAction, CorporateActionKind},
+        entity::Equity,
+    },
+    error::{DcaError, Result},
+    ports::outbound::{
+        adapter::YahooProvider,
+        repository::{market_data::MarketDataRepository, portfolio::PortfolioRepository},
+    },
+};
+
+/// Service keeping equity holdings consistent with splits and dividends.
+/// Splits rescale portfolio quantities and stored price history, dividends are
+/// recorded as portfolio cash flows available for reinvestment.
+pub struct CorporateActionService {
+    yahoo: Arc<YahooProvider>,
+    mkt_data_repo: Arc<MarketDataRepository>,
+    portfolio_repo: Arc<PortfolioRepository>,
+}
+
+impl CorporateActionService {
+    pub fn new(
+        yahoo: Arc<YahooProvider>,
+        mkt_data_repo: Arc<MarketDataRepository>,
+        portfolio_repo: Arc<PortfolioRepository>,
+    ) -> Self {
+        Self {
+            yahoo,
+            mkt_data_repo,
+            portfolio_repo,
+        }
+    }
+
+    /// Fetch `symbol` corporate actions since `start` and apply them to the
+    /// portfolios holding it. Returns the number of new actions
+    pub async fn sync(&self, symbol: &str, start: NaiveDate) -> Result<usize> {
+        let today = Utc::now().date_naive();
+        let fetched = self
+            .yahoo
+            .fetch_corporate_actions(symbol, start, today)
+            .await?;
+
+        let known = self
+            .mkt_data_repo
+            .load_corporate_actions(symbol)
+            .await?
+            .iter()
+            .map(CorporateAction::key)
+            .collect::<HashSet<_>>();
+
+        let mut new_actions = 0;
+        for action in fetched.iter().filter(|a| a.ex_date <= today) {
+            if known.contains(&action.key()) {
+                continue;
+            }
+
+            // Store the action only once applied to the price history, so
+            // that failed adjustments are retried by the next sync
+            info!("New corporate action of '{}': {action:?}", action.symbol);
+            self.adjust_price_history(action).await?;
+            if self.mkt_data_repo.store_corporate_action(action).await? {
+                new_actions += 1;
+            }
+        }
+
+        // Applying is idempotent, so that holdings synced after the ex-date
+        // with older quantities get adjusted as well
+        let actions = self.mkt_data_repo.load_corporate_actions(symbol).await?;
+        for (i, action) in actions.iter().enumerate() {
+            if action.ex_date >= start {
+                self.apply(action, &actions[i + 1..]).await?;
+            }
+        }
+
+        Ok(new_actions)
+    }
+
+    /// Apply `action` to the holdings of its symbol, given the `later`
+    /// actions sorted by ex-date
+    async fn apply(&self, action: &CorporateAction, later: &[CorporateAction]) -> Result<()> {
+        match &action.kind {
+            CorporateActionKind::Split { .. } => {
+                let Some(ratio) = action.split_ratio() else {
+                    return Ok(());
+                };
+
+                let adjusted = self
+                    .portfolio_repo
+                    .apply_split(&action.symbol, action.ex_date, to_decimal(ratio)?)
+                    .await?;
+                if adjusted > 0 {
+                    info!(
+                        "Adjusted {adjusted} '{}' holdings for {} split",
+                        action.symbol, action.ex_date
+                    );
+                }
+            }
+            CorporateActionKind::Dividend { amount, currency } => {
+                let later_splits = later
+                    .iter()
+                    .filter_map(|a| a.split_ratio().map(|r| (a.ex_date, r)))
+                    .map(|(date, r)| to_decimal(r).map(|r| (date, r)))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let recorded = self
+                    .portfolio_repo
+                    .record_dividend(
+                        &action.symbol,
+                        action.ex_date,
+                        to_decimal(*amount)?,
+                        currency.as_deref(),
+                        &later_splits,
+                    )
+                    .await?;
+                if recorded > 0 {
+                    info!(
+                        "Recorded {recorded} '{}' dividends of {}",
+                        action.symbol, action.ex_date
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Express the daily prices stored before a split in post-split shares.
+    /// Prices fetched after the ex-date are already adjusted by the provider
+    async fn adjust_price_history(&self, action: &CorporateAction) -> Result<()> {
+        let Some(ratio) = action.split_ratio() else {
+            return Ok(());
+        };
+
+        let equity = Equity::new_with_ticker(&action.symbol);
+        let markets = self.mkt_data_repo.load_markets().await?;
+        for m in markets.iter().filter(|m| m.base.id() == &equity.id) {
+            let n = self
+                .mkt_data_repo
+                .adjust_daily_prices(&m.id, action.ex_date, 1. / ratio)
+                .await?;
+            info!("Adjusted {n} daily prices of market '{}'", m.id);
+        }
+
+        Ok(())
+    }
+}
+
+fn to_decimal(v: f64) -> Result<Decimal> {
+    Decimal::try_from(v).map_err(|e| DcaError::Generic(format!("Invalid decimal {v}: {e}")))
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use parking_lot::RwLock;
+use sea_orm::sqlx::{self, PgPool};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    DateTime,
+    app::infra::{heartbeat::WorkerHeartbeats, utils::Expiring},
+    error::{DcaError, Result},
+    ports::outbound::{adapter::PriceProviders, repository::market_data::MarketDataRepository},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    /// Serving requests, possibly with outdated data
+    Degraded,
+    /// Not able to serve requests
+    Down,
+}
+
+/// Readiness of the server and the state of each of its dependencies
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checked_at: DateTime,
+    pub redis: DependencyCheck,
+    pub postgres: DependencyCheck,
+    /// Circuit breaker of each price provider
+    pub providers: BTreeMap<&'static str, HealthStatus>,
+    pub workers: BTreeMap<&'static str, WorkerCheck>,
+    pub market_data: MarketDataCheck,
+}
+
+impl HealthReport {
+    /// Drop the dependency errors, which may disclose infrastructure details
+    pub fn redacted(mut self) -> Self {
+        self.redis.error = None;
+        self.postgres.error = None;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyCheck {
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerCheck {
+    pub status: HealthStatus,
+    pub period_secs: u64,
+    pub last_run_at: Option<DateTime>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketDataCheck {
+    pub status: HealthStatus,
+    /// Active markets
+    pub markets: usize,
+    /// Active markets whose price is missing or outdated
+    pub outdated: usize,
+    pub last_price_at: Option<DateTime>,
+}
+
+/// Service checking the dependencies needed to serve requests: storage
+/// pools, price providers, workers and market data freshness
+pub struct HealthService {
+    redis: deadpool_redis::Pool,
+    postgres: PgPool,
+    providers: Arc<PriceProviders>,
+    heartbeats: Arc<WorkerHeartbeats>,
+    mkt_data_repo: Arc<MarketDataRepository>,
+    market_data: RwLock<Option<(Instant, MarketDataCheck)>>,
+}
+
+impl HealthService {
+    const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+    /// Market data freshness is costly to compute, so it is checked at most
+    /// once per period
+    const MARKET_DATA_TTL: Duration = Duration::from_secs(60);
+
+    pub fn new(
+        redis: deadpool_redis::Pool,
+        postgres: PgPool,
+        providers: Arc<PriceProviders>,
+        heartbeats: Arc<WorkerHeartbeats>,
+        mkt_data_repo: Arc<MarketDataRepository>,
+    ) -> Self {
+        Self {
+            redis,
+            postgres,
+            providers,
+            heartbeats,
+            mkt_data_repo,
+            market_data: RwLock::new(None),
+        }
+    }
+
+    pub async fn check(&self) -> HealthReport {
+        let (redis, postgres) = tokio::join!(self.check_redis(), self.check_postgres());
+
+        let providers = self
+            .providers
+            .circuit_breakers()
+            .into_iter()
+            .map(|(name, available)| {
+                let status = if available {
+                    HealthStatus::Up
+                } else {
+                    HealthStatus::Down
+                };
+                (name, status)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let now = Utc::now();
+        let workers = self
+            .heartbeats
+            .snapshot()
+            .into_iter()
+            .map(|(name, hb)| {
+                let status = if hb.is_stale(now) {
+                    HealthStatus::Down
+                } else {
+                    HealthStatus::Up
+                };
+                let check = WorkerCheck {
+                    status,
+                    period_secs: hb.period.as_secs(),
+                    last_run_at: hb.last_run_at,
+                };
+                (name, check)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let market_data = self.check_market_data().await;
+
+        let degradable = providers
+            .values()
+            .copied()
+            .chain(workers.values().map(|w| w.status))
+            .chain([market_data.status]);
+        let status = overall_status([redis.status, postgres.status], degradable);
+
+        HealthReport {
+            status,
+            checked_at: now,
+            redis,
+            postgres,
+            providers,
+            workers,
+            market_data,
+        }
+    }
+
+    async fn check_redis(&self) -> DependencyCheck {
+        timed(async {
+            let mut redis = self.redis.get().await?;
+            redis::cmd("PING").query_async::<String>(&mut redis).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn check_postgres(&self) -> DependencyCheck {
+        timed(async {
+            sqlx::query("SELECT 1")
+                .execute(&self.postgres)
+                .await
+                .map_err(|e| DcaError::Generic(e.to_string()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn check_market_data(&self) -> MarketDataCheck {
+        if let Some((checked_at, check)) = self.market_data.read().as_ref() {
+            if checked_at.elapsed() < Self::MARKET_DATA_TTL {
+                return check.clone();
+            }
+        }
+
+        let check = match self.mkt_data_repo.load_markets().await {
+            Ok(markets) => {
+                let active = markets.iter().filter(|m| m.is_active()).collect::<Vec<_>>();
+                let outdated = active
+                    .iter()
+                    .filter(|m| m.price().is_none_or(|px| px.is_outdated()))
+                    .count();
+                let last_price_at = active
+                    .iter()
+                    .filter_map(|m| m.price().map(|px| px.ts))
+                    .max();
+
+                // Most prices are expected to be refreshed on each update
+                let status = if active.is_empty() || outdated * 2 > active.len() {
+                    HealthStatus::Degraded
+                } else {
+                    HealthStatus::Up
+                };
+
+                MarketDataCheck {
+                    status,
+                    markets: active.len(),
+                    outdated,
+                    last_price_at,
+                }
+            }
+            Err(e) => {
+                warn!("Failed to check market data freshness: {e:?}");
+                MarketDataCheck {
+                    status: HealthStatus::Down,
+                    markets: 0,
+                    outdated: 0,
+                    last_price_at: None,
+                }
+            }
+        };
+
+        *self.market_data.write() = Some((Instant::now(), check.clone()));
+        check
+    }
+}
+
+/// Worst status among the checks. Only storage failures make the server
+/// unable to serve requests, other failures degrade it
+fn overall_status(
+    storage: [HealthStatus; 2],
+    degradable: impl IntoIterator<Item = HealthStatus>,
+) -> HealthStatus {
+    storage
+        .into_iter()
+        .chain(
+            degradable
+                .into_iter()
+                .map(|s| s.min(HealthStatus::Degraded)),
+        )
+        .max()
+        .unwrap_or(HealthStatus::Up)
+}
+
+async fn timed(check: impl Future<Output = Result<()>>) -> DependencyCheck {
+    let start = Instant::now();
+    let res = tokio::time::timeout(HealthService::CHECK_TIMEOUT, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let error = match res {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some("Timed out".to_string()),
+    };
+
+    DependencyCheck {
+        status: if error.is_none() {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        },
+        latency_ms,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use HealthStatus::*;
+
+    use super::*;
+
+    #[test]
+    fn only_storage_takes_the_server_down() {
+        assert_eq!(overall_status([Up, Up], [Up, Up]), Up);
+        assert_eq!(overall_status([Up, Up], []), Up);
+        assert_eq!(overall_status([Up, Up], [Up, Down]), Degraded);
+        assert_eq!(overall_status([Up, Up], [Degraded, Up]), Degraded);
+        assert_eq!(overall_status([Up, Down], [Up]), Down);
+        assert_eq!(overall_status([Down, Up], [Down, Degraded]), Down);
+    }
+}
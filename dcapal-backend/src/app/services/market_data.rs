@@ -1,39 +1,83 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use chrono::Utc;
 use parking_lot::RwLock;
+use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
 use crate::{
     app::{
-        domain::entity::{Asset, AssetId, AssetKind, Market, MarketId, Price},
-        services::command::ConversionRateQuery,
+        domain::{
+            conversion::{Conversion, MarketGraph},
+            entity::{Asset, AssetId, AssetKind, Market, MarketId, Price},
+        },
+        services::{command::ConversionRateQuery, symbols::SymbolRegistry},
     },
-    error::{DcaError, Result},
+    config,
+    error::Result,
     ports::outbound::repository::market_data::MarketDataRepository,
 };
 
 pub struct MarketDataService {
     repo: Arc<MarketDataRepository>,
+    symbols: Arc<SymbolRegistry>,
     markets: RwLock<HashMap<MarketId, Arc<Market>>>,
-    pricers: RwLock<HashMap<(AssetId, AssetId), Option<Price>>>,
+    pricers: RwLock<HashMap<(AssetId, AssetId), CachedRate>>,
     price_deps: RwLock<HashMap<MarketId, Vec<(AssetId, AssetId)>>>,
+    market_graph: RwLock<Option<Arc<MarketGraph>>>,
+    max_conversion_hops: usize,
+    requested: RwLock<RequestedMarkets>,
     assets_cache: RwLock<AssetsCache>,
+    updates: broadcast::Sender<Arc<PriceUpdate>>,
+}
+
+/// Pairs whose conversion rate changed after a market price update
+#[derive(Debug)]
+pub struct PriceUpdate {
+    pub pairs: Vec<(AssetId, AssetId)>,
+}
+
+impl PriceUpdate {
+    pub fn affects(&self, base: &AssetId, quote: &AssetId) -> bool {
+        self.pairs
+            .iter()
+            .any(|(b, q)| (b == base && q == quote) || (b == quote && q == base))
+    }
 }
 
 impl MarketDataService {
-    pub fn new(repo: Arc<MarketDataRepository>) -> Self {
+    const UPDATES_CAPACITY: usize = 1024;
+    /// How long requested markets are tracked. Must cover the longest period
+    /// workers look back on (see [`Self::requested_markets`])
+    pub const REQUESTED_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(
+        repo: Arc<MarketDataRepository>,
+        symbols: Arc<SymbolRegistry>,
+        config: &config::MarketData,
+    ) -> Self {
         let markets = RwLock::new(HashMap::new());
         let pricers = RwLock::new(HashMap::new());
         let assets_cache = RwLock::new(AssetsCache::new());
         let price_deps = RwLock::new(HashMap::new());
+        let market_graph = RwLock::new(None);
+        let (updates, _) = broadcast::channel(Self::UPDATES_CAPACITY);
 
         Self {
             repo,
+            symbols,
             markets,
             pricers,
             price_deps,
+            market_graph,
+            max_conversion_hops: config.max_conversion_hops,
+            requested: RwLock::new(RequestedMarkets::default()),
             assets_cache,
+            updates,
         }
     }
 
@@ -51,6 +95,11 @@ impl MarketDataService {
                         return assets.clone();
                     }
                 }
+                AssetKind::Equity => {
+                    if let Some(assets) = &cache.equities {
+                        return assets.clone();
+                    }
+                }
             }
         }
 
@@ -74,6 +123,10 @@ impl MarketDataService {
                 cache.fiats = Some(Arc::new(assets));
                 cache.fiats.as_ref().unwrap().clone()
             }
+            AssetKind::Equity => {
+                cache.equities = Some(Arc::new(assets));
+                cache.equities.as_ref().unwrap().clone()
+            }
         }
     }
 
@@ -81,6 +134,39 @@ impl MarketDataService {
         let mut cache = self.assets_cache.write();
         cache.crypto = None;
         cache.fiats = None;
+        cache.equities = None;
+    }
+
+    /// Rebuild the market graph on next conversion, picking up newly
+    /// discovered markets. Cached conversion rates are dropped as shorter or
+    /// fresher paths may now exist.
+    pub fn invalidate_market_graph(&self) {
+        *self.market_graph.write() = None;
+        self.pricers.write().clear();
+        self.price_deps.write().clear();
+    }
+
+    /// Drop every cached asset, market and conversion rate, reloading them
+    /// from the repository on next request
+    pub fn invalidate_caches(&self) {
+        self.invalidate_asset_cache();
+        self.markets.write().clear();
+        self.invalidate_market_graph();
+    }
+
+    /// Drop removed markets from cache and rebuild the market graph without
+    /// them
+    pub fn evict_markets(&self, ids: &[&MarketId]) {
+        {
+            let mut markets = self.markets.write();
+            let mut requested = self.requested.write();
+            for id in ids {
+                markets.remove(*id);
+                requested.0.remove(*id);
+            }
+        }
+
+        self.invalidate_market_graph();
     }
 
     /// Lookup a [`Market`] by [`MarketId`]
@@ -92,15 +178,7 @@ impl MarketDataService {
             }
         }
 
-        let market = match self.load_market(id).await {
-            Ok(m) => m,
-            Err(e) => {
-                error!("{:?}", e);
-                None
-            }
-        };
-
-        let Some(market) = market else {
+        let Some(market) = self.load_market(id).await? else {
             return Ok(None);
         };
 
@@ -110,10 +188,7 @@ impl MarketDataService {
     }
 
     async fn load_market(&self, id: &MarketId) -> Result<Option<Arc<Market>>> {
-        let mkt = self.repo.find_market(id).await.map_err(|e| {
-            error!(mkt = id, "Error occured in loading market: {}", e);
-            DcaError::MarketNotFound(id.clone())
-        })?;
+        let mkt = self.repo.find_market(id).await?;
 
         match mkt {
             Some(mkt) => Ok(Some(Arc::new(mkt))),
@@ -134,6 +209,8 @@ impl MarketDataService {
             market.as_ref().clone()
         };
 
+        let market_pair = (updated.base.id().clone(), updated.quote.id().clone());
+
         // Update Market price
         {
             let mut markets = self.markets.write();
@@ -142,49 +219,131 @@ impl MarketDataService {
         }
 
         // Invalidate dependent syntetic rates
-        let mut pricers = self.pricers.write();
-        let mut price_deps = self.price_deps.write();
-        if let Some(deps) = price_deps.get(id) {
-            for pair in deps {
-                pricers.remove(pair);
+        let mut pairs = vec![market_pair];
+        {
+            let mut pricers = self.pricers.write();
+            let mut price_deps = self.price_deps.write();
+            if let Some(deps) = price_deps.get(id) {
+                for pair in deps {
+                    pricers.remove(pair);
+                }
             }
+
+            // Clear dependency list
+            pairs.extend(price_deps.remove(id).unwrap_or_default());
         }
 
-        // Clear dependency list
-        price_deps.remove(id);
+        // Notify subscribers, if any
+        let _ = self.updates.send(Arc::new(PriceUpdate { pairs }));
 
         true
     }
 
+    /// Subscribe to [`PriceUpdate`]s pushed whenever a market price changes
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<PriceUpdate>> {
+        self.updates.subscribe()
+    }
+
     pub async fn get_conversion_rate(&self, cmd: ConversionRateQuery) -> Result<Option<Price>> {
         let (base, quote) = (cmd.base.id(), cmd.quote.id());
         let pair = (base.clone(), quote.clone());
 
         {
             let pricers = self.pricers.read();
-            if let Some(price) = pricers.get(&pair) {
-                return Ok(*price);
+            if let Some(rate) = pricers.get(&pair) {
+                self.track_requested(&rate.markets);
+                return Ok(Some(rate.price));
             }
         }
 
         if let Some((price, deps)) = self.compute_conversion_rate(base, quote).await? {
+            self.track_requested(&deps);
+
             {
                 // Track market dependencies to this syntetic rate
                 let mut price_deps = self.price_deps.write();
-                for dep in deps {
-                    price_deps.entry(dep).or_default().push(pair.clone());
+                for dep in &deps {
+                    price_deps
+                        .entry(dep.clone())
+                        .or_default()
+                        .push(pair.clone());
                 }
             }
 
             // Update cached rate
             let mut pricers = self.pricers.write();
-            pricers.insert(pair, Some(price));
+            pricers.insert(
+                pair,
+                CachedRate {
+                    price,
+                    markets: deps,
+                },
+            );
             Ok(Some(price))
         } else {
             Ok(None)
         }
     }
 
+    fn track_requested(&self, markets: &[MarketId]) {
+        self.requested.write().track(markets, Instant::now());
+    }
+
+    /// Markets priced to serve a request in the last `period`, along with the
+    /// time they were last requested
+    pub fn requested_markets(&self, period: Duration) -> HashMap<MarketId, Instant> {
+        self.requested.read().within(period)
+    }
+
+    /// Forget markets not requested within [`Self::REQUESTED_RETENTION`]
+    pub fn prune_requested(&self) {
+        self.requested.write().prune(Self::REQUESTED_RETENTION);
+    }
+
+    /// Get conversion rates for many pairs at once. Direct markets not cached
+    /// yet are loaded from the repository in a single round trip.
+    pub async fn get_conversion_rates(
+        &self,
+        queries: Vec<ConversionRateQuery>,
+    ) -> Vec<Result<Option<Price>>> {
+        let missing: Vec<MarketId> = {
+            let pricers = self.pricers.read();
+            let markets = self.markets.read();
+            queries
+                .iter()
+                .filter(|q| !pricers.contains_key(&(q.base.id().clone(), q.quote.id().clone())))
+                .flat_map(|q| {
+                    let base = self.symbols.normalize(q.base.id());
+                    let quote = self.symbols.normalize(q.quote.id());
+                    [format!("{base}{quote}"), format!("{quote}{base}")]
+                })
+                .filter(|id| !markets.contains_key(id))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        if !missing.is_empty() {
+            let ids: Vec<&MarketId> = missing.iter().collect();
+            match self.repo.find_markets(&ids).await {
+                Ok(loaded) => {
+                    let mut markets = self.markets.write();
+                    for m in loaded.into_iter().flatten() {
+                        markets.entry(m.id.clone()).or_insert_with(|| Arc::new(m));
+                    }
+                }
+                Err(e) => error!("Failed to load markets {missing:?}: {e:?}"),
+            }
+        }
+
+        let mut rates = Vec::with_capacity(queries.len());
+        for q in queries {
+            rates.push(self.get_conversion_rate(q).await);
+        }
+
+        rates
+    }
+
     async fn compute_conversion_rate(
         &self,
         base: &AssetId,
@@ -195,147 +354,145 @@ impl MarketDataService {
             return Ok(Some((Price::new(1., Utc::now()), vec![])));
         }
 
-        let base = normalized_asset(base);
-        let quote = normalized_asset(quote);
+        let base = self.symbols.normalize(base);
+        let quote = self.symbols.normalize(quote);
 
-        // Find base/quote market
-        let id = format!("{}{}", base, quote);
-        let mkt = self.get_market(&id).await?;
-        if let Some(m) = mkt {
-            if let Some(px) = m.price() {
-                info!("Computed conversion rate for market {}", m.id);
-                return Ok(Some((*px, vec![id])));
-            }
+        // Direct markets need no graph search
+        if let Some(conversion) = self.find_direct_rate(&base, &quote) {
+            info!(
+                "Computed conversion rate for market {}",
+                conversion.markets[0]
+            );
+            return Ok(Some((conversion.price, conversion.markets)));
         }
 
-        // Find quote/base market
-        let id = format!("{}{}", quote, base);
-        let mkt = self.get_market(&id).await?;
-        if let Some(m) = mkt {
-            if let Some(px) = m.price() {
-                let rate = Price::new(1. / px.price, px.ts);
-                info!("Computed conversion rate for market {}", m.id);
-                return Ok(Some((rate, vec![id])));
-            }
-        }
+        let graph = self.get_market_graph().await?;
+        let conversion = {
+            let markets = self.markets.read();
+            graph.find_rate(&base, &quote, self.max_conversion_hops, |id| {
+                markets
+                    .get(id)
+                    .filter(|m| m.is_listed())
+                    .and_then(|m| *m.price())
+            })
+        };
 
-        // Find alternative markets
-        let Some((base_usd_id, base_usd_px)) = self.get_base_usd_price(&base, &quote).await? else {
+        let Some(conversion) = conversion else {
+            warn!(
+                base = base,
+                quote = quote,
+                "Cannot find any path of at most {} priced markets",
+                self.max_conversion_hops
+            );
             return Ok(None);
         };
 
-        let base_quote_id = format!("{}{}", base, quote);
-        let usd_quote_id = format!("{}{}", "usd", quote);
-        let quote_usd_id = format!("{}{}", quote, "usd");
-
-        let usd_quote = self.get_market(&usd_quote_id).await?;
-        if let Some(usd_quote) = usd_quote {
-            if let Some(usd_quote_px) = usd_quote.price() {
-                let price = base_usd_px.price * usd_quote_px.price;
-                let ts = std::cmp::min(base_usd_px.ts, usd_quote_px.ts);
-                let rate = Price::new(price, ts);
-                info!(
-                    "Computed conversion rate for market {} triangulating between markets",
-                    base_quote_id
-                );
-                return Ok(Some((rate, vec![base_usd_id, usd_quote_id])));
-            }
-        }
-
-        let quote_usd = self.get_market(&quote_usd_id).await?;
-        if let Some(quote_usd) = quote_usd {
-            if let Some(quote_usd_px) = quote_usd.price() {
-                let price = base_usd_px.price / quote_usd_px.price;
-                let ts = std::cmp::min(base_usd_px.ts, quote_usd_px.ts);
-                let rate = Price::new(price, ts);
-                info!(
-                    "Computed conversion rate for market {} triangulating between markets",
-                    base_quote_id
-                );
-                return Ok(Some((rate, vec![base_quote_id, usd_quote_id])));
-            }
-        }
-
-        warn!(
+        info!(
             base = base,
             quote = quote,
-            "Price not available for markets '{}' and '{}'",
-            usd_quote_id,
-            quote_usd_id
+            "Computed conversion rate through markets {:?}",
+            conversion.markets
         );
 
-        Ok(None)
+        Ok(Some((conversion.price, conversion.markets)))
     }
 
-    async fn get_base_usd_price(
-        &self,
-        base: &AssetId,
-        quote: &AssetId,
-    ) -> Result<Option<(MarketId, Price)>> {
-        let base_usd_id = format!("{}{}", base, "usd");
-        let usd_base_id = format!("{}{}", "usd", base);
-
-        let base_usd = self.get_market(&base_usd_id).await?;
-        if let Some(ref m) = base_usd {
-            if let Some(px) = m.price() {
-                return Ok(Some((base_usd_id, *px)));
-            }
-        }
+    fn find_direct_rate(&self, base: &AssetId, quote: &AssetId) -> Option<Conversion> {
+        direct_rate(&self.markets.read(), base, quote)
+    }
 
-        let usd_base = self.get_market(&usd_base_id).await?;
-        if let Some(ref m) = usd_base {
-            if let Some(px) = m.price() {
-                return Ok(Some((
-                    usd_base_id,
-                    Price {
-                        price: 1. / px.price,
-                        ts: px.ts,
-                    },
-                )));
-            }
+    /// Get the [`MarketGraph`] of all known markets, loading every market in
+    /// cache the first time it is built
+    pub async fn get_market_graph(&self) -> Result<Arc<MarketGraph>> {
+        if let Some(graph) = self.market_graph.read().as_ref() {
+            return Ok(graph.clone());
         }
 
-        match (base_usd, usd_base) {
-            (None, None) => {
-                warn!(
-                    base = base,
-                    quote = quote,
-                    "Cannot find any of markets: '{}', '{}'",
-                    base_usd_id,
-                    usd_base_id
-                );
-                Ok(None)
-            }
-            (_, _) => {
-                warn!(
-                    base = base,
-                    quote = quote,
-                    "Price not available for any of markets: '{}', '{}'",
-                    base_usd_id,
-                    usd_base_id
-                );
-                Ok(None)
-            }
+        let loaded = self.repo.load_markets().await?;
+
+        let mut markets = self.markets.write();
+        for m in loaded {
+            // Cached markets may hold fresher prices than the repository
+            markets.entry(m.id.clone()).or_insert_with(|| Arc::new(m));
         }
+
+        let graph = Arc::new(MarketGraph::new(markets.values().map(|m| m.as_ref())));
+        *self.market_graph.write() = Some(graph.clone());
+
+        Ok(graph)
     }
 }
 
-fn normalized_asset(id: &AssetId) -> AssetId {
-    lazy_static::lazy_static! {
-        static ref NORMALIZED: HashMap<&'static str, &'static str> = {
-            [("eth2", "eth"), ("eth2.s", "eth")].into_iter().collect()
-        };
+/// Find the fresher between cached base/quote and quote/base market rates
+fn direct_rate(
+    markets: &HashMap<MarketId, Arc<Market>>,
+    base: &AssetId,
+    quote: &AssetId,
+) -> Option<Conversion> {
+    let price_of = |id: &MarketId| {
+        markets
+            .get(id)
+            .filter(|m| m.is_listed())
+            .and_then(|m| *m.price())
+            // A zero quote is no rate and must not yield an infinite inverse
+            .filter(|px| px.price > 0.)
+    };
+
+    let direct_id = format!("{base}{quote}");
+    let direct = price_of(&direct_id).map(|px| Conversion {
+        price: px,
+        markets: vec![direct_id],
+    });
+
+    let inverse_id = format!("{quote}{base}");
+    let inverse = price_of(&inverse_id).map(|px| Conversion {
+        price: Price {
+            price: 1. / px.price,
+            ..px
+        },
+        markets: vec![inverse_id],
+    });
+
+    match (direct, inverse) {
+        (Some(d), Some(i)) if i.price.ts > d.price.ts => Some(i),
+        (Some(d), _) => Some(d),
+        (None, i) => i,
     }
+}
 
-    NORMALIZED
-        .get(id.as_str())
-        .unwrap_or(&id.as_str())
-        .to_string()
+/// Markets priced to serve requests, with the time they were last requested
+#[derive(Default)]
+struct RequestedMarkets(HashMap<MarketId, Instant>);
+
+impl RequestedMarkets {
+    fn track(&mut self, markets: &[MarketId], now: Instant) {
+        for m in markets {
+            self.0.insert(m.clone(), now);
+        }
+    }
+
+    fn within(&self, period: Duration) -> HashMap<MarketId, Instant> {
+        self.0
+            .iter()
+            .filter(|(_, ts)| ts.elapsed() < period)
+            .map(|(id, ts)| (id.clone(), *ts))
+            .collect()
+    }
+
+    fn prune(&mut self, retention: Duration) {
+        self.0.retain(|_, ts| ts.elapsed() < retention);
+    }
+}
+
+struct CachedRate {
+    price: Price,
+    markets: Vec<MarketId>,
 }
 
 struct AssetsCache {
     fiats: Option<Arc<Vec<Asset>>>,
     crypto: Option<Arc<Vec<Asset>>>,
+    equities: Option<Arc<Vec<Asset>>>,
 }
 
 impl AssetsCache {
@@ -343,6 +500,71 @@ impl AssetsCache {
         Self {
             fiats: None,
             crypto: None,
+            equities: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::entity::Crypto;
+
+    fn cache_market(
+        markets: &mut HashMap<MarketId, Arc<Market>>,
+        base: &str,
+        quote: &str,
+        price: f64,
+    ) {
+        let id = format!("{base}{quote}");
+        let market = Market::new(
+            id.clone(),
+            Asset::Crypto(Crypto::new_with_id(base.to_string())),
+            Asset::Crypto(Crypto::new_with_id(quote.to_string())),
+            Some(Price::new(price, Utc::now())),
+        );
+        markets.insert(id, Arc::new(market));
+    }
+
+    #[test]
+    fn direct_rate_skips_zero_prices() {
+        let mut markets = HashMap::new();
+        cache_market(&mut markets, "btc", "eur", 0.);
+        cache_market(&mut markets, "eth", "eur", 2000.);
+
+        assert!(direct_rate(&markets, &"btc".into(), &"eur".into()).is_none());
+        assert!(direct_rate(&markets, &"eur".into(), &"btc".into()).is_none());
+
+        let inverse = direct_rate(&markets, &"eur".into(), &"eth".into()).unwrap();
+        assert_eq!(inverse.price.price, 1. / 2000.);
+    }
+
+    #[test]
+    fn requested_markets_does_not_evict_wider_windows() {
+        let now = Instant::now();
+        // Instants cannot precede the monotonic clock origin, e.g. boot time
+        let (Some(half_hour_ago), Some(two_hours_ago)) = (
+            now.checked_sub(Duration::from_secs(30 * 60)),
+            now.checked_sub(Duration::from_secs(2 * 60 * 60)),
+        ) else {
+            return;
+        };
+
+        let mut requested = RequestedMarkets::default();
+        requested.track(&["btceur".to_string()], now);
+        requested.track(&["etheur".to_string()], half_hour_ago);
+        requested.track(&["xrpeur".to_string()], two_hours_ago);
+
+        let short = requested.within(Duration::from_secs(15 * 60));
+        assert_eq!(short.len(), 1);
+        assert!(short.contains_key("btceur"));
+
+        let long = requested.within(Duration::from_secs(60 * 60));
+        assert_eq!(long.len(), 2);
+        assert!(long.contains_key("etheur"));
+
+        requested.prune(MarketDataService::REQUESTED_RETENTION);
+        assert_eq!(requested.0.len(), 2);
+        assert!(!requested.0.contains_key("xrpeur"));
+    }
+}
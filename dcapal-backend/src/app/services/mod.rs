@@ -1,4 +1,13 @@
+pub mod admin;
+pub mod calendar;
+pub mod chart;
 pub mod command;
+pub mod connector;
+pub mod corporate_actions;
+pub mod health;
 pub mod ip2location;
 pub mod market_data;
 pub mod portfolio;
+pub mod price_history;
+pub mod search;
+pub mod symbols;
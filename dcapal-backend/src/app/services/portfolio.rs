@@ -3,11 +3,11 @@ use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
 
 use crate::{
-    error::Result,
+    error::{DcaError, Result},
     ports::{
         inbound::rest::{
             request::{PortfolioRequest, SyncPortfoliosRequest},
-            response::{PortfolioResponse, SyncPortfoliosResponse},
+            response::{CashFlowResponse, PortfolioResponse, SyncPortfoliosResponse},
         },
         outbound::repository::portfolio::PortfolioRepository,
     },
@@ -29,6 +29,22 @@ impl PortfolioService {
         user_id: Uuid,
         req: SyncPortfoliosRequest,
     ) -> Result<SyncPortfoliosResponse> {
+        // Reject the whole sync before any write if it touches other users
+        // portfolios
+        let requested_ids = req
+            .portfolios
+            .iter()
+            .map(|pf| pf.id)
+            .chain(req.deleted_portfolios.iter().copied())
+            .collect::<Vec<_>>();
+        let foreign = self
+            .portfolio_repository
+            .find_foreign_portfolios(user_id, &requested_ids)
+            .await?;
+        if let Some(id) = foreign.first() {
+            return Err(DcaError::PortfolioForbidden(*id));
+        }
+
         let db_portfolios = self
             .portfolio_repository
             .get_user_portfolios_with_assets(user_id)
@@ -80,7 +96,9 @@ impl PortfolioService {
 
         // Process deleted portfolios
         for deleted_pf in req.deleted_portfolios {
-            self.portfolio_repository.soft_delete(deleted_pf).await?;
+            self.portfolio_repository
+                .soft_delete(user_id, deleted_pf)
+                .await?;
         }
 
         Ok(SyncPortfoliosResponse {
@@ -88,4 +106,74 @@ impl PortfolioService {
             deleted_portfolios,
         })
     }
+
+    /// Cash flows credited to `user_id` portfolio, newest first
+    pub async fn get_cash_flows(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Vec<CashFlowResponse>> {
+        let cash_flows = self
+            .portfolio_repository
+            .find_cash_flows(user_id, portfolio_id)
+            .await?
+            .ok_or(DcaError::PortfolioNotFound(portfolio_id))?;
+
+        Ok(cash_flows.into_iter().map(CashFlowResponse::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use super::*;
+    use crate::app::domain::db::portfolios;
+
+    #[tokio::test]
+    async fn test_sync_portfolios_rejects_foreign_ids_before_writing() {
+        let (user_id, owner_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (owned_id, foreign_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let foreign = portfolios::Model {
+            id: foreign_id,
+            user_id: owner_id,
+            name: "Foreign".to_string(),
+            currency: "eur".to_string(),
+            deleted: false,
+            last_updated_at: Default::default(),
+            max_fee_impact: None,
+            fee_type: None,
+            fee_amount: None,
+            fee_rate: None,
+            min_fee: None,
+            max_fee: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![foreign]])
+            .into_connection();
+        let repository = Arc::new(PortfolioRepository { db_conn: db });
+        let service = PortfolioService::new(repository.clone());
+
+        let req = SyncPortfoliosRequest {
+            portfolios: vec![PortfolioRequest {
+                id: owned_id,
+                name: "Owned".to_string(),
+                quote_ccy: "eur".to_string(),
+                fees: None,
+                assets: vec![],
+                last_updated_at: Utc::now(),
+            }],
+            deleted_portfolios: vec![foreign_id],
+        };
+        let res = service.sync_portfolios(user_id, req).await;
+        assert!(matches!(res, Err(DcaError::PortfolioForbidden(id)) if id == foreign_id));
+
+        // Only the ownership check ran, no portfolio was written
+        drop(service);
+        let repository = Arc::try_unwrap(repository).ok().unwrap();
+        assert_eq!(repository.db_conn.into_transaction_log().len(), 1);
+    }
 }
@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDate, Utc};
+use parking_lot::RwLock;
+use tracing::{error, info, warn};
+
+use crate::{
+    app::{
+        domain::{
+            conversion::MarketGraph,
+            entity::{AssetId, MarketId, Price},
+            market_data_utils::fetch_daily_price,
+        },
+        infra::utils::day_start,
+        services::{
+            command::ConversionRateQuery, market_data::MarketDataService, symbols::SymbolRegistry,
+        },
+    },
+    config::{self, PriceProvider},
+    error::Result,
+    ports::outbound::{adapter::PriceProviders, repository::market_data::MarketDataRepository},
+};
+
+/// Service resolving conversion rates at past dates, from the daily close
+/// prices of the markets on the conversion path. Prices are fetched from the
+/// providers once, then served from the repository.
+pub struct PriceHistoryService {
+    repo: Arc<MarketDataRepository>,
+    mkt_data: Arc<MarketDataService>,
+    symbols: Arc<SymbolRegistry>,
+    providers: Arc<PriceProviders>,
+    price_provider: PriceProvider,
+    fiat_provider: PriceProvider,
+    max_conversion_hops: usize,
+    /// Daily prices providers had not, with the time they were looked up
+    missing: RwLock<HashMap<(MarketId, NaiveDate), Instant>>,
+}
+
+impl PriceHistoryService {
+    /// Conversion paths tried before giving up, each one skipping the markets
+    /// found without price on the previous ones
+    const MAX_PATHS: usize = 5;
+    /// Time daily prices not found are not looked up again, sparing
+    /// providers repeated requests while they publish late closes
+    const MISSING_TTL: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(
+        repo: Arc<MarketDataRepository>,
+        mkt_data: Arc<MarketDataService>,
+        symbols: Arc<SymbolRegistry>,
+        providers: Arc<PriceProviders>,
+        price_provider: PriceProvider,
+        fiat_provider: PriceProvider,
+        config: &config::MarketData,
+    ) -> Self {
+        Self {
+            repo,
+            mkt_data,
+            symbols,
+            providers,
+            price_provider,
+            fiat_provider,
+            max_conversion_hops: config.max_conversion_hops,
+            missing: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the conversion rate of `cmd` pair at the close of `date`. Today and
+    /// later dates resolve to the current rate
+    pub async fn get_conversion_rate(
+        &self,
+        cmd: ConversionRateQuery,
+        date: NaiveDate,
+    ) -> Result<Option<Price>> {
+        if date >= Utc::now().date_naive() {
+            return self.mkt_data.get_conversion_rate(cmd).await;
+        }
+
+        let base = self.symbols.normalize(cmd.base.id());
+        let quote = self.symbols.normalize(cmd.quote.id());
+        if base == quote {
+            return Ok(Some(Price::new(1., day_start(date))));
+        }
+
+        let graph = self.mkt_data.get_market_graph().await?;
+        let rate = find_daily_rate(
+            &graph,
+            &base,
+            &quote,
+            self.max_conversion_hops,
+            date,
+            |id| async move { self.get_daily_price(&id, date).await },
+        )
+        .await?;
+
+        if rate.is_none() {
+            warn!(
+                base = base,
+                quote = quote,
+                "Cannot find any path of at most {} markets priced on {date}",
+                self.max_conversion_hops
+            );
+        }
+
+        Ok(rate)
+    }
+
+    /// Daily close price of market `id`, fetched from its provider if not
+    /// stored yet
+    async fn get_daily_price(&self, id: &MarketId, date: NaiveDate) -> Result<Option<Price>> {
+        if let Some(price) = self.repo.find_daily_price(id, date).await? {
+            return Ok(Some(price));
+        }
+
+        let key = (id.clone(), date);
+        if let Some(ts) = self.missing.read().get(&key) {
+            if ts.elapsed() < Self::MISSING_TTL {
+                return Ok(None);
+            }
+        }
+
+        let Some(market) = self.mkt_data.get_market(id).await? else {
+            return Ok(None);
+        };
+
+        let provider = market.price_provider(self.price_provider, self.fiat_provider);
+        let Some(price) = fetch_daily_price(&market, &self.providers, provider, date).await else {
+            let mut missing = self.missing.write();
+            missing.retain(|_, ts| ts.elapsed() < Self::MISSING_TTL);
+            missing.insert(key, Instant::now());
+            return Ok(None);
+        };
+
+        if let Err(e) = self.repo.store_daily_price(id, date, &price).await {
+            error!("Failed to store {id} daily price (date={date}): {e:?}");
+        }
+
+        Ok(Some(price))
+    }
+}
+
+/// Conversion rate of `base` into `quote` at the close of `date`, through at
+/// most [`PriceHistoryService::MAX_PATHS`] paths of `graph`. Markets are priced
+/// with `daily_price` on first use, each path skipping the markets found
+/// without price on the previous ones
+async fn find_daily_rate<F, Fut>(
+    graph: &MarketGraph,
+    base: &AssetId,
+    quote: &AssetId,
+    max_hops: usize,
+    date: NaiveDate,
+    mut daily_price: F,
+) -> Result<Option<Price>>
+where
+    F: FnMut(MarketId) -> Fut,
+    Fut: Future<Output = Result<Option<Price>>>,
+{
+    let day_start = day_start(date);
+
+    // Markets not fetched yet are assumed priced, until proven otherwise
+    let mut prices: HashMap<MarketId, Option<Price>> = HashMap::new();
+    for _ in 0..PriceHistoryService::MAX_PATHS {
+        let Some(path) = graph.find_rate(base, quote, max_hops, |id| {
+            prices
+                .get(id)
+                .copied()
+                .unwrap_or(Some(Price::new(1., day_start)))
+        }) else {
+            break;
+        };
+
+        let missing = path
+            .markets
+            .iter()
+            .filter(|id| !prices.contains_key(*id))
+            .collect::<Vec<_>>();
+
+        if missing.is_empty() {
+            info!(
+                base = base,
+                quote = quote,
+                "Computed {date} conversion rate through markets {:?}",
+                path.markets
+            );
+            return Ok(Some(Price::new(path.price.price, day_start)));
+        }
+
+        for id in missing {
+            let price = daily_price(id.clone()).await?;
+            prices.insert(id.clone(), price);
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::entity::{Asset, Crypto, Fiat, Market};
+
+    fn market(base: Asset, quote: Asset) -> Market {
+        let id = format!("{}{}", base.id(), quote.id());
+        Market::new(id, base, quote, None)
+    }
+
+    fn crypto(id: &str) -> Asset {
+        Asset::Crypto(Crypto::new_with_id(id.to_string()))
+    }
+
+    fn fiat(id: &str) -> Asset {
+        Asset::Fiat(Fiat::new(id.to_string(), id.to_uppercase()))
+    }
+
+    async fn rate(daily: &[(&str, f64)], base: &str, quote: &str) -> Option<f64> {
+        let markets = vec![
+            market(crypto("btc"), fiat("eur")),
+            market(crypto("eth"), crypto("btc")),
+            market(fiat("usd"), fiat("eur")),
+        ];
+        let graph = MarketGraph::new(&markets);
+        let date = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let daily: HashMap<MarketId, f64> =
+            daily.iter().map(|(id, px)| (id.to_string(), *px)).collect();
+
+        find_daily_rate(
+            &graph,
+            &base.to_string(),
+            &quote.to_string(),
+            3,
+            date,
+            |id| {
+                let px = daily.get(&id).map(|px| Price::new(*px, day_start(date)));
+                async move { Ok(px) }
+            },
+        )
+        .await
+        .unwrap()
+        .map(|px| px.price)
+    }
+
+    #[tokio::test]
+    async fn finds_direct_inverse_and_cross_rates() {
+        let daily = [("btceur", 50000.), ("ethbtc", 0.05), ("usdeur", 0.8)];
+
+        assert_eq!(rate(&daily, "btc", "eur").await, Some(50000.));
+        assert_eq!(rate(&daily, "eur", "btc").await, Some(1. / 50000.));
+
+        let eth_usd = rate(&daily, "eth", "usd").await.unwrap();
+        assert!((eth_usd - 0.05 * 50000. / 0.8).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn no_rate_when_a_market_is_missing() {
+        let daily = [("btceur", 50000.), ("ethbtc", 0.05)];
+
+        assert_eq!(rate(&daily, "eth", "usd").await, None);
+        assert_eq!(rate(&daily, "btc", "gbp").await, None);
+    }
+}
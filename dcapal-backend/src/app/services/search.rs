@@ -0,0 +1,212 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::RwLock;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info};
+
+use crate::{
+    app::{
+        domain::{chart::SymbolInfo, entity::AssetKind, search::AssetIndex},
+        services::{chart::ChartService, symbols::SymbolRegistry},
+    },
+    error::Result,
+    ports::outbound::repository::market_data::MarketDataRepository,
+};
+
+/// Service searching assets known by DCA-Pal, merged with the upstream symbol
+/// search results for equities
+pub struct AssetSearchService {
+    repo: Arc<MarketDataRepository>,
+    symbols: Arc<SymbolRegistry>,
+    chart: Arc<ChartService>,
+    index: RwLock<Option<(Instant, Arc<AssetIndex>)>>,
+    /// Held while rebuilding the index, so that concurrent searches wait for
+    /// a single rebuild
+    rebuild: Mutex<()>,
+}
+
+impl AssetSearchService {
+    const INDEX_TTL: Duration = Duration::from_secs(10 * 60);
+    const MAX_RESULTS: usize = 20;
+
+    pub fn new(
+        repo: Arc<MarketDataRepository>,
+        symbols: Arc<SymbolRegistry>,
+        chart: Arc<ChartService>,
+    ) -> Self {
+        Self {
+            repo,
+            symbols,
+            chart,
+            index: RwLock::new(None),
+            rebuild: Mutex::new(()),
+        }
+    }
+
+    /// Search assets matching `query`, optionally restricted to `kind`
+    pub async fn search(&self, query: &str, kind: Option<AssetKind>) -> Result<Vec<SymbolInfo>> {
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let local = self
+            .get_index()
+            .await?
+            .search(query, kind, Self::MAX_RESULTS);
+        debug!("Found {} local matches for '{query}'", local.len());
+
+        // Upstream search only knows about exchange listed symbols
+        if kind.is_some_and(|k| k != AssetKind::Equity) {
+            return Ok(local);
+        }
+
+        let upstream = match self.chart.search(query).await {
+            Ok(results) => results,
+            Err(e) if !local.is_empty() => {
+                error!("Failed to search upstream symbols matching '{query}': {e:?}");
+                vec![]
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(merge_results(local, upstream, Self::MAX_RESULTS))
+    }
+
+    async fn get_index(&self) -> Result<Arc<AssetIndex>> {
+        if let Some(index) = self.fresh_index() {
+            return Ok(index);
+        }
+
+        let _rebuild = self.rebuild.lock().await;
+        // Rebuilt by another search while waiting
+        if let Some(index) = self.fresh_index() {
+            return Ok(index);
+        }
+
+        let index = match self.build_index().await {
+            Ok(index) => Arc::new(index),
+            Err(e) => {
+                // Keep serving the stale index, if any, rather than failing
+                let Some((_, index)) = self.index.read().clone() else {
+                    return Err(e);
+                };
+                error!("Failed to rebuild asset search index: {e:?}");
+                return Ok(index);
+            }
+        };
+
+        *self.index.write() = Some((Instant::now(), index.clone()));
+        Ok(index)
+    }
+
+    fn fresh_index(&self) -> Option<Arc<AssetIndex>> {
+        self.index
+            .read()
+            .as_ref()
+            .filter(|(built_at, _)| built_at.elapsed() < Self::INDEX_TTL)
+            .map(|(_, index)| index.clone())
+    }
+
+    async fn build_index(&self) -> Result<AssetIndex> {
+        let mut assets = Vec::new();
+        for kind in [AssetKind::Crypto, AssetKind::Fiat, AssetKind::Equity] {
+            assets.extend(self.repo.load_assets_by_type(kind).await?);
+        }
+
+        let index = AssetIndex::new(&assets, self.symbols.all());
+        info!("Built asset search index of {} assets", index.len());
+
+        Ok(index)
+    }
+}
+
+/// Local matches first, then `upstream` symbols not known locally, up to
+/// `limit` results. Local equities take the name, type and exchange reported
+/// upstream, as only their ticker is stored
+fn merge_results(
+    local: Vec<SymbolInfo>,
+    mut upstream: Vec<SymbolInfo>,
+    limit: usize,
+) -> Vec<SymbolInfo> {
+    let mut results = local
+        .into_iter()
+        .map(|l| {
+            match upstream
+                .iter()
+                .position(|u| u.symbol.eq_ignore_ascii_case(&l.symbol))
+            {
+                Some(i) => SymbolInfo {
+                    isin: l.isin,
+                    source: l.source,
+                    ..upstream.remove(i)
+                },
+                None => l,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    results.extend(upstream);
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::chart::SymbolSource;
+
+    fn info(symbol: &str, name: &str, source: SymbolSource) -> SymbolInfo {
+        SymbolInfo {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            quote_type: "EQUITY".to_string(),
+            exchange: None,
+            exchange_name: None,
+            isin: None,
+            source,
+        }
+    }
+
+    #[test]
+    fn merge_enriches_local_matches_with_upstream_results() {
+        let local = vec![
+            info("VWCE.DE", "VWCE.DE", SymbolSource::Local),
+            info("BTC", "Bitcoin", SymbolSource::Local),
+        ];
+        let upstream = vec![
+            info(
+                "VWCA.DE",
+                "Vanguard FTSE All-World Acc",
+                SymbolSource::Yahoo,
+            ),
+            info("VWCE.DE", "Vanguard FTSE All-World", SymbolSource::Yahoo),
+        ];
+
+        let results = merge_results(local, upstream, 10);
+        let symbols = results
+            .iter()
+            .map(|r| r.symbol.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(symbols, vec!["VWCE.DE", "BTC", "VWCA.DE"]);
+
+        assert_eq!(results[0].name, "Vanguard FTSE All-World");
+        assert_eq!(results[0].source, SymbolSource::Local);
+        assert_eq!(results[2].source, SymbolSource::Yahoo);
+    }
+
+    #[test]
+    fn merge_keeps_at_most_limit_results() {
+        let local = vec![info("BTC", "Bitcoin", SymbolSource::Local)];
+        let upstream = vec![
+            info("BTC-USD", "Bitcoin USD", SymbolSource::Yahoo),
+            info("BTC-EUR", "Bitcoin EUR", SymbolSource::Yahoo),
+        ];
+
+        let results = merge_results(local, upstream, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].symbol, "BTC");
+    }
+}
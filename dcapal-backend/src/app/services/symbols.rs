@@ -0,0 +1,262 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use parking_lot::RwLock;
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    app::domain::entity::{AssetId, SymbolMapping},
+    config::PriceProvider,
+    error::{DcaError, Result},
+    ports::outbound::repository::symbol::SymbolRepository,
+};
+
+static DEFAULT_SYMBOLS_STR: &str = include_str!("../../../config/dcapal/symbols.yml");
+
+/// Central registry of [`SymbolMapping`]s. Mappings are seeded from a YAML
+/// file into the [`SymbolRepository`] at startup and then served from
+/// memory. Reloading from the repository picks up mappings edited at runtime.
+pub struct SymbolRegistry {
+    repo: Arc<SymbolRepository>,
+    index: RwLock<SymbolIndex>,
+}
+
+impl SymbolRegistry {
+    pub fn new(repo: Arc<SymbolRepository>) -> Self {
+        Self {
+            repo,
+            index: RwLock::new(SymbolIndex::default()),
+        }
+    }
+
+    /// Import mappings from `path` (or the bundled defaults, if `None`) into
+    /// the repository, then reload the registry. Only mappings never stored
+    /// are seeded, preserving the ones edited or deleted at runtime. If the
+    /// repository is unavailable, the imported mappings are served until the
+    /// next successful reload
+    pub async fn import(&self, path: Option<&str>) -> Result<()> {
+        let mappings = match path {
+            Some(p) => {
+                info!("Importing symbol mappings from '{p}'");
+                let content = std::fs::read_to_string(Path::new(p))
+                    .map_err(|e| DcaError::InvalidSymbolsPath(p.to_string(), e))?;
+                parse_symbols_file(&content)?
+            }
+            None => parse_symbols_file(DEFAULT_SYMBOLS_STR)?,
+        };
+
+        match self.repo.seed(&mappings).await {
+            Ok(n) => info!("Seeded {n} new symbol mappings"),
+            Err(e) => error!("Failed to seed symbol mappings: {:?}", e),
+        }
+
+        if let Err(e) = self.reload().await {
+            error!(
+                "Failed to load symbol mappings, serving imported ones: {:?}",
+                e
+            );
+            *self.index.write() = SymbolIndex::from(mappings);
+        }
+
+        Ok(())
+    }
+
+    /// Reload all mappings from the repository
+    pub async fn reload(&self) -> Result<()> {
+        let mappings = self.repo.load_all().await?;
+        *self.index.write() = SymbolIndex::from(mappings);
+
+        Ok(())
+    }
+
+    /// Store `mapping` in the repository, replacing any existing one with the
+    /// same id
+    pub async fn upsert(&self, mapping: SymbolMapping) -> Result<()> {
+        self.repo.store(&mapping).await?;
+        self.index.write().insert(mapping);
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: &AssetId) -> Result<bool> {
+        let deleted = self.repo.delete(id).await?;
+        self.index.write().remove(id);
+
+        Ok(deleted)
+    }
+
+    pub fn get(&self, id: &AssetId) -> Option<SymbolMapping> {
+        self.index.read().by_id.get(id).cloned()
+    }
+
+    pub fn all(&self) -> Vec<SymbolMapping> {
+        self.index.read().by_id.values().cloned().collect()
+    }
+
+    /// Resolve the asset used to price `id`, following aliases
+    pub fn normalize(&self, id: &AssetId) -> AssetId {
+        let index = self.index.read();
+        index
+            .by_id
+            .get(id)
+            .and_then(|m| m.alias_of.clone())
+            .unwrap_or_else(|| id.clone())
+    }
+
+    /// Ticker used by `provider` for asset `id`. Defaults to `id` itself
+    pub fn to_provider(&self, id: &AssetId, provider: PriceProvider) -> String {
+        let index = self.index.read();
+        index
+            .by_id
+            .get(id)
+            .and_then(|m| m.ticker(provider).cloned())
+            .unwrap_or_else(|| id.clone())
+    }
+
+    /// Internal asset id for a `provider` ticker. Defaults to the lowercase
+    /// ticker
+    pub fn from_provider(&self, ticker: &str, provider: PriceProvider) -> AssetId {
+        let ticker = ticker.to_lowercase();
+        let index = self.index.read();
+        index
+            .by_ticker
+            .get(&(provider, ticker.clone()))
+            .cloned()
+            .unwrap_or(ticker)
+    }
+
+    /// Find a mapping by asset id, ISIN or CUSIP
+    pub fn resolve(&self, identifier: &str) -> Option<SymbolMapping> {
+        let index = self.index.read();
+        let key = identifier.to_lowercase();
+        if let Some(m) = index.by_id.get(&key) {
+            return Some(m.clone());
+        }
+
+        let key = identifier.to_uppercase();
+        index
+            .by_isin
+            .get(&key)
+            .or_else(|| index.by_cusip.get(&key))
+            .and_then(|id| index.by_id.get(id))
+            .cloned()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SymbolsFile {
+    symbols: Vec<SymbolMapping>,
+}
+
+fn parse_symbols_file(content: &str) -> Result<Vec<SymbolMapping>> {
+    let file: SymbolsFile = config::Config::builder()
+        .add_source(config::File::from_str(content, config::FileFormat::Yaml))
+        .build()?
+        .try_deserialize()?;
+
+    Ok(file
+        .symbols
+        .into_iter()
+        .map(|mut m| {
+            m.id = m.id.to_lowercase();
+            m.alias_of = m.alias_of.map(|a| a.to_lowercase());
+            m
+        })
+        .collect())
+}
+
+#[derive(Default)]
+struct SymbolIndex {
+    by_id: HashMap<AssetId, SymbolMapping>,
+    by_ticker: HashMap<(PriceProvider, String), AssetId>,
+    by_isin: HashMap<String, AssetId>,
+    by_cusip: HashMap<String, AssetId>,
+}
+
+impl SymbolIndex {
+    fn insert(&mut self, mapping: SymbolMapping) {
+        self.remove(&mapping.id);
+
+        for (provider, ticker) in &mapping.tickers {
+            let key = (*provider, ticker.to_lowercase());
+            if let Some(other) = self.by_ticker.insert(key, mapping.id.clone()) {
+                if other != mapping.id {
+                    error!(
+                        "Ticker '{ticker}' ({provider}) mapped to both '{other}' and '{}'",
+                        mapping.id
+                    );
+                }
+            }
+        }
+        if let Some(ref isin) = mapping.isin {
+            self.by_isin.insert(isin.to_uppercase(), mapping.id.clone());
+        }
+        if let Some(ref cusip) = mapping.cusip {
+            self.by_cusip
+                .insert(cusip.to_uppercase(), mapping.id.clone());
+        }
+
+        self.by_id.insert(mapping.id.clone(), mapping);
+    }
+
+    fn remove(&mut self, id: &AssetId) {
+        if self.by_id.remove(id).is_none() {
+            return;
+        }
+
+        self.by_ticker.retain(|_, v| v != id);
+        self.by_isin.retain(|_, v| v != id);
+        self.by_cusip.retain(|_, v| v != id);
+    }
+}
+
+impl From<Vec<SymbolMapping>> for SymbolIndex {
+    fn from(mappings: Vec<SymbolMapping>) -> Self {
+        let mut index = SymbolIndex::default();
+        for m in mappings {
+            index.insert(m);
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use super::*;
+
+    #[test]
+    fn bundled_symbols_are_valid() {
+        let mappings = parse_symbols_file(DEFAULT_SYMBOLS_STR).unwrap();
+        let index = SymbolIndex::from(mappings);
+
+        assert_eq!(index.by_id["eth2.s"].alias_of.as_deref(), Some("eth"));
+        assert_eq!(
+            index
+                .by_ticker
+                .get(&(PriceProvider::Kraken, "xbt".to_string())),
+            Some(&"btc".to_string())
+        );
+        assert_eq!(
+            index.by_id["luna"].ticker(PriceProvider::Yahoo),
+            Some(&"luna1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn import_serves_file_when_storage_fails() {
+        // No query results: every statement fails
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let registry = SymbolRegistry::new(Arc::new(SymbolRepository { db_conn: db }));
+
+        registry.import(None).await.unwrap();
+
+        assert_eq!(registry.normalize(&"eth2.s".to_string()), "eth");
+        assert_eq!(
+            registry.to_provider(&"luna".to_string(), PriceProvider::Yahoo),
+            "luna1"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::{error, info};
+
+use crate::{
+    AppContext,
+    app::{
+        infra::scheduler::{Job, RunContext, Schedule},
+        services::corporate_actions::CorporateActionService,
+    },
+    error::Result,
+    ports::outbound::repository::portfolio::PortfolioRepository,
+};
+
+/// Worker syncing the splits and dividends of the equities held by user
+/// portfolios
+pub struct CorporateActionsWorker {
+    service: Arc<CorporateActionService>,
+    portfolio_repo: Arc<PortfolioRepository>,
+}
+
+impl CorporateActionsWorker {
+    pub const NAME: &'static str = "corporateActions";
+    const PERIOD: Duration = Duration::from_secs(6 * 60 * 60);
+    /// How far back corporate actions are looked up on each run
+    const LOOKBACK: chrono::Duration = chrono::Duration::days(90);
+
+    pub fn new(ctx: &AppContext) -> Self {
+        Self {
+            service: ctx.services.corporate_actions.clone(),
+            portfolio_repo: ctx.repos.portfolio.clone(),
+        }
+    }
+
+    async fn sync_actions(&self) -> Result<()> {
+        let start = (Utc::now() - Self::LOOKBACK).date_naive();
+
+        let symbols = self
+            .portfolio_repo
+            .find_portfolio_symbols()
+            .await?
+            .into_iter()
+            .filter(|s| s.is_yahoo_equity())
+            .map(|s| s.symbol)
+            .collect::<BTreeSet<_>>();
+
+        let mut new_actions = 0;
+        for symbol in &symbols {
+            match self.service.sync(symbol, start).await {
+                Ok(n) => new_actions += n,
+                Err(e) => error!("Failed to sync '{symbol}' corporate actions: {e:?}"),
+            }
+        }
+
+        info!(
+            "Synced corporate actions of {} equities ({new_actions} new)",
+            symbols.len()
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for CorporateActionsWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(Self::PERIOD)
+    }
+
+    async fn run(&self, _: RunContext) -> Result<()> {
+        self.sync_actions().await
+    }
+}
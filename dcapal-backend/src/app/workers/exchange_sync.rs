@@ -0,0 +1,44 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::{
+    app::{
+        infra::scheduler::{Job, RunContext, Schedule},
+        services::connector::ConnectorService,
+    },
+    error::Result,
+};
+
+/// Worker syncing portfolio holdings with the balances of linked exchange
+/// accounts
+pub struct ExchangeSyncWorker {
+    period: Duration,
+    service: Arc<ConnectorService>,
+}
+
+impl ExchangeSyncWorker {
+    pub const NAME: &'static str = "exchangeSync";
+
+    pub fn new(service: Arc<ConnectorService>, period: Duration) -> Self {
+        Self { period, service }
+    }
+}
+
+#[async_trait]
+impl Job for ExchangeSyncWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.period)
+    }
+
+    async fn run(&self, _: RunContext) -> Result<()> {
+        let (synced, total) = self.service.sync_all().await?;
+        info!("Synced {synced}/{total} exchange connections");
+        Ok(())
+    }
+}
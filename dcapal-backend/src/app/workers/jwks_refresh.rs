@@ -0,0 +1,44 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::{
+    app::infra::{
+        jwt::JwtVerifier,
+        scheduler::{Job, RunContext, Schedule},
+    },
+    error::Result,
+};
+
+/// Worker reloading the JWKS of the identity provider, picking up rotated
+/// signing keys
+pub struct JwksRefreshWorker {
+    period: Duration,
+    verifier: Arc<JwtVerifier>,
+}
+
+impl JwksRefreshWorker {
+    pub const NAME: &'static str = "jwksRefresh";
+
+    pub fn new(verifier: Arc<JwtVerifier>, period: Duration) -> Self {
+        Self { period, verifier }
+    }
+}
+
+#[async_trait]
+impl Job for JwksRefreshWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.period)
+    }
+
+    async fn run(&self, _: RunContext) -> Result<()> {
+        let loaded = self.verifier.refresh_keys().await?;
+        info!("Loaded {loaded} JWKS signing keys");
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tracing::{error, info, warn};
+
+use crate::{
+    AppContext,
+    app::{
+        domain::entity::{Market, MarketId, Price},
+        infra::utils::{StopToken, should_stop},
+        services::market_data::MarketDataService,
+    },
+    config::PriceProvider,
+    error::Result,
+    ports::outbound::{
+        adapter::{KrakenTickerConnection, KrakenTickerFeed},
+        repository::market_data::MarketDataRepository,
+    },
+};
+
+/// Worker streaming market prices from Kraken WebSocket ticker feed. While the
+/// feed is down, prices get outdated and [`PriceUpdaterWorker`] falls back to
+/// polling them.
+///
+/// [`PriceUpdaterWorker`]: super::price_updater::PriceUpdaterWorker
+pub struct KrakenTickerWorker {
+    feed: KrakenTickerFeed,
+    fiat_provider: PriceProvider,
+    market_data_service: Arc<MarketDataService>,
+    market_data_repo: Arc<MarketDataRepository>,
+    markets: HashMap<MarketId, Market>,
+    last_stored: HashMap<MarketId, Instant>,
+}
+
+impl KrakenTickerWorker {
+    const MIN_BACKOFF: Duration = Duration::from_secs(1);
+    const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+    /// Period to subscribe newly discovered markets
+    const SUBSCRIBE_PERIOD: Duration = Duration::from_secs(10 * 60);
+    /// Minimum delay between two price updates of the same market
+    const STORE_PERIOD: Duration = Duration::from_secs(10);
+
+    pub fn new(ctx: &AppContext) -> Self {
+        Self {
+            feed: KrakenTickerFeed::new(
+                ctx.config.app.providers.endpoints.kraken_ws.clone(),
+                ctx.services.symbols.clone(),
+            ),
+            fiat_provider: ctx.config.app.providers.fiat_provider,
+            market_data_service: ctx.services.mkt_data.clone(),
+            market_data_repo: ctx.repos.mkt_data.clone(),
+            markets: HashMap::new(),
+            last_stored: HashMap::new(),
+        }
+    }
+
+    pub async fn run(&mut self, mut stop_token: StopToken) {
+        let mut backoff = Self::MIN_BACKOFF;
+        loop {
+            match self.stream_prices(&mut stop_token, &mut backoff).await {
+                Ok(true) => break,
+                Ok(false) => warn!("Kraken ticker feed disconnected"),
+                Err(e) => error!("Error occurred while streaming Kraken prices: {e:?}"),
+            }
+
+            info!("Reconnecting to Kraken ticker feed in {backoff:?}");
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = should_stop(&mut stop_token) => break,
+            }
+
+            backoff = (backoff * 2).min(Self::MAX_BACKOFF);
+        }
+    }
+
+    /// Stream prices until the connection drops. Returns `true` if stopped
+    async fn stream_prices(
+        &mut self,
+        stop_token: &mut StopToken,
+        backoff: &mut Duration,
+    ) -> Result<bool> {
+        let mut conn = self.feed.connect().await?;
+        info!("Connected to Kraken ticker feed");
+
+        let mut subscribe = tokio::time::interval(Self::SUBSCRIBE_PERIOD);
+        loop {
+            tokio::select! {
+                _ = should_stop(stop_token) => return Ok(true),
+                _ = subscribe.tick() => self.subscribe_markets(&mut conn).await?,
+                tick = conn.next_tick() => {
+                    let Some(tick) = tick else {
+                        return Ok(false);
+                    };
+
+                    let tick = tick?;
+                    *backoff = Self::MIN_BACKOFF;
+                    self.update_price(tick.market, tick.price).await;
+                }
+            }
+        }
+    }
+
+    async fn subscribe_markets(&mut self, conn: &mut KrakenTickerConnection) -> Result<()> {
+        // Markets priced by other providers (e.g. tracked equities, fiat rates)
+        // are polled
+        let markets = self
+            .market_data_repo
+            .load_markets()
+            .await?
+            .into_iter()
+            .filter(|m| {
+                m.is_active()
+                    && m.price_provider(PriceProvider::Kraken, self.fiat_provider)
+                        == PriceProvider::Kraken
+            })
+            .collect::<Vec<_>>();
+        conn.subscribe(&markets).await?;
+
+        self.markets = markets.into_iter().map(|m| (m.id.clone(), m)).collect();
+        Ok(())
+    }
+
+    async fn update_price(&mut self, id: MarketId, price: f64) {
+        let now = Instant::now();
+        if self
+            .last_stored
+            .get(&id)
+            .is_some_and(|ts| now.duration_since(*ts) < Self::STORE_PERIOD)
+        {
+            return;
+        }
+
+        let Some(m) = self.markets.get_mut(&id) else {
+            return;
+        };
+
+        let price = Price::new(price, Utc::now());
+        m.set_price(price);
+        if let Err(e) = self.market_data_repo.update_mkt_price(m).await {
+            error!("Failed to store market price update {m:?}: {e:?}");
+            return;
+        }
+
+        self.market_data_service.set_price(&id, price);
+        self.last_stored.insert(id, now);
+    }
+}
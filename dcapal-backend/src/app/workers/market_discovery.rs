@@ -1,98 +1,90 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     AppContext, DateTime,
     app::{
-        domain::market_data_utils::fetch_market_price,
-        infra::utils::{StopToken, should_stop},
-        services::market_data::MarketDataService,
+        domain::{
+            entity::{Asset, Fiat, Market, MarketId, MarketStatus, Price},
+            market_data_utils::fetch_market_price,
+        },
+        infra::scheduler::{Job, JobTrigger, RunContext, Schedule},
+        services::{
+            calendar::TradingCalendars, market_data::MarketDataService, symbols::SymbolRegistry,
+        },
     },
     config::PriceProvider,
     error::Result,
     ports::outbound::{
-        adapter::PriceProviders,
+        adapter::{PriceProviders, currency_name},
         repository::{MiscRepository, market_data::MarketDataRepository},
     },
 };
 
-/// Worker to periodically discover new crypto assets and markets. As of today,
-/// new markets are checked every 24 hours.
+/// Worker to periodically discover new crypto assets and markets, along with
+/// the fiat markets quoted by the ECB. As of today, new markets are checked
+/// every 24 hours: by default, each run checks whether the last discovery is
+/// outdated every minute.
 pub struct MarketDiscoveryWorker {
     market_data_service: Arc<MarketDataService>,
+    symbols: Arc<SymbolRegistry>,
     misc_repo: Arc<MiscRepository>,
     market_data_repo: Arc<MarketDataRepository>,
     price_provider: PriceProvider,
+    fiat_provider: PriceProvider,
     providers: Arc<PriceProviders>,
+    calendars: Arc<TradingCalendars>,
 }
 
 impl MarketDiscoveryWorker {
+    pub const NAME: &'static str = "marketDiscovery";
+    /// Period of the checks for outdated listings
+    const CHECK_PERIOD: Duration = Duration::from_secs(60);
+    /// Time delisted markets are kept before being purged
+    const PURGE_AFTER: chrono::Duration = chrono::Duration::days(30);
+
     pub fn new(ctx: &AppContext) -> Self {
         let market_data_service = ctx.services.mkt_data.clone();
+        let symbols = ctx.services.symbols.clone();
         let misc_repo = ctx.repos.misc.clone();
         let market_data_repo = ctx.repos.mkt_data.clone();
         let price_provider = ctx.config.app.providers.price_provider;
+        let fiat_provider = ctx.config.app.providers.fiat_provider;
         let providers = ctx.providers.clone();
+        let calendars = ctx.services.calendars.clone();
 
         Self {
             market_data_service,
+            symbols,
             misc_repo,
             market_data_repo,
             price_provider,
+            fiat_provider,
             providers,
+            calendars,
         }
     }
 
-    pub async fn run(&self, mut stop_token: StopToken) {
-        let mut sleep = tokio::time::sleep(Duration::from_millis(50));
-
-        loop {
-            tokio::select! {
-                _ = sleep => {}
-                _ = should_stop(&mut stop_token) => break,
-            }
-
-            // Reset next check timeout
-            sleep = tokio::time::sleep(Duration::from_secs(60));
-
-            let res = is_outdated(&self.misc_repo).await;
-            if let Err(e) = res {
-                error!("Failed to fetch last update time: {:?}", e);
-                continue;
-            }
-
-            let (is_outdated, last_fetched_ts) = res.unwrap();
-            if !is_outdated {
-                debug!(
-                    "Kraken assets already fetched today ({})",
-                    last_fetched_ts.map(|t| t.to_string()).unwrap_or_default()
-                );
-                continue;
-            }
-
-            if let Err(e) = self.discover_new_markets().await {
-                error!("Failed to update Kraken Assets and Markets data: {:?}", e);
+    async fn discover_new_markets(&self) -> Result<()> {
+        // Collect assets and markets from the crypto exchange backing prices.
+        // Kraken listings are used for any other provider
+        let (assets, markets, listed) = match self.price_provider {
+            PriceProvider::Binance => {
+                self.providers
+                    .binance
+                    .fetch_assets(&self.market_data_repo)
+                    .await?
             }
-
-            let now = Utc::now();
-            if let Err(e) = self.misc_repo.set_cw_last_fetched(now).await {
-                error!("Failed to update last update time: {:?}", e);
+            _ => {
+                self.providers
+                    .kraken
+                    .fetch_assets(&self.market_data_repo)
+                    .await?
             }
-
-            // Reset next check timeout
-            sleep = tokio::time::sleep(Duration::from_secs(60));
-        }
-    }
-
-    async fn discover_new_markets(&self) -> Result<()> {
-        // Collect assets and markets from Kraken
-        let (assets, markets) = self
-            .providers
-            .kraken
-            .fetch_assets(&self.market_data_repo)
-            .await?;
+        };
 
         // Store assets in repository
         for a in assets {
@@ -113,7 +105,9 @@ impl MarketDiscoveryWorker {
         // Store markets in repository
         for mut m in markets {
             info!("Fetching price for market '{}'", m.id);
-            let Some(price) = fetch_market_price(&m, &self.providers, self.price_provider).await
+            let provider = m.price_provider(self.price_provider, self.fiat_provider);
+            let Some(price) =
+                fetch_market_price(&m, &self.providers, provider, &self.calendars).await
             else {
                 continue;
             };
@@ -132,11 +126,180 @@ impl MarketDiscoveryWorker {
         }
 
         self.market_data_service.invalidate_asset_cache();
+        self.market_data_service.invalidate_market_graph();
+
+        self.update_lifecycle(&listed).await
+    }
+
+    /// Create a market for each euro reference rate published by the ECB, so
+    /// that any pair of these currencies converts through the euro
+    async fn discover_fx_markets(&self) -> Result<()> {
+        let (date, rates) = self.providers.ecb.fetch_latest_rates().await?;
+        let Some(eur) = self.find_or_store_fiat("eur").await? else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let mut created = 0;
+        for (ccy, rate) in rates {
+            let ids = [format!("eur{ccy}"), format!("{ccy}eur")];
+            let known = self
+                .market_data_repo
+                .find_markets(&ids.iter().collect::<Vec<_>>())
+                .await?;
+            if known.iter().any(Option::is_some) {
+                continue;
+            }
+
+            let Some(quote) = self.find_or_store_fiat(&ccy).await? else {
+                continue;
+            };
+
+            let [id, _] = ids;
+            let m = Self::fx_market(id, eur.clone(), quote, Price::new(rate, now));
+            info!("Storing market '{}' (ECB rate of {date})", m.id);
+            self.market_data_repo.store_market(&m).await?;
+            created += 1;
+        }
+
+        if created > 0 {
+            self.market_data_service.invalidate_asset_cache();
+            self.market_data_service.invalidate_market_graph();
+        }
+
+        Ok(())
+    }
+
+    /// Market of an ECB reference rate. It is priced by the ECB, whatever the
+    /// configured fiat provider, and never listed by the exchange
+    fn fx_market(id: MarketId, eur: Asset, quote: Asset, price: Price) -> Market {
+        Market::new(id, eur, quote, Some(price)).with_provider(Some(PriceProvider::Ecb))
+    }
+
+    async fn find_or_store_fiat(&self, id: &str) -> Result<Option<Asset>> {
+        match self.market_data_repo.find_asset(&id.to_string()).await? {
+            Some(a @ Asset::Fiat(_)) => Ok(Some(a)),
+            Some(other) => {
+                warn!("Skipping ECB currency '{id}': asset id already taken by {other:?}");
+                Ok(None)
+            }
+            None => {
+                let name = currency_name(id)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| id.to_uppercase());
+                let a = Asset::Fiat(Fiat::new(id.to_string(), name));
+                info!("Storing asset '{id}'");
+                self.market_data_repo.store_asset(&a).await?;
+                Ok(Some(a))
+            }
+        }
+    }
+
+    /// Status of `m` among the markets `listed` by the exchange, or `None` if
+    /// it is priced by another provider, hence never listed by the exchange
+    fn listed_status(
+        m: &Market,
+        listed: &HashMap<MarketId, MarketStatus>,
+        price_provider: PriceProvider,
+        fiat_provider: PriceProvider,
+    ) -> Option<MarketStatus> {
+        if m.provider.is_some() || m.price_provider(price_provider, fiat_provider) != price_provider
+        {
+            return None;
+        }
+
+        Some(listed.get(&m.id).copied().unwrap_or(MarketStatus::Delisted))
+    }
+
+    /// Diff known markets against the ones `listed` by the exchange, updating
+    /// their status and purging the ones delisted for longer than
+    /// [`Self::PURGE_AFTER`]
+    async fn update_lifecycle(&self, listed: &HashMap<MarketId, MarketStatus>) -> Result<()> {
+        if listed.is_empty() {
+            warn!("Exchange listed no market. Skipping market lifecycle update");
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let mut changed = vec![];
+        for mut m in self.market_data_repo.load_markets().await? {
+            let Some(status) =
+                Self::listed_status(&m, listed, self.price_provider, self.fiat_provider)
+            else {
+                continue;
+            };
+
+            if status == MarketStatus::Delisted && m.is_purgeable(now, Self::PURGE_AFTER) {
+                info!(
+                    "Purging market '{}' (delisted since {:?})",
+                    m.id, m.status_since
+                );
+                if let Err(e) = self.market_data_repo.delete_market(&m.id).await {
+                    error!("Failed to purge market '{}': {e:?}", m.id);
+                    continue;
+                }
+            } else if m.update_status(status, now) {
+                info!("Market '{}' is now {}", m.id, m.status);
+                if let Err(e) = self.market_data_repo.update_market_status(&m).await {
+                    error!("Failed to update market '{}' status: {e:?}", m.id);
+                    continue;
+                }
+            } else {
+                continue;
+            }
+
+            changed.push(m.id);
+        }
+
+        if !changed.is_empty() {
+            self.market_data_service
+                .evict_markets(&changed.iter().collect::<Vec<_>>());
+        }
 
         Ok(())
     }
 }
 
+#[async_trait]
+impl Job for MarketDiscoveryWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(Self::CHECK_PERIOD)
+    }
+
+    async fn run(&self, run: RunContext) -> Result<()> {
+        // Pick up symbol mappings edited since last check
+        if let Err(e) = self.symbols.reload().await {
+            error!("Failed to reload symbol mappings: {:?}", e);
+        }
+
+        // Manual runs discover markets whatever the last discovery time
+        let (is_outdated, last_fetched_ts) = is_outdated(&self.misc_repo).await?;
+        if !is_outdated && run.trigger == JobTrigger::Schedule {
+            debug!(
+                "Crypto assets already fetched today ({})",
+                last_fetched_ts.map(|t| t.to_string()).unwrap_or_default()
+            );
+            return Ok(());
+        }
+
+        if let Err(e) = self.discover_new_markets().await {
+            error!("Failed to update crypto Assets and Markets data: {:?}", e);
+        }
+
+        if self.fiat_provider == PriceProvider::Ecb {
+            if let Err(e) = self.discover_fx_markets().await {
+                error!("Failed to update ECB fiat markets: {:?}", e);
+            }
+        }
+
+        self.misc_repo.set_cw_last_fetched(Utc::now()).await
+    }
+}
+
 async fn is_outdated(misc: &MiscRepository) -> Result<(bool, Option<DateTime>)> {
     let last_fetched = misc.get_cw_last_fetched().await?;
     if let Some(ts) = last_fetched {
@@ -147,3 +310,49 @@ async fn is_outdated(misc: &MiscRepository) -> Result<(bool, Option<DateTime>)>
 
     Ok((true, None))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::entity::Crypto;
+
+    #[test]
+    fn ecb_markets_stay_active_after_lifecycle_update() {
+        let now = Utc::now();
+        let eur = Asset::Fiat(Fiat::new("eur".into(), "Euro".into()));
+        let usd = Asset::Fiat(Fiat::new("usd".into(), "US Dollar".into()));
+        let btc = Asset::Crypto(Crypto::new_with_id("btc".into()));
+
+        // Discovered from the ECB rates
+        let fx = MarketDiscoveryWorker::fx_market(
+            "eurusd".into(),
+            eur,
+            usd.clone(),
+            Price::new(1.1, now),
+        );
+        // The exchange lists its own markets only
+        let listed = HashMap::from([("btceur".to_string(), MarketStatus::Active)]);
+
+        for fiat_provider in [PriceProvider::Ecb, PriceProvider::Kraken] {
+            let status = MarketDiscoveryWorker::listed_status(
+                &fx,
+                &listed,
+                PriceProvider::Kraken,
+                fiat_provider,
+            );
+            assert_eq!(status, None);
+        }
+        assert!(fx.is_active());
+
+        let unlisted = Market::new("btcusd".into(), btc, usd, None);
+        assert_eq!(
+            MarketDiscoveryWorker::listed_status(
+                &unlisted,
+                &listed,
+                PriceProvider::Kraken,
+                PriceProvider::Ecb
+            ),
+            Some(MarketStatus::Delisted)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    AppContext, DateTime,
+    app::{
+        domain::entity::{Asset, Equity, Market, MarketId},
+        infra::scheduler::{Job, RunContext, Schedule},
+        services::{
+            calendar::TradingCalendars, market_data::MarketDataService, symbols::SymbolRegistry,
+        },
+    },
+    config::PriceProvider,
+    error::Result,
+    ports::outbound::repository::{
+        market_data::MarketDataRepository,
+        portfolio::{PortfolioRepository, PortfolioSymbol},
+    },
+};
+
+/// Worker tracking the markets referenced by user portfolios and recent price
+/// requests. Portfolio equities get a market priced by Yahoo Finance, which
+/// [`PriceUpdaterWorker`] then keeps up to date. Markets not referenced for
+/// [`Self::RETENTION`] are untracked, and removed if they were created here.
+///
+/// [`PriceUpdaterWorker`]: super::price_updater::PriceUpdaterWorker
+pub struct MarketTrackerWorker {
+    market_data_service: Arc<MarketDataService>,
+    market_data_repo: Arc<MarketDataRepository>,
+    portfolio_repo: Arc<PortfolioRepository>,
+    symbols: Arc<SymbolRegistry>,
+    calendars: Arc<TradingCalendars>,
+}
+
+impl MarketTrackerWorker {
+    pub const NAME: &'static str = "marketTracker";
+    const PERIOD: Duration = Duration::from_secs(15 * 60);
+    /// Time a market stays tracked once no longer referenced
+    const RETENTION: chrono::Duration = chrono::Duration::days(7);
+
+    pub fn new(ctx: &AppContext) -> Self {
+        Self {
+            market_data_service: ctx.services.mkt_data.clone(),
+            market_data_repo: ctx.repos.mkt_data.clone(),
+            portfolio_repo: ctx.repos.portfolio.clone(),
+            symbols: ctx.services.symbols.clone(),
+            calendars: ctx.services.calendars.clone(),
+        }
+    }
+
+    /// Track the markets referenced by portfolios, along with the ones
+    /// requested within `period`, i.e. since the previous run
+    async fn track_markets(&self, period: Duration) -> Result<()> {
+        let now = Utc::now();
+
+        let mut referenced = HashSet::new();
+        let mut created = 0;
+        for s in self.portfolio_repo.find_portfolio_symbols().await? {
+            match self.portfolio_market(&s).await {
+                Ok(Some((id, is_new))) => {
+                    referenced.insert(id);
+                    created += is_new as usize;
+                }
+                Ok(None) => debug!("No market found for portfolio asset {s:?}"),
+                Err(e) => error!("Failed to track portfolio asset {s:?}: {e:?}"),
+            }
+        }
+
+        referenced.extend(
+            self.market_data_service
+                .requested_markets(period)
+                .into_keys(),
+        );
+
+        let ids = referenced.iter().collect::<Vec<_>>();
+        self.market_data_repo
+            .touch_tracked_markets(&ids, now)
+            .await?;
+        info!("Tracking {} markets ({created} new)", ids.len());
+
+        if created > 0 {
+            self.market_data_service.invalidate_asset_cache();
+            self.market_data_service.invalidate_market_graph();
+        }
+
+        self.collect_garbage(now).await
+    }
+
+    /// Find the market pricing a portfolio asset, creating it for equities
+    /// not tracked yet. Returns whether the market was created
+    async fn portfolio_market(&self, s: &PortfolioSymbol) -> Result<Option<(MarketId, bool)>> {
+        let quote_id = self.symbols.normalize(&s.currency.to_lowercase());
+
+        if !s.is_yahoo_equity() {
+            // Other assets are priced by discovered markets
+            let base_id = self.symbols.normalize(&s.symbol.to_lowercase());
+            let ids = [
+                format!("{base_id}{quote_id}"),
+                format!("{quote_id}{base_id}"),
+            ];
+            let markets = self
+                .market_data_repo
+                .find_markets(&ids.iter().collect::<Vec<_>>())
+                .await?;
+
+            return Ok(markets.into_iter().flatten().next().map(|m| (m.id, false)));
+        }
+
+        let base = Asset::Equity(Equity::new_with_ticker(&s.symbol));
+        let id = format!("{}{}", base.id(), quote_id);
+        if self.market_data_repo.find_market(&id).await?.is_some() {
+            return Ok(Some((id, false)));
+        }
+
+        match self.market_data_repo.find_asset(base.id()).await? {
+            Some(Asset::Equity(_)) | None => {}
+            Some(other) => {
+                warn!(
+                    "Cannot track equity '{}': asset id already taken by {other:?}",
+                    s.symbol
+                );
+                return Ok(None);
+            }
+        }
+
+        let Some(quote) = self.market_data_repo.find_asset(&quote_id).await? else {
+            warn!(
+                "Cannot track equity '{}': unknown currency {quote_id}",
+                s.symbol
+            );
+            return Ok(None);
+        };
+
+        let exchange = self
+            .calendars
+            .find_by_yahoo_equity(&s.symbol)
+            .map(|c| c.id.clone());
+        let market = Market::new(id.clone(), base, quote, None)
+            .with_exchange(exchange)
+            .with_provider(Some(PriceProvider::Yahoo));
+
+        info!("Tracking new market '{}' ({})", market.id, s.symbol);
+        self.market_data_repo.store_asset(&market.base).await?;
+        self.market_data_repo.store_market(&market).await?;
+
+        Ok(Some((id, true)))
+    }
+
+    /// Untrack markets no longer referenced, removing the equity markets
+    /// created for portfolios along with their assets
+    async fn collect_garbage(&self, now: DateTime) -> Result<()> {
+        let expired = self
+            .market_data_repo
+            .load_tracked_markets()
+            .await?
+            .into_iter()
+            .filter(|t| now - t.referenced_at > Self::RETENTION)
+            .map(|t| t.id)
+            .collect::<Vec<_>>();
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        let expired = expired.iter().collect::<Vec<_>>();
+        let markets = self.market_data_repo.find_markets(&expired).await?;
+
+        let mut removed = vec![];
+        for m in markets.iter().flatten() {
+            if !matches!(m.base, Asset::Equity(_)) {
+                continue;
+            }
+
+            info!("Removing market '{}': no longer referenced", m.id);
+            if let Err(e) = self.market_data_repo.delete_market(&m.id).await {
+                error!("Failed to remove market '{}': {e:?}", m.id);
+                continue;
+            }
+
+            removed.push(m);
+        }
+
+        if !removed.is_empty() {
+            // Drop equities not quoted by any other market
+            let quoted = self
+                .market_data_repo
+                .load_markets()
+                .await?
+                .into_iter()
+                .map(|m| m.base.id().clone())
+                .collect::<HashSet<_>>();
+
+            for m in &removed {
+                if !quoted.contains(m.base.id()) {
+                    self.market_data_repo.delete_asset(&m.base).await?;
+                }
+            }
+
+            self.market_data_service
+                .evict_markets(&removed.iter().map(|m| &m.id).collect::<Vec<_>>());
+            self.market_data_service.invalidate_asset_cache();
+        }
+
+        let n = self.market_data_repo.untrack_markets(&expired).await?;
+        info!("Untracked {n} markets ({} removed)", removed.len());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Job for MarketTrackerWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(Self::PERIOD)
+    }
+
+    async fn run(&self, run: RunContext) -> Result<()> {
+        // Requests are forgotten after their retention anyway
+        let period = run.period.min(MarketDataService::REQUESTED_RETENTION);
+        self.track_markets(period).await
+    }
+}
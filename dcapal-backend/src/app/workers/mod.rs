@@ -1,2 +1,7 @@
+pub mod corporate_actions;
+pub mod exchange_sync;
+pub mod jwks_refresh;
+pub mod kraken_ticker;
 pub mod market_discovery;
+pub mod market_tracker;
 pub mod price_updater;
@@ -1,86 +1,129 @@
-use std::{sync::Arc, time::Duration};
+use std::{cmp::Reverse, sync::Arc, time::Duration};
 
-use chrono::Utc;
+use async_trait::async_trait;
+use futures::StreamExt;
 use tracing::{error, info, warn};
 
 use crate::{
     AppContext,
     app::{
         domain::market_data_utils::fetch_market_price,
-        infra::utils::{StopToken, should_stop},
-        services::market_data::MarketDataService,
+        infra::{
+            scheduler::{Job, RunContext, Schedule},
+            utils::Expiring,
+        },
+        services::{calendar::TradingCalendars, market_data::MarketDataService},
     },
     config::PriceProvider,
     error::Result,
     ports::outbound::{adapter::PriceProviders, repository::market_data::MarketDataRepository},
 };
 
-/// Worker periodically updating market prices. As of today, prices are
-/// refreshed every 5 minutes.
+/// Worker periodically updating market prices. By default, prices are
+/// refreshed every 5 minutes, fetching up to `maxConcurrentFetches` at once.
+/// Provider rate limits are enforced by each price provider.
 pub struct PriceUpdaterWorker {
-    period: Duration,
     market_data_service: Arc<MarketDataService>,
     market_data_repo: Arc<MarketDataRepository>,
     price_provider: PriceProvider,
+    fiat_provider: PriceProvider,
     providers: Arc<PriceProviders>,
+    calendars: Arc<TradingCalendars>,
+    max_concurrent_fetches: usize,
 }
 
 impl PriceUpdaterWorker {
-    pub fn new(ctx: &AppContext, period: Duration) -> Self {
+    pub const NAME: &'static str = "priceUpdater";
+    const PERIOD: Duration = Duration::from_secs(5 * 60);
+    /// Markets requested within this period are updated first
+    const REQUESTED_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(ctx: &AppContext) -> Self {
         let market_data_service = ctx.services.mkt_data.clone();
         let market_data_repo = ctx.repos.mkt_data.clone();
         let price_provider = ctx.config.app.providers.price_provider;
+        let fiat_provider = ctx.config.app.providers.fiat_provider;
         let providers = ctx.providers.clone();
+        let calendars = ctx.services.calendars.clone();
+        let max_concurrent_fetches = ctx.config.app.providers.max_concurrent_fetches.max(1);
 
         Self {
-            period,
             market_data_service,
             market_data_repo,
             price_provider,
+            fiat_provider,
             providers,
-        }
-    }
-
-    pub async fn run(&self, mut stop_token: StopToken) {
-        let mut sleep = tokio::time::sleep(Duration::from_millis(50));
-        loop {
-            tokio::select! {
-                _ = sleep => {}
-                _ = should_stop(&mut stop_token) => break,
-            }
-
-            if let Err(e) = self.update_prices().await {
-                error!("Error occurred while updating prices: {e:?}");
-            }
-
-            sleep = tokio::time::sleep(self.period);
-            let next = Utc::now() + chrono::Duration::from_std(self.period).unwrap();
-            info!("Next PriceUpdaterWorker execution: {next}");
+            calendars,
+            max_concurrent_fetches,
         }
     }
 
     async fn update_prices(&self) -> Result<()> {
-        // Get all known markets
-        let markets = self.market_data_repo.load_markets().await?;
+        // Get all active markets, skipping the ones still fresh (e.g. kept up
+        // to date by a streaming feed or whose exchange is closed)
+        let mut markets = self
+            .market_data_repo
+            .load_markets()
+            .await?
+            .into_iter()
+            .filter(|m| m.is_active())
+            .filter(|m| m.price().is_none_or(|px| px.is_outdated()))
+            .collect::<Vec<_>>();
 
-        for mut m in markets {
-            let Some(price) = fetch_market_price(&m, &self.providers, self.price_provider).await
-            else {
-                warn!("Failed to fetch price update for market {}", m.id);
-                continue;
-            };
+        // Most recently requested markets first
+        self.market_data_service.prune_requested();
+        let requested = self
+            .market_data_service
+            .requested_markets(Self::REQUESTED_PERIOD);
+        markets.sort_by_key(|m| Reverse(requested.get(&m.id).copied()));
 
-            m.set_price(price);
-            if let Err(e) = self.market_data_repo.update_mkt_price(&m).await {
-                error!("Failed to store market price update {m:?}: {e:?}");
-            }
+        info!(
+            "Updating {} outdated market prices ({} recently requested)",
+            markets.len(),
+            markets
+                .iter()
+                .filter(|m| requested.contains_key(&m.id))
+                .count()
+        );
 
-            self.market_data_service.set_price(&m.id, price);
+        futures::stream::iter(markets)
+            .map(|m| async move {
+                let provider = m.price_provider(self.price_provider, self.fiat_provider);
+                let price =
+                    fetch_market_price(&m, &self.providers, provider, &self.calendars).await;
+                (m, price)
+            })
+            .buffer_unordered(self.max_concurrent_fetches)
+            .for_each(|(mut m, price)| async move {
+                let Some(price) = price else {
+                    warn!("Failed to fetch price update for market {}", m.id);
+                    return;
+                };
 
-            // Please the rate limiter
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }
+                m.set_price(price);
+                if let Err(e) = self.market_data_repo.update_mkt_price(&m).await {
+                    error!("Failed to store market price update {m:?}: {e:?}");
+                }
+
+                self.market_data_service.set_price(&m.id, price);
+            })
+            .await;
 
         Ok(())
     }
 }
+
+#[async_trait]
+impl Job for PriceUpdaterWorker {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(Self::PERIOD)
+    }
+
+    async fn run(&self, _: RunContext) -> Result<()> {
+        self.update_prices().await
+    }
+}
@@ -1,11 +1,17 @@
+use std::{collections::HashMap, net::SocketAddr};
+
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
+use crate::error::{DcaError, Result};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, strum_macros::Display)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, strum_macros::Display,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum PriceProvider {
+    Binance,
     CryptoWatch,
+    Ecb,
     Kraken,
     Yahoo,
 }
@@ -32,15 +38,265 @@ fn default_enable_stdout() -> bool {
 #[serde(rename_all = "camelCase")]
 pub struct Providers {
     pub price_provider: PriceProvider,
-    pub cw_api_key: String,
+    /// Provider pricing fiat/fiat markets, unless overridden by the market
+    #[serde(default = "default_fiat_provider")]
+    pub fiat_provider: PriceProvider,
+    /// CryptoWatch is only available if its API key is set
+    pub cw_api_key: Option<String>,
     pub ip_api_key: String,
     pub cmc_api_key: Option<String>,
+    /// Path to the symbol mappings file. Bundled mappings are used if unset
+    pub symbols_path: Option<String>,
+    /// Stream Kraken prices from its WebSocket ticker feed, polling REST API
+    /// only while the feed is down
+    #[serde(default)]
+    pub kraken_ticker_feed: bool,
+    /// Requests rate limit of each price provider
+    #[serde(default)]
+    pub rate_limits: HashMap<PriceProvider, RateLimit>,
+    /// Requests rate limit of Yahoo Finance symbol search, metered apart from
+    /// price fetches so that searches cannot starve price updates
+    #[serde(default = "default_search_rate_limit")]
+    pub search_rate_limit: RateLimit,
+    /// Maximum number of market prices fetched concurrently
+    #[serde(default = "default_max_concurrent_fetches")]
+    pub max_concurrent_fetches: usize,
+    /// Base URLs of the upstream APIs
+    #[serde(default)]
+    pub endpoints: Endpoints,
+    /// Serve recorded responses instead of calling upstream APIs
+    pub stub: Option<Stub>,
+}
+
+impl Providers {
+    pub fn rate_limit(&self, provider: PriceProvider) -> RateLimit {
+        self.rate_limits.get(&provider).cloned().unwrap_or_default()
+    }
+
+    fn validate(&self) -> Result<()> {
+        // ECB publishes fiat reference rates only, no crypto listing
+        if self.price_provider == PriceProvider::Ecb {
+            return Err(DcaError::Config(::config::ConfigError::Message(
+                "Invalid `priceProvider`: ECB can only be the `fiatProvider`".to_string(),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn default_fiat_provider() -> PriceProvider {
+    PriceProvider::Ecb
+}
+
+fn default_search_rate_limit() -> RateLimit {
+    RateLimit {
+        requests_per_second: 2.,
+        burst: 5,
+    }
+}
+
+fn default_max_concurrent_fetches() -> usize {
+    4
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoints {
+    #[serde(default = "default_kraken_endpoint")]
+    pub kraken: String,
+    #[serde(default = "default_kraken_ws_endpoint")]
+    pub kraken_ws: String,
+    #[serde(default = "default_binance_endpoint")]
+    pub binance: String,
+    #[serde(default = "default_cryptowatch_endpoint")]
+    pub cryptowatch: String,
+    #[serde(default = "default_coinmarketcap_endpoint")]
+    pub coinmarketcap: String,
+    #[serde(default = "default_ecb_endpoint")]
+    pub ecb: String,
+    #[serde(default = "default_yahoo_chart_endpoint")]
+    pub yahoo_chart: String,
+    #[serde(default = "default_yahoo_search_endpoint")]
+    pub yahoo_search: String,
+    #[serde(default = "default_ipapi_endpoint")]
+    pub ipapi: String,
+}
+
+impl Endpoints {
+    /// Endpoints of the stub server listening at `addr`, serving each
+    /// provider under its own path prefix
+    pub fn stub(addr: SocketAddr) -> Self {
+        let base_url = format!("http://{addr}");
+        Self {
+            kraken: format!("{base_url}/kraken"),
+            kraken_ws: format!("ws://{addr}/kraken-ws"),
+            binance: format!("{base_url}/binance"),
+            cryptowatch: format!("{base_url}/cryptowatch"),
+            coinmarketcap: format!("{base_url}/coinmarketcap"),
+            ecb: format!("{base_url}/ecb"),
+            yahoo_chart: format!("{base_url}/yahoo"),
+            yahoo_search: format!("{base_url}/yahoo"),
+            ipapi: format!("{base_url}/ipapi"),
+        }
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            kraken: default_kraken_endpoint(),
+            kraken_ws: default_kraken_ws_endpoint(),
+            binance: default_binance_endpoint(),
+            cryptowatch: default_cryptowatch_endpoint(),
+            coinmarketcap: default_coinmarketcap_endpoint(),
+            ecb: default_ecb_endpoint(),
+            yahoo_chart: default_yahoo_chart_endpoint(),
+            yahoo_search: default_yahoo_search_endpoint(),
+            ipapi: default_ipapi_endpoint(),
+        }
+    }
+}
+
+fn default_kraken_endpoint() -> String {
+    "https://api.kraken.com".to_string()
+}
+
+fn default_kraken_ws_endpoint() -> String {
+    "wss://ws.kraken.com".to_string()
+}
+
+fn default_binance_endpoint() -> String {
+    "https://api.binance.com".to_string()
+}
+
+fn default_cryptowatch_endpoint() -> String {
+    "https://api.cryptowat.ch".to_string()
+}
+
+fn default_coinmarketcap_endpoint() -> String {
+    "https://pro-api.coinmarketcap.com".to_string()
+}
+
+fn default_ecb_endpoint() -> String {
+    "https://www.ecb.europa.eu".to_string()
+}
+
+fn default_yahoo_chart_endpoint() -> String {
+    "https://query1.finance.yahoo.com".to_string()
+}
+
+fn default_yahoo_search_endpoint() -> String {
+    "https://query2.finance.yahoo.com".to_string()
+}
+
+fn default_ipapi_endpoint() -> String {
+    "http://api.ipapi.com".to_string()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stub {
+    /// Directory of recorded responses, laid out as
+    /// `<provider>/<request path>.json`
+    pub fixtures_path: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// Maximum number of requests sent at once after an idle period
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 10.,
+            burst: default_burst(),
+        }
+    }
+}
+
+fn default_burst() -> u32 {
+    1
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Auth {
-    pub jwt_secret: String,
+    /// Shared secret of HS256 tokens
+    pub jwt_secret: Option<String>,
+    /// Public keys of RS256 and ES256 tokens
+    pub jwks: Option<Jwks>,
+    /// Expected `iss` claim of tokens. Not checked if unset
+    pub issuer: Option<String>,
+    /// Accepted `aud` claims of tokens
+    #[serde(default = "default_audience")]
+    pub audience: Vec<String>,
+    /// Claims holding user details. Defaults to the layout of Supabase tokens
+    #[serde(default)]
+    pub claims: ClaimNames,
+}
+
+fn default_audience() -> Vec<String> {
+    vec!["authenticated".to_string()]
+}
+
+/// Dotted paths of the claims holding user details, e.g.
+/// `user_metadata.email`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimNames {
+    #[serde(default = "default_role_claim")]
+    pub role: String,
+    /// Role of callers whose token has no role claim, if any
+    pub default_role: Option<String>,
+    #[serde(default = "default_email_claim")]
+    pub email: String,
+    #[serde(default = "default_full_name_claim")]
+    pub full_name: String,
+}
+
+impl Default for ClaimNames {
+    fn default() -> Self {
+        Self {
+            role: default_role_claim(),
+            default_role: None,
+            email: default_email_claim(),
+            full_name: default_full_name_claim(),
+        }
+    }
+}
+
+fn default_role_claim() -> String {
+    "role".to_string()
+}
+
+fn default_email_claim() -> String {
+    "user_metadata.email".to_string()
+}
+
+fn default_full_name_claim() -> String {
+    "user_metadata.full_name".to_string()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Jwks {
+    /// URL of the JSON Web Key Set of the identity provider
+    pub url: Option<String>,
+    /// Path of a local JSON Web Key Set. Exclusive with `url`
+    pub path: Option<String>,
+    /// Seconds between two reloads of the key set
+    #[serde(default = "default_jwks_refresh_secs")]
+    pub refresh_secs: u64,
+}
+
+fn default_jwks_refresh_secs() -> u64 {
+    60 * 60
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,6 +309,46 @@ pub struct IpService {
 #[serde(rename_all = "camelCase")]
 pub struct Services {
     pub ip: Option<IpService>,
+    pub market_data: Option<MarketData>,
+    /// Exchange account connectors are disabled if unset
+    pub connectors: Option<Connectors>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connectors {
+    /// Base64 encoded 256-bit key encrypting exchange API credentials at rest
+    pub encryption_key: String,
+    /// Seconds between two syncs of the linked exchange accounts
+    #[serde(default = "default_connectors_sync_period_secs")]
+    pub sync_period_secs: u64,
+}
+
+fn default_connectors_sync_period_secs() -> u64 {
+    60 * 60
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketData {
+    /// Maximum number of markets crossed to compute a conversion rate
+    #[serde(default = "default_max_conversion_hops")]
+    pub max_conversion_hops: usize,
+    /// Path to the trading calendars file. Bundled calendars are used if unset
+    pub calendars_path: Option<String>,
+}
+
+impl Default for MarketData {
+    fn default() -> Self {
+        Self {
+            max_conversion_hops: default_max_conversion_hops(),
+            calendars_path: None,
+        }
+    }
+}
+
+fn default_max_conversion_hops() -> usize {
+    3
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -61,6 +357,28 @@ pub struct Application {
     pub providers: Providers,
     pub services: Option<Services>,
     pub auth: Auth,
+    /// Schedule overrides of background jobs, keyed by job name
+    #[serde(default)]
+    pub jobs: HashMap<String, Job>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    /// Seconds between the end of a run and the start of the next one
+    pub every_secs: Option<u64>,
+    /// Cron expression of the runs (`minute hour day-of-month month
+    /// day-of-week`, in UTC). Exclusive with `everySecs`
+    pub cron: Option<String>,
+    /// Maximum random delay added to each scheduled run
+    #[serde(default)]
+    pub jitter_secs: u64,
+    #[serde(default = "default_job_enabled")]
+    pub enabled: bool,
+}
+
+fn default_job_enabled() -> bool {
+    true
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -98,9 +416,19 @@ impl Postgres {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Web {
     pub hostname: String,
     pub port: u32,
+    /// Time granted on shutdown to drain in-flight requests and let workers
+    /// complete their current run, together. Keep it below the grace period
+    /// of the orchestrator, e.g. 30s on Kubernetes
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    25
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -129,6 +457,32 @@ impl Config {
             .add_source(config::File::with_name("dcapal.yml").format(config::FileFormat::Yaml))
             .build()?;
 
-        Ok(s.try_deserialize()?)
+        let config: Self = s.try_deserialize()?;
+        config.app.providers.validate()?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecb_cannot_be_the_price_provider() {
+        let providers = |price_provider: &str| -> Providers {
+            serde_json::from_value(serde_json::json!({
+                "priceProvider": price_provider,
+                "fiatProvider": "ecb",
+                "ipApiKey": "",
+            }))
+            .unwrap()
+        };
+
+        assert!(providers("kraken").validate().is_ok());
+        assert!(matches!(
+            providers("ecb").validate(),
+            Err(DcaError::Config(_))
+        ));
     }
 }
@@ -6,6 +6,7 @@ use deadpool_redis::PoolError;
 use hyper::StatusCode;
 use redis::RedisError;
 use tracing::error;
+use uuid::Uuid;
 
 use crate::app::domain::entity::{AssetId, MarketId};
 
@@ -15,12 +16,26 @@ pub enum DcaError {
     Generic(String),
     #[error("Bad Request: {0}")]
     BadRequest(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
     #[error("Price for market '{0}/{1}' not available")]
     PriceNotAvailable(AssetId, AssetId),
     #[error("Price for market '{0}' not available")]
     PriceNotAvailableId(MarketId),
     #[error("Market '{0}' not found")]
     MarketNotFound(MarketId),
+    #[error("Symbol '{0}' not found")]
+    SymbolNotFound(String),
+    #[error("Portfolio '{0}' not found")]
+    PortfolioNotFound(Uuid),
+    #[error("Portfolio '{0}' belongs to another user")]
+    PortfolioForbidden(Uuid),
+    #[error("Exchange connection '{0}' not found")]
+    ConnectionNotFound(Uuid),
+    #[error("Job '{0}' not found")]
+    JobNotFound(String),
     #[error("Failed to store in Repository: {0}")]
     RepositoryStoreFailure(String),
     #[error("External service died: {0}")]
@@ -33,8 +48,16 @@ pub enum DcaError {
     InvalidLogPath(String),
     #[error("Invalid log file path: {0}")]
     InvalidLogPath2(String, #[source] std::io::Error),
+    #[error("Invalid symbol mappings file path: {0}")]
+    InvalidSymbolsPath(String, #[source] std::io::Error),
+    #[error("Invalid trading calendars file path: {0}")]
+    InvalidCalendarsPath(String, #[source] std::io::Error),
+    #[error("Invalid JWKS file path: {0}")]
+    InvalidJwksPath(String, #[source] std::io::Error),
     #[error("Failed to deserialized into {1}: {0}")]
     JsonDeserializationFailure(String, String, #[source] serde_json::Error),
+    #[error("Failed to serialize {0}")]
+    JsonSerializationFailure(String, #[source] serde_json::Error),
     #[error("Failed to obtain Redis connection")]
     RedisPool(#[from] PoolError),
     #[error(transparent)]
@@ -55,6 +78,8 @@ pub enum DcaError {
     DatabaseError(#[from] sea_orm::error::DbErr),
     #[error("Third-party API reqwest failed")]
     Rquest(#[from] rquest::Error),
+    #[error("Third-party WebSocket connection failed")]
+    WebSocket(#[source] Box<tokio_tungstenite::tungstenite::Error>),
 }
 
 impl Debug for DcaError {
@@ -79,7 +104,7 @@ impl From<sea_orm::TransactionError<DcaError>> for DcaError {
 }
 
 impl DcaError {
-    pub fn iter_sources(&self) -> ErrorIter {
+    pub fn iter_sources(&self) -> ErrorIter<'_> {
         ErrorIter {
             current: (self as &dyn std::error::Error).source(),
         }
@@ -102,7 +127,18 @@ impl IntoResponse for DcaError {
             DcaError::BadRequest(_) => {
                 (StatusCode::BAD_REQUEST, format!("{}", self)).into_response()
             }
-            DcaError::PriceNotAvailable(_, _) => {
+            DcaError::Unauthorized(_) => {
+                (StatusCode::UNAUTHORIZED, format!("{}", self)).into_response()
+            }
+            DcaError::Forbidden(_) | DcaError::PortfolioForbidden(_) => {
+                (StatusCode::FORBIDDEN, format!("{}", self)).into_response()
+            }
+            DcaError::PriceNotAvailable(_, _)
+            | DcaError::MarketNotFound(_)
+            | DcaError::SymbolNotFound(_)
+            | DcaError::PortfolioNotFound(_)
+            | DcaError::ConnectionNotFound(_)
+            | DcaError::JobNotFound(_) => {
                 (StatusCode::NOT_FOUND, format!("{}", self)).into_response()
             }
             _ => (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response(),
@@ -130,3 +166,9 @@ impl From<ip2location::error::Error> for DcaError {
         DcaError::Ip2Location(e)
     }
 }
+
+impl From<tokio_tungstenite::tungstenite::Error> for DcaError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        DcaError::WebSocket(Box::new(e))
+    }
+}
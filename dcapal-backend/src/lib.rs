@@ -3,19 +3,22 @@ extern crate const_format;
 
 use std::{
     net::{AddrParseError, SocketAddr},
-    sync::Arc,
-    time::Duration,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
 };
 
 use axum::{
     Router,
     extract::connect_info::IntoMakeServiceWithConnectInfo,
     middleware,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
 use chrono::prelude::*;
 use deadpool_redis::{Pool, Runtime};
-use futures::future::BoxFuture;
+use futures::{
+    FutureExt,
+    future::{BoxFuture, join_all},
+};
 use hyper::header;
 use metrics::{Unit, counter, describe_counter, describe_histogram};
 use sea_orm::{
@@ -25,27 +28,46 @@ use sea_orm::{
 use tokio::{net::TcpListener, task::JoinHandle};
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     app::{
-        infra,
+        infra::{
+            self,
+            auth::{self, Authorization, Permission},
+            cipher::CredentialCipher,
+            heartbeat::WorkerHeartbeats,
+            jwt::JwtVerifier,
+            scheduler::Scheduler,
+            utils::StopToken,
+        },
         services::{
-            ip2location::Ip2LocationService, market_data::MarketDataService,
-            portfolio::PortfolioService,
+            admin::AdminService, calendar::TradingCalendars, chart::ChartService,
+            connector::ConnectorService, corporate_actions::CorporateActionService,
+            health::HealthService, ip2location::Ip2LocationService, market_data::MarketDataService,
+            portfolio::PortfolioService, price_history::PriceHistoryService,
+            search::AssetSearchService, symbols::SymbolRegistry,
+        },
+        workers::{
+            corporate_actions::CorporateActionsWorker, exchange_sync::ExchangeSyncWorker,
+            jwks_refresh::JwksRefreshWorker, kraken_ticker::KrakenTickerWorker,
+            market_discovery::MarketDiscoveryWorker, market_tracker::MarketTrackerWorker,
+            price_updater::PriceUpdaterWorker,
         },
-        workers::{market_discovery::MarketDiscoveryWorker, price_updater::PriceUpdaterWorker},
     },
-    config::{Config, Postgres},
+    config::{Config, Endpoints, Postgres, PriceProvider},
     error::{DcaError, Result},
     ports::{
         inbound::rest,
         outbound::{
-            adapter::{CryptoWatchProvider, IpApi, KrakenProvider, PriceProviders, YahooProvider},
+            adapter::{
+                BinanceProvider, CryptoWatchProvider, EcbProvider, IpApi, KrakenAccountConnector,
+                KrakenProvider, PriceProviders, StubServer, YahooProvider,
+            },
             repository::{
-                ImportedRepository, MiscRepository, StatsRepository,
-                market_data::MarketDataRepository, portfolio::PortfolioRepository,
-                user::UserRepository,
+                CacheRepository, ImportedRepository, MiscRepository, StatsRepository,
+                connection::ConnectionRepository, market_data::MarketDataRepository,
+                portfolio::PortfolioRepository, symbol::SymbolRepository, user::UserRepository,
             },
         },
     },
@@ -72,15 +94,28 @@ pub struct AppContextInner {
     services: Services,
     repos: Arc<Repository>,
     providers: Arc<PriceProviders>,
+    /// Signalled on shutdown
+    stop_token: StopToken,
 }
 
 pub type AppContext = Arc<AppContextInner>;
 
 #[derive(Clone)]
 struct Services {
+    admin: Arc<AdminService>,
     mkt_data: Arc<MarketDataService>,
+    price_history: Arc<PriceHistoryService>,
+    chart: Arc<ChartService>,
+    search: Arc<AssetSearchService>,
+    corporate_actions: Arc<CorporateActionService>,
     ip2location: Option<Arc<Ip2LocationService>>,
+    connectors: Option<Arc<ConnectorService>>,
     portfolio: Arc<PortfolioService>,
+    symbols: Arc<SymbolRegistry>,
+    calendars: Arc<TradingCalendars>,
+    health: Arc<HealthService>,
+    scheduler: Arc<Scheduler>,
+    jwt: Arc<JwtVerifier>,
 }
 
 #[derive(Clone)]
@@ -89,7 +124,10 @@ struct Repository {
     pub mkt_data: Arc<MarketDataRepository>,
     pub stats: Arc<StatsRepository>,
     pub imported: Arc<ImportedRepository>,
+    pub cache: Arc<CacheRepository>,
     pub portfolio: Arc<PortfolioRepository>,
+    pub connection: Arc<ConnectionRepository>,
+    pub symbol: Arc<SymbolRepository>,
     pub user: Arc<UserRepository>,
 }
 
@@ -102,106 +140,27 @@ pub struct DcaServer {
 }
 
 impl DcaServer {
-    pub async fn try_new(config: Config) -> Result<Self> {
-        let config = Arc::new(config);
-
-        let http = reqwest::Client::builder()
-            .gzip(true)
-            .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(10))
-            .build()?;
-
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            rquest::header::USER_AGENT,
-            header::HeaderValue::from_static(
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
-                                  AppleWebKit/537.36 (KHTML, like Gecko) \
-                                  Chrome/122.0.0.0 Safari/537.36",
-            ),
-        );
+    pub async fn try_new(mut config: Config) -> Result<Self> {
+        if let Some(ref stub) = config.app.providers.stub {
+            let addr = StubServer::start(&stub.fixtures_path).await?;
+            config.app.providers.endpoints = Endpoints::stub(addr);
+        }
 
-        let rquest = rquest::Client::builder().default_headers(headers).build()?;
+        if config.app.providers.price_provider == PriceProvider::CryptoWatch
+            && config.app.providers.cw_api_key.is_none()
+        {
+            return Err(DcaError::StartupFailure(
+                "Invalid price provider".into(),
+                anyhow::anyhow!("CryptoWatch requires `cwApiKey`"),
+            ));
+        }
 
+        let config = Arc::new(config);
         let redis = build_redis_pool(&config.server.redis)?;
-
         let postgres = build_postgres_pool(&config.server.postgres).await?;
 
-        let repos = Arc::new(Repository {
-            misc: Arc::new(MiscRepository::new(redis.clone())),
-            mkt_data: Arc::new(MarketDataRepository::new(redis.clone())),
-            stats: Arc::new(StatsRepository::new(redis.clone())),
-            imported: Arc::new(ImportedRepository::new(redis.clone())),
-            portfolio: Arc::new(PortfolioRepository::new(postgres.clone())),
-            user: Arc::new(UserRepository::new(postgres.clone())),
-        });
-
-        let providers = Arc::new(PriceProviders {
-            cw: Arc::new(CryptoWatchProvider::new(
-                http.clone(),
-                &config.app.providers,
-            )),
-            kraken: Arc::new(KrakenProvider::new(http.clone(), &config.app.providers)),
-            yahoo: Arc::new(YahooProvider::new(rquest.clone())),
-            ipapi: Arc::new(IpApi::new(http.clone(), &config.app.providers)),
-        });
-
-        let ip2location = {
-            if let Some(ref service_config) = config.app.services {
-                if let Some(ref ip_config) = service_config.ip {
-                    Some(Arc::new(Ip2LocationService::try_new(&ip_config.db_path)?))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        };
-
-        let services = Services {
-            mkt_data: Arc::new(MarketDataService::new(repos.mkt_data.clone())),
-            ip2location,
-            portfolio: Arc::new(PortfolioService::new(repos.portfolio.clone())),
-        };
-
-        let ctx = Arc::new(AppContextInner {
-            config: config.clone(),
-            http,
-            redis,
-            postgres,
-            services,
-            repos,
-            providers,
-        });
-
-        let open_routes = Router::new()
-            .route("/", get(|| async { "Greetings from DCA-Pal APIs!" }))
-            .route("/assets/fiat", get(rest::get_assets_fiat))
-            .route("/assets/crypto", get(rest::get_assets_crypto))
-            .route("/assets/search", get(rest::get_assets_data))
-            .route("/assets/chart/{symbol}", get(rest::get_assets_chart))
-            .route("/price/{asset}", get(rest::get_price))
-            .route("/import/portfolio", post(rest::import_portfolio))
-            .route("/import/portfolio/{id}", get(rest::get_imported_portfolio));
-
-        let authenticated_routes = Router::new()
-            .route("/v1/sync/portfolios", post(rest::request::sync_portfolios))
-            .with_state(ctx.clone());
-
-        let merged_app = Router::new().merge(open_routes).merge(authenticated_routes);
-
-        let app = merged_app
-            .route_layer(
-                ServiceBuilder::new()
-                    .layer(TraceLayer::new_for_http())
-                    .layer(middleware::from_fn_with_state(
-                        ctx.clone(),
-                        infra::stats::requests_stats,
-                    ))
-                    .layer(middleware::from_fn(infra::stats::latency_stats)),
-            )
-            .with_state(ctx.clone())
-            .into_make_service_with_connect_info();
+        let (ctx, stop_tx) = build_context(config.clone(), redis, postgres).await?;
+        let app = router(&ctx).into_make_service_with_connect_info();
 
         let (hostname, port) = (&config.server.web.hostname, config.server.web.port);
         let addr = format!("{}:{}", hostname, port)
@@ -213,7 +172,6 @@ impl DcaServer {
                 )
             })?;
 
-        let (stop_tx, _) = tokio::sync::watch::channel(false);
         Ok(Self {
             addr,
             app,
@@ -223,27 +181,71 @@ impl DcaServer {
         })
     }
 
-    pub async fn start(&mut self, _signal_handler: BoxFuture<'_, ()>) -> Result<()> {
+    /// Serve requests until `signal_handler` completes, then drain in-flight
+    /// requests and stop workers
+    pub async fn start(&mut self, signal_handler: BoxFuture<'static, ()>) -> Result<()> {
         info!("Initializing metrics");
         self.init_metrics().await;
 
-        info!("Starting MarketDiscovery worker");
+        let scheduler = self.ctx.services.scheduler.clone();
+        let stop_rx = || self.stop_tx.subscribe();
+        let startup_failure =
+            |e: DcaError| DcaError::StartupFailure("Invalid job schedule".into(), e.into());
+
+        scheduler
+            .check_config(&[
+                MarketDiscoveryWorker::NAME,
+                PriceUpdaterWorker::NAME,
+                MarketTrackerWorker::NAME,
+                CorporateActionsWorker::NAME,
+                ExchangeSyncWorker::NAME,
+                JwksRefreshWorker::NAME,
+            ])
+            .map_err(|e| DcaError::StartupFailure("Invalid jobs config".into(), e.into()))?;
+
+        let mut jobs = vec![
+            scheduler.spawn(MarketDiscoveryWorker::new(&self.ctx), stop_rx()),
+            scheduler.spawn(PriceUpdaterWorker::new(&self.ctx), stop_rx()),
+            scheduler.spawn(MarketTrackerWorker::new(&self.ctx), stop_rx()),
+            scheduler.spawn(CorporateActionsWorker::new(&self.ctx), stop_rx()),
+        ];
+
+        let connectors_config = self
+            .ctx
+            .config
+            .app
+            .services
+            .as_ref()
+            .and_then(|s| s.connectors.as_ref());
+        if let (Some(service), Some(connectors_config)) =
+            (self.ctx.services.connectors.clone(), connectors_config)
         {
-            let ctx = self.ctx.clone();
-            let stop_rx = self.stop_tx.subscribe();
-            let handle = tokio::spawn(async move {
-                let worker = MarketDiscoveryWorker::new(&ctx);
-                worker.run(stop_rx).await;
-            });
-            self.worker_handlers.push(handle);
+            let period = Duration::from_secs(connectors_config.sync_period_secs);
+            jobs.push(scheduler.spawn(ExchangeSyncWorker::new(service, period), stop_rx()));
+        }
+
+        if let Some(period) = self.ctx.services.jwt.refresh_period() {
+            let worker = JwksRefreshWorker::new(self.ctx.services.jwt.clone(), period);
+            jobs.push(scheduler.spawn(worker, stop_rx()));
+        }
+
+        for job in jobs {
+            if let Some(handle) = job.map_err(startup_failure)? {
+                self.worker_handlers.push(handle);
+            }
         }
 
-        info!("Starting PriceUpdater worker");
+        let providers_config = &self.ctx.config.app.providers;
+        // Recorded fixtures cannot stream prices
+        if providers_config.kraken_ticker_feed
+            && providers_config.price_provider == PriceProvider::Kraken
+            && providers_config.stub.is_none()
         {
+            info!("Starting KrakenTicker worker");
             let ctx = self.ctx.clone();
             let stop_rx = self.stop_tx.subscribe();
             let handle = tokio::spawn(async move {
-                let worker = PriceUpdaterWorker::new(&ctx, Duration::from_secs(5 * 60));
+                let mut worker = KrakenTickerWorker::new(&ctx);
                 worker.run(stop_rx).await;
             });
             self.worker_handlers.push(handle);
@@ -254,11 +256,65 @@ impl DcaServer {
             .await
             .map_err(|e| DcaError::StartupFailure("Failed to start DcaServer".into(), e.into()))?;
 
-        axum::serve(listener, self.app.clone())
-            .await
-            .map_err(|e| DcaError::StartupFailure("Failed to start DcaServer".into(), e.into()))?;
+        // Workers are signalled along with the request drain, so both share
+        // the shutdown timeout
+        let timeout = Duration::from_secs(self.ctx.config.server.web.shutdown_timeout_secs);
+        let shutdown_at = Arc::new(OnceLock::new());
+        let signal_handler = {
+            let shutdown_at = shutdown_at.clone();
+            async move {
+                signal_handler.await;
+                let _ = shutdown_at.set(Instant::now());
+            }
+            .boxed()
+        };
+
+        let served = serve(
+            listener,
+            self.app.clone(),
+            signal_handler,
+            self.stop_tx.clone(),
+            timeout,
+        )
+        .await
+        .map_err(|e| DcaError::StartupFailure("Failed to start DcaServer".into(), e.into()));
+
+        let deadline = shutdown_at.get().copied().unwrap_or_else(Instant::now) + timeout;
+        self.stop(deadline).await;
 
-        Ok(())
+        served
+    }
+
+    /// Stop workers, waiting until `deadline` for their current run to
+    /// complete, and release storage connections
+    pub async fn stop(&mut self, deadline: Instant) {
+        info!("Stopping {} workers", self.worker_handlers.len());
+        // Workers check for stop between runs, so none is interrupted mid-write
+        let _ = self.stop_tx.send(true);
+
+        let handlers = std::mem::take(&mut self.worker_handlers);
+        let aborts = handlers
+            .iter()
+            .map(|h| h.abort_handle())
+            .collect::<Vec<_>>();
+        match tokio::time::timeout_at(deadline.into(), join_all(handlers)).await {
+            Ok(results) => {
+                for res in results {
+                    if let Err(e) = res {
+                        error!("Worker terminated abnormally: {e:?}");
+                    }
+                }
+            }
+            Err(_) => {
+                let pending = aborts.iter().filter(|h| !h.is_finished()).count();
+                warn!("Aborting {pending} workers still running at shutdown timeout");
+                aborts.iter().for_each(|h| h.abort());
+            }
+        }
+
+        self.ctx.redis.close();
+        self.ctx.postgres.close().await;
+        info!("DcaServer stopped");
     }
 
     pub async fn init_metrics(&self) {
@@ -282,6 +338,16 @@ impl DcaServer {
             Unit::Count,
             "Number of portfolios imported"
         );
+        describe_counter!(
+            infra::stats::JOB_RUNS_TOTAL,
+            Unit::Count,
+            "Number of background job runs, by outcome"
+        );
+        describe_histogram!(
+            infra::stats::JOB_DURATION_SUMMARY,
+            Unit::Seconds,
+            "Summary of background job run time"
+        );
 
         // Refresh Prometheus stats
         if let Err(e) = refresh_total_visitors_stats(&self.ctx.repos.stats).await {
@@ -299,6 +365,372 @@ impl DcaServer {
     }
 }
 
+/// Build the services and repositories backing the server on top of storage
+/// pools. Returns the context along with the sender signalling shutdown
+async fn build_context(
+    config: Arc<Config>,
+    redis: Pool,
+    postgres: PgPool,
+) -> Result<(AppContext, tokio::sync::watch::Sender<bool>)> {
+    let http = reqwest::Client::builder()
+        .gzip(true)
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(10))
+        .build()?;
+
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        rquest::header::USER_AGENT,
+        header::HeaderValue::from_static(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
+                              AppleWebKit/537.36 (KHTML, like Gecko) \
+                              Chrome/122.0.0.0 Safari/537.36",
+        ),
+    );
+
+    let rquest = rquest::Client::builder().default_headers(headers).build()?;
+
+    let jwt = Arc::new(
+        JwtVerifier::new(http.clone(), &config.app.auth)
+            .map_err(|e| DcaError::StartupFailure("Invalid auth config".into(), e.into()))?,
+    );
+    if jwt.refresh_period().is_some() {
+        let loaded = jwt
+            .refresh_keys()
+            .await
+            .map_err(|e| DcaError::StartupFailure("Failed to load JWKS".into(), e.into()))?;
+        info!("Loaded {loaded} JWKS signing keys");
+    }
+
+    let repos = Arc::new(Repository {
+        misc: Arc::new(MiscRepository::new(redis.clone())),
+        mkt_data: Arc::new(MarketDataRepository::new(redis.clone())),
+        stats: Arc::new(StatsRepository::new(redis.clone())),
+        imported: Arc::new(ImportedRepository::new(redis.clone())),
+        cache: Arc::new(CacheRepository::new(redis.clone())),
+        portfolio: Arc::new(PortfolioRepository::new(postgres.clone())),
+        connection: Arc::new(ConnectionRepository::new(postgres.clone())),
+        symbol: Arc::new(SymbolRepository::new(postgres.clone())),
+        user: Arc::new(UserRepository::new(postgres.clone())),
+    });
+
+    let symbols = Arc::new(SymbolRegistry::new(repos.symbol.clone()));
+    symbols
+        .import(config.app.providers.symbols_path.as_deref())
+        .await
+        .map_err(|e| {
+            DcaError::StartupFailure("Failed to import symbol mappings".into(), e.into())
+        })?;
+
+    let providers = Arc::new(PriceProviders {
+        binance: Arc::new(BinanceProvider::new(
+            http.clone(),
+            &config.app.providers,
+            symbols.clone(),
+        )),
+        cw: config.app.providers.cw_api_key.as_ref().map(|api_key| {
+            Arc::new(CryptoWatchProvider::new(
+                http.clone(),
+                api_key.clone(),
+                &config.app.providers,
+                symbols.clone(),
+            ))
+        }),
+        ecb: Arc::new(EcbProvider::new(http.clone(), &config.app.providers)),
+        kraken: Arc::new(KrakenProvider::new(
+            http.clone(),
+            &config.app.providers,
+            symbols.clone(),
+        )),
+        yahoo: Arc::new(YahooProvider::new(
+            rquest.clone(),
+            &config.app.providers,
+            symbols.clone(),
+        )),
+        ipapi: Arc::new(IpApi::new(http.clone(), &config.app.providers)),
+    });
+
+    let ip2location = {
+        if let Some(ref service_config) = config.app.services {
+            if let Some(ref ip_config) = service_config.ip {
+                Some(Arc::new(Ip2LocationService::try_new(&ip_config.db_path)?))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    let mkt_data_config = config
+        .app
+        .services
+        .as_ref()
+        .and_then(|s| s.market_data.clone())
+        .unwrap_or_default();
+
+    let calendars = Arc::new(
+        TradingCalendars::load(mkt_data_config.calendars_path.as_deref()).map_err(|e| {
+            DcaError::StartupFailure("Failed to load trading calendars".into(), e.into())
+        })?,
+    );
+
+    let mkt_data = Arc::new(MarketDataService::new(
+        repos.mkt_data.clone(),
+        symbols.clone(),
+        &mkt_data_config,
+    ));
+
+    let price_history = Arc::new(PriceHistoryService::new(
+        repos.mkt_data.clone(),
+        mkt_data.clone(),
+        symbols.clone(),
+        providers.clone(),
+        config.app.providers.price_provider,
+        config.app.providers.fiat_provider,
+        &mkt_data_config,
+    ));
+
+    let chart = Arc::new(ChartService::new(
+        providers.yahoo.clone(),
+        repos.cache.clone(),
+    ));
+
+    let search = Arc::new(AssetSearchService::new(
+        repos.mkt_data.clone(),
+        symbols.clone(),
+        chart.clone(),
+    ));
+
+    let corporate_actions = Arc::new(CorporateActionService::new(
+        providers.yahoo.clone(),
+        repos.mkt_data.clone(),
+        repos.portfolio.clone(),
+    ));
+
+    let connectors = match config
+        .app
+        .services
+        .as_ref()
+        .and_then(|s| s.connectors.as_ref())
+    {
+        Some(connectors_config) => {
+            let cipher = CredentialCipher::new(&connectors_config.encryption_key).map_err(|e| {
+                DcaError::StartupFailure("Invalid connectors encryption key".into(), e.into())
+            })?;
+            Some(Arc::new(ConnectorService::new(
+                repos.connection.clone(),
+                repos.portfolio.clone(),
+                repos.mkt_data.clone(),
+                symbols.clone(),
+                cipher,
+                Arc::new(KrakenAccountConnector::new(
+                    http.clone(),
+                    &config.app.providers,
+                )),
+            )))
+        }
+        None => None,
+    };
+
+    let heartbeats = Arc::new(WorkerHeartbeats::new());
+    let health = Arc::new(HealthService::new(
+        redis.clone(),
+        postgres.clone(),
+        providers.clone(),
+        heartbeats.clone(),
+        repos.mkt_data.clone(),
+    ));
+    let scheduler = Arc::new(Scheduler::new(config.app.jobs.clone(), heartbeats));
+
+    let admin = Arc::new(AdminService::new(
+        mkt_data.clone(),
+        repos.mkt_data.clone(),
+        symbols.clone(),
+        providers.clone(),
+        calendars.clone(),
+        &config.app.providers,
+    ));
+
+    let services = Services {
+        admin,
+        mkt_data,
+        price_history,
+        chart,
+        search,
+        corporate_actions,
+        ip2location,
+        connectors,
+        portfolio: Arc::new(PortfolioService::new(repos.portfolio.clone())),
+        symbols,
+        calendars,
+        health,
+        scheduler,
+        jwt,
+    };
+
+    let (stop_tx, stop_token) = tokio::sync::watch::channel(false);
+
+    let ctx = Arc::new(AppContextInner {
+        config: config.clone(),
+        http,
+        redis,
+        postgres,
+        services,
+        repos,
+        providers,
+        stop_token,
+    });
+
+    Ok((ctx, stop_tx))
+}
+
+/// Routes of the server. Each authenticated route declares the permission it
+/// requires
+fn router(ctx: &AppContext) -> Router<()> {
+    let open_routes = Router::new()
+        .route("/", get(|| async { "Greetings from DCA-Pal APIs!" }))
+        .route("/health/live", get(rest::get_health_live))
+        .route("/health/ready", get(rest::get_health_ready))
+        .route("/assets/fiat", get(rest::get_assets_fiat))
+        .route("/assets/crypto", get(rest::get_assets_crypto))
+        .route("/assets/search", get(rest::get_assets_data))
+        .route("/assets/chart/{symbol}", get(rest::get_assets_chart))
+        .route("/markets/{id}", get(rest::get_market))
+        .route("/price/{asset}", get(rest::get_price))
+        .route("/price/{asset}/history", get(rest::get_historical_price))
+        .route("/prices", get(rest::get_prices))
+        .route("/prices/stream", get(rest::stream_prices))
+        .route("/import/portfolio", post(rest::import_portfolio))
+        .route("/import/portfolio/{id}", get(rest::get_imported_portfolio));
+
+    let require = |permission| {
+        middleware::from_fn_with_state(Authorization::new(ctx.clone(), permission), auth::authorize)
+    };
+    let authenticated_routes = Router::new()
+        .route(
+            "/v1/sync/portfolios",
+            post(rest::request::sync_portfolios).route_layer(require(Permission::ManagePortfolios)),
+        )
+        .route(
+            "/v1/portfolios/{id}/cash-flows",
+            get(rest::request::get_cash_flows).route_layer(require(Permission::ManagePortfolios)),
+        )
+        .route(
+            "/v1/connections",
+            get(rest::request::get_connections)
+                .post(rest::request::link_connection)
+                .route_layer(require(Permission::ManagePortfolios)),
+        )
+        .route(
+            "/v1/connections/{id}",
+            delete(rest::request::delete_connection)
+                .route_layer(require(Permission::ManagePortfolios)),
+        )
+        .route(
+            "/v1/connections/{id}/sync",
+            post(rest::request::sync_connection).route_layer(require(Permission::ManagePortfolios)),
+        )
+        .route(
+            "/admin/assets",
+            get(rest::admin::get_assets).route_layer(require(Permission::ReadAdmin)),
+        )
+        .route(
+            "/admin/markets",
+            get(rest::admin::get_markets).route_layer(require(Permission::ReadAdmin)),
+        )
+        .route(
+            "/admin/markets/discover",
+            post(rest::admin::discover_markets).route_layer(require(Permission::WriteAdmin)),
+        )
+        .route(
+            "/admin/markets/{id}",
+            get(rest::admin::get_market).route_layer(require(Permission::ReadAdmin)),
+        )
+        .route(
+            "/admin/markets/{id}/refresh",
+            post(rest::admin::refresh_market_price).route_layer(require(Permission::WriteAdmin)),
+        )
+        .route(
+            "/admin/caches/invalidate",
+            post(rest::admin::invalidate_caches).route_layer(require(Permission::WriteAdmin)),
+        )
+        .route(
+            "/admin/jobs",
+            get(rest::admin::get_jobs).route_layer(require(Permission::ReadAdmin)),
+        )
+        .route(
+            "/admin/jobs/{name}/trigger",
+            post(rest::admin::trigger_job).route_layer(require(Permission::WriteAdmin)),
+        )
+        .route(
+            "/admin/symbols",
+            get(rest::admin::get_symbols).route_layer(require(Permission::ReadAdmin)),
+        )
+        .route(
+            "/admin/symbols/{id}",
+            put(rest::admin::put_symbol)
+                .delete(rest::admin::delete_symbol)
+                .route_layer(require(Permission::WriteAdmin)),
+        )
+        .with_state(ctx.clone());
+
+    let merged_app = Router::new().merge(open_routes).merge(authenticated_routes);
+
+    merged_app
+        .route_layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(middleware::from_fn_with_state(
+                    ctx.clone(),
+                    infra::stats::requests_stats,
+                ))
+                .layer(middleware::from_fn(infra::stats::latency_stats)),
+        )
+        .with_state(ctx.clone())
+}
+
+/// Serve `app` until `signal_handler` completes. Then signal `stop_tx`, ending
+/// long-lived responses, and wait up to `drain_timeout` for in-flight requests
+async fn serve(
+    listener: TcpListener,
+    app: IntoMakeServiceWithConnectInfo<Router<()>, SocketAddr>,
+    signal_handler: BoxFuture<'static, ()>,
+    stop_tx: tokio::sync::watch::Sender<bool>,
+    drain_timeout: Duration,
+) -> std::io::Result<()> {
+    let mut stop_rx = stop_tx.subscribe();
+    let stopped = async move {
+        let _ = stop_rx.wait_for(|stop| *stop).await;
+    };
+    let shutdown = async move {
+        signal_handler.await;
+        info!("Shutting down DcaServer");
+        let _ = stop_tx.send(true);
+    };
+
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        res = &mut server => res,
+        _ = stopped => {
+            match tokio::time::timeout(drain_timeout, server).await {
+                Ok(res) => res,
+                Err(_) => {
+                    warn!(
+                        "Dropping requests still in flight after {}s",
+                        drain_timeout.as_secs()
+                    );
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
 fn build_redis_pool(config: &config::Redis) -> Result<deadpool_redis::Pool> {
     let url = config.connection_url();
     let redis_pool = deadpool_redis::Config::from_url(url)
@@ -375,3 +807,196 @@ async fn refresh_imported_portfolios_stats(stats_repo: &StatsRepository) -> Resu
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use axum::response::sse::{Event, KeepAlive, Sse};
+
+    use super::*;
+    use crate::app::infra::utils::until_stopped;
+
+    async fn spawn_server(
+        app: Router<()>,
+        stop_tx: tokio::sync::watch::Sender<bool>,
+        drain_timeout: Duration,
+    ) -> (
+        SocketAddr,
+        tokio::sync::oneshot::Sender<()>,
+        JoinHandle<std::io::Result<()>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server = tokio::spawn(serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+            signal_rx.map(|_| ()).boxed(),
+            stop_tx,
+            drain_timeout,
+        ));
+
+        (addr, signal_tx, server)
+    }
+
+    #[tokio::test]
+    async fn shutdown_ends_open_streams() {
+        let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+        let app = Router::new().route(
+            "/stream",
+            get(move || {
+                let events = futures::stream::pending::<std::result::Result<Event, Infallible>>();
+                let events = until_stopped(events, stop_rx.clone());
+                async move { Sse::new(events).keep_alive(KeepAlive::default()) }
+            }),
+        );
+        let (addr, signal_tx, server) = spawn_server(app, stop_tx, Duration::from_secs(30)).await;
+
+        let mut stream = reqwest::get(format!("http://{addr}/stream")).await.unwrap();
+        signal_tx.send(()).unwrap();
+
+        let served = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(served, Ok(Ok(Ok(())))));
+        while let Some(chunk) = stream.chunk().await.unwrap() {
+            assert!(chunk.starts_with(b":"), "unexpected event {chunk:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_drops_requests_after_drain_timeout() {
+        let app = Router::new().route("/hang", get(futures::future::pending::<()>));
+        let (stop_tx, _) = tokio::sync::watch::channel(false);
+        let (addr, signal_tx, server) =
+            spawn_server(app, stop_tx, Duration::from_millis(100)).await;
+
+        let request = tokio::spawn(reqwest::get(format!("http://{addr}/hang")));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        signal_tx.send(()).unwrap();
+
+        let served = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(served, Ok(Ok(Ok(())))));
+        request.abort();
+    }
+
+    const CONFIG: &str = "
+app:
+  providers:
+    priceProvider: kraken
+    ipApiKey: IP_API_KEY
+  auth:
+    jwtSecret: JWT_SECRET
+  log:
+    level: info
+server:
+  web:
+    hostname: 127.0.0.1
+    port: 8080
+  metrics:
+    hostname: 127.0.0.1
+    port: 9000
+  redis:
+    hostname: 127.0.0.1
+    port: 6379
+    user: dcapal
+    password: dcapal
+  postgres:
+    hostname: 127.0.0.1
+    port: 5432
+    user: dcapal
+    password: dcapal
+    database: dcapal
+";
+
+    /// Server of [`CONFIG`]. Storage is never reached, as requests are
+    /// rejected before
+    async fn spawn_app() -> SocketAddr {
+        let config: Config = ::config::Config::builder()
+            .add_source(::config::File::from_str(CONFIG, ::config::FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        let redis = build_redis_pool(&config.server.redis).unwrap();
+        let postgres = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy(&config.server.postgres.connection_url())
+            .unwrap();
+        let (ctx, _) = build_context(Arc::new(config), redis, postgres)
+            .await
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(&ctx).into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        addr
+    }
+
+    /// Token signed with the HS256 secret of [`CONFIG`]
+    fn token(role: &str) -> String {
+        let claims = serde_json::json!({
+            "iat": 0,
+            "exp": Utc::now().timestamp() + 60,
+            "sub": uuid::Uuid::new_v4(),
+            "session_id": uuid::Uuid::new_v4(),
+            "role": role,
+            "aud": "authenticated",
+            "user_metadata": { "email": "user@example.com" },
+        });
+        let key = jsonwebtoken::EncodingKey::from_secret(b"JWT_SECRET");
+        jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn routes_require_their_permission() {
+        let addr = spawn_app().await;
+        let http = reqwest::Client::new();
+        let status = |req: reqwest::RequestBuilder| async move {
+            req.send().await.unwrap().status().as_u16()
+        };
+
+        let jobs = format!("http://{addr}/admin/jobs");
+        assert_eq!(status(http.get(&jobs)).await, 401);
+        assert_eq!(
+            status(http.get(&jobs).bearer_auth("not-a-token")).await,
+            401
+        );
+        let forged = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "role": "admin", "exp": Utc::now().timestamp() + 60 }),
+            &jsonwebtoken::EncodingKey::from_secret(b"another secret"),
+        )
+        .unwrap();
+        assert_eq!(status(http.get(&jobs).bearer_auth(forged)).await, 401);
+
+        assert_eq!(
+            status(http.get(&jobs).bearer_auth(token("authenticated"))).await,
+            403
+        );
+        assert_eq!(
+            status(http.get(&jobs).bearer_auth(token("anon"))).await,
+            403
+        );
+
+        let trigger = format!("http://{addr}/admin/jobs/marketDiscovery/trigger");
+        assert_eq!(
+            status(http.post(&trigger).bearer_auth(token("service"))).await,
+            403
+        );
+        let symbol = format!("http://{addr}/admin/symbols/btc");
+        assert_eq!(
+            status(http.delete(&symbol).bearer_auth(token("service"))).await,
+            403
+        );
+
+        let connections = format!("http://{addr}/v1/connections");
+        assert_eq!(status(http.get(&connections)).await, 401);
+        assert_eq!(
+            status(http.get(&connections).bearer_auth(token("service"))).await,
+            403
+        );
+    }
+}
@@ -14,13 +14,17 @@ use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 async fn main() -> anyhow::Result<()> {
     let config = Config::new()?;
 
-    let _guard = init_tracing(&config.app.log)?;
+    let guard = init_tracing(&config.app.log)?;
 
     init_prometheus_exporter(&config.server.metrics)?;
 
     let mut server = DcaServer::try_new(config).await?;
+    let res = server.start(shutdown_signal().boxed()).await;
 
-    Ok(server.start(shutdown_signal().boxed()).await?)
+    // Flush buffered logs before exiting
+    drop(guard);
+
+    Ok(res?)
 }
 
 fn init_tracing(config: &dcapal_backend::config::Log) -> anyhow::Result<Option<WorkerGuard>> {
@@ -0,0 +1,108 @@
+//! Admin API to inspect and fix market data at runtime. Access is granted by
+//! the authorization layer of each route.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::{
+    AppContext,
+    app::{
+        domain::entity::{AssetId, AssetKind, MarketId, SymbolMapping},
+        workers::market_discovery::MarketDiscoveryWorker,
+    },
+    error::Result,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GetAssetsQuery {
+    kind: Option<AssetKind>,
+}
+
+pub async fn get_assets(
+    State(ctx): State<AppContext>,
+    Query(query): Query<GetAssetsQuery>,
+) -> Result<Response> {
+    let assets = ctx.services.admin.get_assets(query.kind).await?;
+
+    Ok(Json(assets).into_response())
+}
+
+pub async fn get_markets(State(ctx): State<AppContext>) -> Result<Response> {
+    let markets = ctx.services.admin.get_markets().await?;
+
+    Ok(Json(markets).into_response())
+}
+
+pub async fn get_market(
+    State(ctx): State<AppContext>,
+    Path(id): Path<MarketId>,
+) -> Result<Response> {
+    let market = ctx.services.admin.get_market(&id).await?;
+
+    Ok(Json(market).into_response())
+}
+
+pub async fn refresh_market_price(
+    State(ctx): State<AppContext>,
+    Path(id): Path<MarketId>,
+) -> Result<Response> {
+    let market = ctx.services.admin.refresh_price(&id).await?;
+
+    Ok(Json(market).into_response())
+}
+
+/// Discover new markets right away, even if already discovered today
+pub async fn discover_markets(State(ctx): State<AppContext>) -> Result<Response> {
+    ctx.services
+        .scheduler
+        .trigger(MarketDiscoveryWorker::NAME)?;
+
+    Ok(StatusCode::ACCEPTED.into_response())
+}
+
+pub async fn invalidate_caches(State(ctx): State<AppContext>) -> Response {
+    ctx.services.admin.invalidate_caches();
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+pub async fn get_jobs(State(ctx): State<AppContext>) -> Response {
+    Json(ctx.services.scheduler.jobs()).into_response()
+}
+
+pub async fn trigger_job(
+    State(ctx): State<AppContext>,
+    Path(name): Path<String>,
+) -> Result<Response> {
+    ctx.services.scheduler.trigger(&name)?;
+
+    Ok(StatusCode::ACCEPTED.into_response())
+}
+
+pub async fn get_symbols(State(ctx): State<AppContext>) -> Response {
+    Json(ctx.services.admin.get_symbols()).into_response()
+}
+
+pub async fn put_symbol(
+    State(ctx): State<AppContext>,
+    Path(id): Path<AssetId>,
+    Json(mapping): Json<SymbolMapping>,
+) -> Result<Response> {
+    ctx.services.admin.put_symbol(&id, mapping).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn delete_symbol(
+    State(ctx): State<AppContext>,
+    Path(id): Path<AssetId>,
+) -> Result<Response> {
+    ctx.services.admin.delete_symbol(&id).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -5,28 +5,48 @@ use std::{fmt::Display, time::Duration};
 use axum::{
     Json,
     extract::{Path, Query, State},
-    response::{IntoResponse, Response},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, CacheControl, authorization::Bearer},
 };
-use axum_extra::{TypedHeader, headers::CacheControl};
+use chrono::{TimeZone, Utc};
+use futures::StreamExt;
 use hyper::StatusCode;
 use lazy_static::lazy_static;
 use metrics::counter;
 use sea_orm::prelude::Decimal;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
 use utoipa::ToSchema;
 
 use crate::{
     AppContext,
     app::{
-        domain::entity::AssetKind,
-        infra::utils::Expiring,
-        services::command::{ConversionRateQuery, ImportPortfolioCmd},
+        domain::{
+            chart::{ChartResolution, SymbolInfo},
+            entity::{AssetId, AssetKind, MarketId, Price},
+        },
+        infra::{
+            auth::{Permission, Role},
+            utils::{Expiring, until_stopped},
+        },
+        services::{
+            command::{BatchConversionRateQuery, ConversionRateQuery, ImportPortfolioCmd},
+            health::{HealthReport, HealthStatus},
+            market_data::MarketDataService,
+        },
     },
     error::{DcaError, Result},
     infra::stats,
     ports::outbound::repository::ImportedPortfolio,
 };
 
+pub mod admin;
 pub mod request;
 pub mod response;
 
@@ -43,6 +63,44 @@ lazy_static! {
         jsonschema::draft7::new(&PORTFOLIO_JSON_SCHEMA).unwrap();
 }
 
+pub async fn get_health_live() -> Response {
+    Json(serde_json::json!({ "status": HealthStatus::Up })).into_response()
+}
+
+/// Readiness to serve requests. Degraded dependencies are reported, but only
+/// unavailable storage makes the server not ready. Storage errors are only
+/// disclosed to callers allowed to read the admin API
+pub async fn get_health_ready(
+    State(ctx): State<AppContext>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Response {
+    let report = ctx.services.health.check().await;
+
+    let is_admin = match bearer {
+        Some(TypedHeader(Authorization(bearer))) => ctx
+            .services
+            .jwt
+            .decode(bearer.token())
+            .await
+            .ok()
+            .and_then(|claims| claims.role.as_deref().and_then(Role::from_claim))
+            .is_some_and(|role| role.can(Permission::ReadAdmin)),
+        None => false,
+    };
+
+    ready_response(report, is_admin)
+}
+
+fn ready_response(report: HealthReport, is_admin: bool) -> Response {
+    let status = match report.status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+    let report = if is_admin { report } else { report.redacted() };
+
+    (status, Json(report)).into_response()
+}
+
 pub async fn get_assets_fiat(State(ctx): State<AppContext>) -> Result<Response> {
     let service = &ctx.services.mkt_data;
 
@@ -72,13 +130,25 @@ pub async fn get_assets_crypto(State(ctx): State<AppContext>) -> Result<Response
 #[derive(Debug, Deserialize)]
 pub struct GetAssetsQuery {
     name: String,
+    aclass: Option<AssetKind>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchAssetsResponse {
+    pub results: Vec<SymbolInfo>,
 }
 
 pub async fn get_assets_data(
     State(ctx): State<AppContext>,
     Query(params): Query<GetAssetsQuery>,
 ) -> Result<Response> {
-    Ok(ctx.providers.yahoo.search(params.name).await)
+    let results = ctx
+        .services
+        .search
+        .search(&params.name, params.aclass)
+        .await?;
+
+    Ok(Json(SearchAssetsResponse { results }).into_response())
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +156,7 @@ pub async fn get_assets_data(
 pub struct GetAssetChartQuery {
     start_period: i64,
     end_period: i64,
+    resolution: Option<ChartResolution>,
 }
 
 pub async fn get_assets_chart(
@@ -93,11 +164,24 @@ pub async fn get_assets_chart(
     Query(params): Query<GetAssetChartQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
-    Ok(ctx
-        .providers
-        .yahoo
-        .chart(asset, params.start_period, params.end_period)
-        .await)
+    let period = |ts| {
+        Utc.timestamp_opt(ts, 0)
+            .single()
+            .ok_or_else(|| DcaError::BadRequest(format!("Invalid timestamp: {ts}")))
+    };
+    let (start, end) = (period(params.start_period)?, period(params.end_period)?);
+
+    let chart = ctx
+        .services
+        .chart
+        .get_chart(&asset, start, end, params.resolution)
+        .await?;
+
+    let cache_control = CacheControl::new()
+        .with_public()
+        .with_max_age(chart.resolution.cache_ttl());
+
+    Ok((TypedHeader(cache_control), Json(chart)).into_response())
 }
 
 #[derive(Debug, Deserialize)]
@@ -125,6 +209,250 @@ pub async fn get_price(
     Ok(response.into_response())
 }
 
+/// Get a [`Market`](crate::app::domain::entity::Market), along with its
+/// lifecycle status
+pub async fn get_market(
+    Path(id): Path<MarketId>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let id = id.to_lowercase();
+    let market = ctx
+        .services
+        .mkt_data
+        .get_market(&id)
+        .await?
+        .ok_or(DcaError::MarketNotFound(id))?;
+
+    Ok(Json(market.as_ref()).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetHistoricalPriceQuery {
+    quote: String,
+    /// Unix timestamp, in seconds. Resolved to the close of its UTC day
+    ts: i64,
+}
+
+pub async fn get_historical_price(
+    Path(asset): Path<String>,
+    Query(query): Query<GetHistoricalPriceQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let repo = &ctx.repos.mkt_data;
+    let service = &ctx.services.price_history;
+
+    let Some(ts) = Utc.timestamp_opt(query.ts, 0).single() else {
+        return Err(DcaError::BadRequest(format!(
+            "Invalid timestamp: {}",
+            query.ts
+        )));
+    };
+    if ts > Utc::now() {
+        return Err(DcaError::BadRequest(format!(
+            "Timestamp in the future: {ts}"
+        )));
+    }
+
+    let date = ts.date_naive();
+    let cmd = ConversionRateQuery::try_new(&asset, &query.quote, repo).await?;
+    let (base, quote) = (cmd.base.id().clone(), cmd.quote.id().clone());
+
+    let price = service
+        .get_conversion_rate(cmd, date)
+        .await?
+        .ok_or(DcaError::PriceNotAvailable(base, quote))?;
+
+    // Past close prices never change
+    let cache_control = if date < Utc::now().date_naive() {
+        CacheControl::new()
+            .with_public()
+            .with_max_age(Duration::from_secs(24 * 60 * 60))
+    } else {
+        cache_control(&price)
+    };
+
+    let response = (TypedHeader(cache_control), Json(price));
+    Ok(response.into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetPricesQuery {
+    assets: String,
+    quotes: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetPricesResponse {
+    pub prices: Vec<PriceEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceEntry {
+    pub base: AssetId,
+    pub quote: AssetId,
+    #[serde(flatten)]
+    pub price: Option<Price>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PriceEntry {
+    fn new(base: AssetId, quote: AssetId, rate: Result<Option<Price>>) -> Self {
+        match rate.and_then(|px| px.ok_or(DcaError::PriceNotAvailable(base.clone(), quote.clone())))
+        {
+            Ok(price) => Self {
+                base,
+                quote,
+                price: Some(price),
+                error: None,
+            },
+            Err(e) => Self {
+                base,
+                quote,
+                price: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+pub async fn get_prices(
+    Query(query): Query<GetPricesQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let repo = &ctx.repos.mkt_data;
+    let service = &ctx.services.mkt_data;
+
+    let (assets, quotes) = (parse_ids(&query.assets), parse_ids(&query.quotes));
+    let cmd = BatchConversionRateQuery::try_new(&assets, &quotes, repo).await?;
+
+    // Unknown assets fail their own entry only
+    let mut entries = Vec::with_capacity(cmd.entries.len());
+    let mut queries = vec![];
+    for (base, quote, query) in cmd.entries {
+        match query {
+            Ok(q) => {
+                queries.push(q);
+                entries.push((base, quote, None));
+            }
+            Err(e) => entries.push((base, quote, Some(e))),
+        }
+    }
+
+    let mut rates = service.get_conversion_rates(queries).await.into_iter();
+    let prices: Vec<PriceEntry> = entries
+        .into_iter()
+        .map(|(base, quote, error)| {
+            let rate = match error {
+                Some(e) => Err(e),
+                None => rates.next().unwrap(),
+            };
+
+            PriceEntry::new(base, quote, rate)
+        })
+        .collect();
+
+    let ttl = prices
+        .iter()
+        .filter_map(|e| e.price.as_ref())
+        .map(|px| px.time_to_live())
+        .min()
+        .unwrap_or_default();
+    let cache_control = CacheControl::new().with_public().with_max_age(ttl);
+
+    let response = (
+        TypedHeader(cache_control),
+        Json(GetPricesResponse { prices }),
+    );
+    Ok(response.into_response())
+}
+
+/// Stream [`PriceEntry`] events as Server-Sent Events: a snapshot of all
+/// requested pairs first, then every time a pair rate changes
+pub async fn stream_prices(
+    Query(query): Query<GetPricesQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let repo = &ctx.repos.mkt_data;
+    let service = ctx.services.mkt_data.clone();
+
+    let (assets, quotes) = (parse_ids(&query.assets), parse_ids(&query.quotes));
+    let cmd = BatchConversionRateQuery::try_new(&assets, &quotes, repo).await?;
+
+    // Subscribe before the snapshot, not to miss updates in between
+    let updates = service.subscribe();
+
+    let mut snapshot = vec![];
+    let mut queries = vec![];
+    for (base, quote, query) in cmd.entries {
+        match query {
+            Ok(q) => queries.push(q),
+            Err(e) => snapshot.push(PriceEntry::new(base, quote, Err(e))),
+        }
+    }
+    snapshot.extend(price_entries(&service, queries.clone()).await);
+
+    let updates = futures::stream::unfold(
+        (service, queries, updates),
+        |(service, queries, mut updates)| async move {
+            loop {
+                let affected: Vec<ConversionRateQuery> = match updates.recv().await {
+                    Ok(update) => queries
+                        .iter()
+                        .filter(|q| update.affects(q.base.id(), q.quote.id()))
+                        .cloned()
+                        .collect(),
+                    // Too slow to keep up: refresh every pair
+                    Err(RecvError::Lagged(_)) => queries.clone(),
+                    Err(RecvError::Closed) => return None,
+                };
+
+                if !affected.is_empty() {
+                    let entries = price_entries(&service, affected).await;
+                    return Some((entries, (service, queries, updates)));
+                }
+            }
+        },
+    );
+
+    let events = futures::stream::iter(snapshot)
+        .chain(updates.flat_map(futures::stream::iter))
+        .map(|entry| Event::default().event("price").json_data(entry));
+    // Endless streams would hold graceful shutdown forever
+    let events = until_stopped(events, ctx.stop_token.clone());
+
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+async fn price_entries(
+    service: &MarketDataService,
+    queries: Vec<ConversionRateQuery>,
+) -> Vec<PriceEntry> {
+    let pairs: Vec<_> = queries
+        .iter()
+        .map(|q| (q.base.id().clone(), q.quote.id().clone()))
+        .collect();
+
+    pairs
+        .into_iter()
+        .zip(service.get_conversion_rates(queries).await)
+        .map(|((base, quote), rate)| PriceEntry::new(base, quote, rate))
+        .collect()
+}
+
+fn parse_ids(ids: &str) -> Vec<AssetId> {
+    let mut parsed: Vec<AssetId> = vec![];
+    for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        if !parsed.iter().any(|p| p == id) {
+            parsed.push(id.to_string());
+        }
+    }
+
+    parsed
+}
+
 fn cache_control<T: Expiring>(t: &T) -> CacheControl {
     CacheControl::new()
         .with_public()
@@ -216,3 +544,65 @@ impl Display for FeeStructure {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::app::services::health::{DependencyCheck, MarketDataCheck};
+
+    fn report(postgres: HealthStatus) -> HealthReport {
+        let check = |status, error: Option<&str>| DependencyCheck {
+            status,
+            latency_ms: 1,
+            error: error.map(str::to_string),
+        };
+
+        HealthReport {
+            status: postgres,
+            checked_at: Utc::now(),
+            redis: check(HealthStatus::Up, None),
+            postgres: check(postgres, Some("connection refused to db.internal:5432")),
+            providers: BTreeMap::new(),
+            workers: BTreeMap::new(),
+            market_data: MarketDataCheck {
+                status: HealthStatus::Up,
+                markets: 1,
+                outdated: 0,
+                last_price_at: None,
+            },
+        }
+    }
+
+    async fn body(res: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn not_ready_when_storage_is_down() {
+        let res = ready_response(report(HealthStatus::Down), false);
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let res = ready_response(report(HealthStatus::Degraded), false);
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn storage_errors_are_disclosed_to_admins_only() {
+        let res = ready_response(report(HealthStatus::Down), false);
+        let json = body(res).await;
+        assert_eq!(json["postgres"]["status"], "down");
+        assert!(json["postgres"].get("error").is_none());
+
+        let res = ready_response(report(HealthStatus::Down), true);
+        let json = body(res).await;
+        assert_eq!(
+            json["postgres"]["error"],
+            "connection refused to db.internal:5432"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::{IntoResponse, Response},
 };
@@ -10,7 +10,14 @@ use tracing::{error, info};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{AppContext, DateTime, app::infra::claim::Claims, ports::inbound::rest::FeeStructure};
+use crate::{
+    AppContext, DateTime,
+    app::{
+        domain::connector::Exchange, infra::claim::Claims, services::connector::ConnectorService,
+    },
+    error::DcaError,
+    ports::inbound::rest::FeeStructure,
+};
 
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -44,6 +51,17 @@ pub struct PortfolioAssetRequest {
     pub fees: Option<TransactionFeesRequest>,
 }
 
+/// Read-only API credentials of an exchange account to link to a portfolio
+#[derive(Deserialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionRequest {
+    pub portfolio_id: Uuid,
+    #[schema(value_type = String)]
+    pub exchange: Exchange,
+    pub api_key: String,
+    pub api_secret: String,
+}
+
 #[derive(Debug, Deserialize, ToSchema, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionFeesRequest {
@@ -78,6 +96,68 @@ pub async fn sync_portfolios(
     }
 }
 
+pub async fn get_cash_flows(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+    Path(portfolio_id): Path<Uuid>,
+) -> crate::error::Result<Response> {
+    let cash_flows = ctx
+        .services
+        .portfolio
+        .get_cash_flows(claims.sub, portfolio_id)
+        .await?;
+
+    Ok(Json(cash_flows).into_response())
+}
+
+pub async fn link_connection(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+    Json(req): Json<ConnectionRequest>,
+) -> crate::error::Result<Response> {
+    let connection = connectors(&ctx)?.link(claims.sub, req).await?;
+
+    Ok((StatusCode::CREATED, Json(connection)).into_response())
+}
+
+pub async fn get_connections(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+) -> crate::error::Result<Response> {
+    let connections = connectors(&ctx)?.get_connections(claims.sub).await?;
+
+    Ok(Json(connections).into_response())
+}
+
+pub async fn delete_connection(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+    Path(connection_id): Path<Uuid>,
+) -> crate::error::Result<Response> {
+    connectors(&ctx)?.unlink(claims.sub, connection_id).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn sync_connection(
+    State(ctx): State<AppContext>,
+    claims: Claims,
+    Path(connection_id): Path<Uuid>,
+) -> crate::error::Result<Response> {
+    let connection = connectors(&ctx)?
+        .sync_connection(claims.sub, connection_id)
+        .await?;
+
+    Ok(Json(connection).into_response())
+}
+
+fn connectors(ctx: &AppContext) -> crate::error::Result<&ConnectorService> {
+    ctx.services
+        .connectors
+        .as_deref()
+        .ok_or_else(|| DcaError::BadRequest("Exchange connectors are not enabled".to_string()))
+}
+
 #[test]
 fn test_fee_structure_deserialization() {
     let json = r#"{
@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::Serialize;
 use utoipa::ToSchema;
@@ -5,7 +6,11 @@ use uuid::Uuid;
 
 use crate::{
     DateTime,
-    app::domain::db::{portfolio_asset, portfolios},
+    app::domain::{
+        db::{exchange_connection, portfolio_asset, portfolio_cash_flow, portfolios},
+        entity::Market,
+    },
+    config::PriceProvider,
     error::DcaError,
     ports::inbound::rest::FeeStructure,
 };
@@ -46,6 +51,73 @@ pub struct PortfolioAssetResponse {
     pub fees: Option<TransactionFeesResponse>,
 }
 
+/// Cash credited to a portfolio, e.g. by a dividend
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CashFlowResponse {
+    pub id: Uuid,
+    pub symbol: String,
+    pub kind: String,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Decimal,
+    pub currency: String,
+    pub ex_date: NaiveDate,
+    pub reinvested: bool,
+}
+
+impl From<portfolio_cash_flow::Model> for CashFlowResponse {
+    fn from(cf: portfolio_cash_flow::Model) -> Self {
+        Self {
+            id: cf.id,
+            symbol: cf.symbol,
+            kind: cf.kind,
+            amount: cf.amount,
+            currency: cf.currency,
+            ex_date: cf.ex_date,
+            reinvested: cf.reinvested,
+        }
+    }
+}
+
+/// Stored market, along with the provider its price is fetched from
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminMarketResponse {
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub market: Market,
+    #[schema(value_type = String)]
+    pub price_source: PriceProvider,
+    /// Whether the price is missing or older than its time to live
+    pub price_outdated: bool,
+}
+
+/// Exchange account linked to a portfolio. API credentials are never
+/// returned
+#[derive(Debug, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionResponse {
+    pub id: Uuid,
+    pub portfolio_id: Uuid,
+    pub exchange: String,
+    pub last_synced_at: Option<DateTime>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl From<exchange_connection::Model> for ConnectionResponse {
+    fn from(c: exchange_connection::Model) -> Self {
+        Self {
+            id: c.id,
+            portfolio_id: c.portfolio_id,
+            exchange: c.exchange,
+            last_synced_at: c.last_synced_at.map(Into::into),
+            last_error: c.last_error,
+            created_at: c.created_at.into(),
+        }
+    }
+}
+
 impl TryFrom<(portfolios::Model, Vec<portfolio_asset::Model>)> for PortfolioResponse {
     type Error = DcaError;
 
@@ -176,11 +248,11 @@ pub struct TransactionFeesResponse {
 #[cfg(test)]
 mod test {
 
-    use super::*;
-
     use chrono::Utc;
     use rust_decimal::dec;
 
+    use super::*;
+
     #[test]
     fn map_model_to_response() {
         let portfolio_id = Uuid::new_v4();
@@ -221,6 +293,7 @@ mod test {
             max_fee: None,
             created_at: Utc::now().into(),
             updated_at: Utc::now().into(),
+            quantity_as_of: Utc::now().into(),
         };
         let assets_model = vec![asset_model.clone()];
 
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use chrono::NaiveDate;
+use failsafe::futures::CircuitBreaker;
+use reqwest::StatusCode;
+use serde::{Deserialize, de::DeserializeOwned};
+use tracing::{debug, error, warn};
+
+use super::{
+    BreakerState, DefaultCircuitBreaker, circuit_breaker, kraken::resolve_assets_data_basic,
+};
+use crate::{
+    DateTime,
+    app::{
+        domain::entity::{Asset, Market, MarketId, MarketStatus, OHLCFrequency},
+        infra::{rate_limiter::TokenBucket, utils::day_start},
+        services::symbols::SymbolRegistry,
+    },
+    config::{self, PriceProvider},
+    error::{DcaError, Result},
+    ports::outbound::repository::market_data::MarketDataRepository,
+};
+
+/// Binance public market data REST API. No API key is required
+#[derive(Clone)]
+pub struct BinanceProvider {
+    http: reqwest::Client,
+    base_url: String,
+    symbols: Arc<SymbolRegistry>,
+    rate_limiter: Arc<TokenBucket>,
+    circuit_breaker: DefaultCircuitBreaker,
+    breaker_state: BreakerState,
+}
+
+impl BinanceProvider {
+    pub fn new(
+        http: reqwest::Client,
+        config: &config::Providers,
+        symbols: Arc<SymbolRegistry>,
+    ) -> Self {
+        let (circuit_breaker, breaker_state) = circuit_breaker();
+
+        Self {
+            http,
+            base_url: config.endpoints.binance.clone(),
+            symbols,
+            rate_limiter: Arc::new(TokenBucket::new(&config.rate_limit(PriceProvider::Binance))),
+            circuit_breaker,
+            breaker_state,
+        }
+    }
+
+    /// Whether the circuit breaker of Binance is not open
+    pub fn is_available(&self) -> bool {
+        !self.breaker_state.is_open()
+    }
+
+    /// Fetch assets and markets not known by `repo` yet, along with the
+    /// status of every market listed by Binance
+    pub async fn fetch_assets(
+        &self,
+        repo: &MarketDataRepository,
+    ) -> Result<(Vec<Asset>, Vec<Market>, HashMap<MarketId, MarketStatus>)> {
+        let url = format!("{}/api/v3/exchangeInfo", self.base_url);
+
+        debug!(url = url, "Fetching markets from Binance");
+        let Some(res) = self.fetch_binance_api::<ExchangeInfo>(&url).await? else {
+            return Err(DcaError::Generic("ExchangeInfo not found".to_string()));
+        };
+
+        // Pairs on `BREAK` or `HALT` are listed, but cannot be traded
+        let listed = res
+            .symbols
+            .iter()
+            .map(|s| {
+                let base = self
+                    .symbols
+                    .from_provider(&s.base_asset, PriceProvider::Binance);
+                let quote = self
+                    .symbols
+                    .from_provider(&s.quote_asset, PriceProvider::Binance);
+                let status = match s.status.as_str() {
+                    "TRADING" => MarketStatus::Active,
+                    _ => MarketStatus::Halted,
+                };
+                (format!("{base}/{quote}"), status)
+            })
+            .collect::<Vec<_>>();
+
+        let market_symbols = listed
+            .iter()
+            .filter(|(_, status)| *status == MarketStatus::Active)
+            .map(|(symbol, _)| symbol.clone())
+            .collect::<Vec<String>>();
+
+        let statuses = listed
+            .into_iter()
+            .map(|(symbol, status)| (symbol.replace('/', ""), status))
+            .collect();
+
+        let (markets, assets) = resolve_assets_data_basic(&market_symbols, repo).await;
+
+        debug!("New assets: {}", serde_json::to_string(&assets).unwrap());
+        debug!("New markets: {}", serde_json::to_string(&markets).unwrap());
+
+        Ok((assets, markets, statuses))
+    }
+
+    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+        if let Some(px) = self.fetch_price(mkt, OHLCFrequency::Minutes5, ts).await? {
+            return Ok(Some(px));
+        }
+
+        self.fetch_price(mkt, OHLCFrequency::Daily, ts).await
+    }
+
+    /// Fetch the close price of `mkt` daily candlestick opened on `date`
+    pub async fn fetch_daily_price(&self, mkt: &Market, date: NaiveDate) -> Result<Option<f64>> {
+        let day_start = day_start(date).timestamp_millis();
+
+        let klines = self
+            .fetch_klines(mkt, OHLCFrequency::Daily, day_start, day_start)
+            .await?;
+
+        klines
+            .iter()
+            .find(|k| k.open_time == day_start)
+            .map(Kline::close)
+            .transpose()
+    }
+
+    async fn fetch_price(
+        &self,
+        mkt: &Market,
+        freq: OHLCFrequency,
+        ts: DateTime,
+    ) -> Result<Option<f64>> {
+        let (r_lo, r_hi) = freq.ohlc_range(ts);
+
+        debug!(
+            "Fetching {freq} klines for market '{}' since {r_lo}",
+            mkt.id
+        );
+        let klines = self
+            .fetch_klines(mkt, freq, r_lo.timestamp_millis(), r_hi.timestamp_millis())
+            .await?;
+
+        klines.last().map(Kline::close).transpose()
+    }
+
+    /// Fetch `mkt` klines opened in range [`start`, `end`], as Unix timestamps
+    /// in milliseconds. Unknown pairs have no klines
+    async fn fetch_klines(
+        &self,
+        mkt: &Market,
+        freq: OHLCFrequency,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<Kline>> {
+        let pair = self.binance_pair(mkt);
+        let interval = get_binance_api_interval(freq);
+        let url = format!(
+            "{}/api/v3/klines?symbol={pair}&interval={interval}&startTime={start}&endTime={end}",
+            self.base_url
+        );
+
+        debug!(url = url, "Fetching klines for market '{}'", mkt.id);
+        Ok(self
+            .fetch_binance_api::<Vec<Kline>>(&url)
+            .await?
+            .unwrap_or_default())
+    }
+
+    fn binance_pair(&self, mkt: &Market) -> String {
+        let base = self
+            .symbols
+            .to_provider(mkt.base.id(), PriceProvider::Binance);
+        let quote = self
+            .symbols
+            .to_provider(mkt.quote.id(), PriceProvider::Binance);
+        format!("{base}{quote}").to_uppercase()
+    }
+
+    async fn fetch_binance_api<T: DeserializeOwned + Debug>(&self, url: &str) -> Result<Option<T>> {
+        self.rate_limiter.acquire().await;
+        self.circuit_breaker
+            .call(self.fetch_binance_api_inner::<T>(url))
+            .await
+            .map_err(|e| DcaError::from_failsafe(e, "BinanceProvider"))
+    }
+
+    async fn fetch_binance_api_inner<T: DeserializeOwned + Debug>(
+        &self,
+        url: &str,
+    ) -> Result<Option<T>> {
+        let res = self.http.get(url).send().await?;
+
+        if res.status().is_success() {
+            return Ok(Some(res.json::<T>().await?));
+        }
+
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            // Unknown symbols are reported as bad requests
+            StatusCode::BAD_REQUEST => {
+                let err = res.json::<ApiError>().await?;
+                if err.code == INVALID_SYMBOL {
+                    Ok(None)
+                } else {
+                    error!(url = url, "Binance request failed: {err:?}");
+                    Err(DcaError::Generic(err.msg))
+                }
+            }
+            _ => {
+                warn!(url = url, "Binance request failed: {}", res.status());
+                Err(res.error_for_status().unwrap_err().into())
+            }
+        }
+    }
+}
+
+const INVALID_SYMBOL: i64 = -1121;
+
+fn get_binance_api_interval(freq: OHLCFrequency) -> &'static str {
+    match freq {
+        OHLCFrequency::Minutes5 => "5m",
+        OHLCFrequency::Daily => "1d",
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiError {
+    code: i64,
+    msg: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SymbolInfo {
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+}
+
+/// Candlestick as `[open time, open, high, low, close, volume, close time,
+/// ...]`, with prices serialized as strings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "Vec<serde_json::Value>")]
+struct Kline {
+    open_time: i64,
+    close: Option<String>,
+}
+
+impl Kline {
+    fn close(&self) -> Result<f64> {
+        self.close
+            .as_deref()
+            .and_then(|c| c.parse::<f64>().ok())
+            .ok_or_else(|| DcaError::Generic(format!("Malformed kline: {self:?}")))
+    }
+}
+
+impl From<Vec<serde_json::Value>> for Kline {
+    fn from(v: Vec<serde_json::Value>) -> Self {
+        Self {
+            open_time: v.first().and_then(|t| t.as_i64()).unwrap_or_default(),
+            close: v.get(4).and_then(|c| c.as_str()).map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_klines() {
+        let klines: Vec<Kline> = serde_json::from_str(
+            r#"[
+                [1752451200000, "102150.10", "102200.00", "102100.00", "102180.40", "1.5",
+                 1752451499999, "153270.6", 12, "0.7", "71525.2", "0"],
+                [1752451500000]
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(klines[0].open_time, 1752451200000);
+        assert_eq!(klines[0].close().unwrap(), 102180.4);
+        assert!(klines[1].close().is_err());
+    }
+}
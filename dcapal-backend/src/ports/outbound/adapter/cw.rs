@@ -1,8 +1,10 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
+    sync::Arc,
 };
 
+use chrono::NaiveDate;
 use futures::{StreamExt, future};
 use reqwest::StatusCode;
 use serde::{Deserialize, de::DeserializeOwned};
@@ -10,23 +12,42 @@ use tracing::{debug, error, info};
 
 use crate::{
     DateTime,
-    app::domain::entity::{Asset, Crypto, Fiat, Market, MarketId, OHLCFrequency},
-    config,
+    app::{
+        domain::entity::{Asset, Crypto, Fiat, Market, MarketId, OHLCFrequency},
+        infra::{rate_limiter::TokenBucket, utils::day_start},
+        services::symbols::SymbolRegistry,
+    },
+    config::{self, PriceProvider},
     error::{DcaError, Result},
     ports::outbound::repository::market_data::MarketDataRepository,
 };
 
+/// CryptoWatch REST API. The service has been shut down, so this provider is
+/// kept for existing deployments only and enabled by its API key
 #[derive(Clone)]
 pub struct CryptoWatchProvider {
     http: reqwest::Client,
+    base_url: String,
     api_key: String,
+    symbols: Arc<SymbolRegistry>,
+    rate_limiter: Arc<TokenBucket>,
 }
 
 impl CryptoWatchProvider {
-    pub fn new(http: reqwest::Client, config: &config::Providers) -> Self {
+    pub fn new(
+        http: reqwest::Client,
+        api_key: String,
+        config: &config::Providers,
+        symbols: Arc<SymbolRegistry>,
+    ) -> Self {
         Self {
             http,
-            api_key: config.cw_api_key.clone(),
+            base_url: config.endpoints.cryptowatch.clone(),
+            api_key,
+            symbols,
+            rate_limiter: Arc::new(TokenBucket::new(
+                &config.rate_limit(PriceProvider::CryptoWatch),
+            )),
         }
     }
 
@@ -34,11 +55,11 @@ impl CryptoWatchProvider {
         &self,
         repo: &MarketDataRepository,
     ) -> Result<(Vec<Asset>, Vec<Market>)> {
-        const URL: &str = formatcp!("https://api.cryptowat.ch/markets/kraken");
+        let url = format!("{}/markets/kraken", self.base_url);
 
         // Fetch CW markets
-        debug!(url = URL, "Fetching markets from CW");
-        let res: CWMarketsResponse = self.fetch_cw_api(URL).await?;
+        debug!(url = url, "Fetching markets from CW");
+        let res: CWMarketsResponse = self.fetch_cw_api(&url).await?;
         let market_symbols = res
             .result
             .into_iter()
@@ -62,6 +83,20 @@ impl CryptoWatchProvider {
             .filter_map(|a| a.is_ok().then(|| a.unwrap()))
             .collect::<Vec<(String, CWAssetData)>>();
 
+        // Map CW symbols to internal asset ids
+        let cw_assets = cw_assets
+            .into_iter()
+            .map(|(pair, mut a)| {
+                a.base.symbol = self
+                    .symbols
+                    .from_provider(&a.base.symbol, PriceProvider::CryptoWatch);
+                a.quote.symbol = self
+                    .symbols
+                    .from_provider(&a.quote.symbol, PriceProvider::CryptoWatch);
+                (pair, a)
+            })
+            .collect::<Vec<_>>();
+
         // Map assets to entities
         let assets_map = cw_assets
             .iter()
@@ -92,7 +127,7 @@ impl CryptoWatchProvider {
     }
 
     async fn fetch_asset(&self, symbol: String) -> Result<(String, CWAssetData)> {
-        let url = format!("https://api.cryptowat.ch/pairs/{symbol}");
+        let url = format!("{}/pairs/{symbol}", self.base_url);
 
         debug!(url = url, "Fetching '{}' pair from CW", symbol);
         let res: CWAssetDataResult = self.fetch_cw_api(&url).await?;
@@ -115,17 +150,38 @@ impl CryptoWatchProvider {
         }
     }
 
+    /// Fetch the close price of `mkt` on `date`
+    pub async fn fetch_daily_price(&self, mkt: &Market, date: NaiveDate) -> Result<Option<f64>> {
+        let day_start = day_start(date);
+        let day_end = day_start + chrono::Duration::days(1);
+
+        self.fetch_range_price(&mkt.id, OHLCFrequency::Daily, day_start, day_end)
+            .await
+    }
+
     async fn fetch_price(
         &self,
         id: &MarketId,
         freq: OHLCFrequency,
         ts: DateTime,
     ) -> Result<Option<f64>> {
-        let periods = get_cw_api_periods(freq);
         let (r_lo, r_hi) = freq.ohlc_range(ts);
+        self.fetch_range_price(id, freq, r_lo, r_hi).await
+    }
+
+    /// Fetch the last close price of market `id` in range [`r_lo`, `r_hi`]
+    async fn fetch_range_price(
+        &self,
+        id: &MarketId,
+        freq: OHLCFrequency,
+        r_lo: DateTime,
+        r_hi: DateTime,
+    ) -> Result<Option<f64>> {
+        let periods = get_cw_api_periods(freq);
         let (after_ts, before_ts) = (r_lo.timestamp(), r_hi.timestamp());
         let url = format!(
-            "https://api.cryptowat.ch/markets/kraken/{id}/ohlc?after={after_ts}&before={before_ts}&periods={periods}"
+            "{}/markets/kraken/{id}/ohlc?after={after_ts}&before={before_ts}&periods={periods}",
+            self.base_url
         );
 
         debug!(
@@ -157,6 +213,7 @@ impl CryptoWatchProvider {
     }
 
     async fn fetch_cw_api<T: DeserializeOwned + Debug>(&self, url: &str) -> Result<T> {
+        self.rate_limiter.acquire().await;
         let res = self.http.get(url).send().await?;
         if res.status().is_success() {
             return Ok(res.json::<T>().await?);
@@ -0,0 +1,303 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDate, TimeDelta};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{
+    DateTime,
+    app::domain::entity::{AssetId, Market},
+    config,
+    error::{DcaError, Result},
+};
+
+/// Euro reference rates of each currency, by publication date. Each rate is
+/// the amount of the currency worth one euro
+pub type EcbRates = BTreeMap<NaiveDate, HashMap<AssetId, f64>>;
+
+/// European Central Bank euro foreign exchange reference rates, published
+/// once per working day around 16:00 CET
+pub struct EcbProvider {
+    http: reqwest::Client,
+    base_url: String,
+    daily: Mutex<Option<(Instant, Arc<EcbRates>)>>,
+    historical: Mutex<Option<(Instant, Arc<EcbRates>)>>,
+}
+
+impl EcbProvider {
+    const DAILY_TTL: Duration = Duration::from_secs(60 * 60);
+    const HISTORICAL_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+    /// Rates older than this are not used to price a date, e.g. spanning
+    /// weekends and TARGET holidays only
+    const MAX_STALENESS: TimeDelta = TimeDelta::days(7);
+
+    pub fn new(http: reqwest::Client, config: &config::Providers) -> Self {
+        Self {
+            http,
+            base_url: config.endpoints.ecb.clone(),
+            daily: Mutex::new(None),
+            historical: Mutex::new(None),
+        }
+    }
+
+    /// Latest reference rates, with their publication date
+    pub async fn fetch_latest_rates(&self) -> Result<(NaiveDate, HashMap<AssetId, f64>)> {
+        let rates = self.fetch_daily_rates().await?;
+        rates
+            .iter()
+            .next_back()
+            .map(|(date, rates)| (*date, rates.clone()))
+            .ok_or_else(|| DcaError::Generic("Empty ECB daily reference rates".to_string()))
+    }
+
+    pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+        let rates = self.fetch_daily_rates().await?;
+        Ok(cross_rate(&rates, mkt, ts.date_naive()))
+    }
+
+    /// Fetch the reference rate of `mkt` on `date`, falling back to the last
+    /// one published before
+    pub async fn fetch_daily_price(&self, mkt: &Market, date: NaiveDate) -> Result<Option<f64>> {
+        let rates = self.fetch_historical_rates().await?;
+        Ok(cross_rate(&rates, mkt, date))
+    }
+
+    async fn fetch_daily_rates(&self) -> Result<Arc<EcbRates>> {
+        let url = format!("{}/stats/eurofxref/eurofxref-daily.xml", self.base_url);
+        self.fetch_cached(&url, &self.daily, Self::DAILY_TTL).await
+    }
+
+    async fn fetch_historical_rates(&self) -> Result<Arc<EcbRates>> {
+        let url = format!("{}/stats/eurofxref/eurofxref-hist.xml", self.base_url);
+        self.fetch_cached(&url, &self.historical, Self::HISTORICAL_TTL)
+            .await
+    }
+
+    async fn fetch_cached(
+        &self,
+        url: &str,
+        cache: &Mutex<Option<(Instant, Arc<EcbRates>)>>,
+        ttl: Duration,
+    ) -> Result<Arc<EcbRates>> {
+        // Held while fetching, so that concurrent callers wait for a single
+        // download instead of fetching the same document
+        let mut cache = cache.lock().await;
+        if let Some((fetched_at, rates)) = cache.as_ref() {
+            if fetched_at.elapsed() < ttl {
+                return Ok(rates.clone());
+            }
+        }
+
+        debug!(url = url, "Fetching ECB reference rates");
+        let res = self.http.get(url).send().await?;
+        if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
+        }
+
+        let rates = Arc::new(parse_rates(&res.text().await?)?);
+        info!("Fetched ECB reference rates of {} days", rates.len());
+
+        *cache = Some((Instant::now(), rates.clone()));
+        Ok(rates)
+    }
+}
+
+/// Rate of `mkt` quote currency per unit of base currency, as of the last
+/// publication on or before `date`
+fn cross_rate(rates: &EcbRates, mkt: &Market, date: NaiveDate) -> Option<f64> {
+    let (published, rates) = rates.range(..=date).next_back()?;
+    if date - *published > EcbProvider::MAX_STALENESS {
+        return None;
+    }
+
+    let rate = |id: &AssetId| match id.as_str() {
+        "eur" => Some(1.),
+        _ => rates.get(id).copied(),
+    };
+
+    Some(rate(mkt.quote.id())? / rate(mkt.base.id())?)
+}
+
+/// Parse an `eurofxref` document, made of daily `<Cube time='...'>` elements
+/// each holding `<Cube currency='...' rate='...'/>` entries
+pub fn parse_rates(xml: &str) -> Result<EcbRates> {
+    let mut rates = EcbRates::new();
+    let mut day = None;
+
+    for tag in xml.split("<Cube").skip(1) {
+        let tag = tag.split('>').next().unwrap_or_default();
+
+        if let Some(time) = attribute(tag, "time") {
+            let date = time.parse::<NaiveDate>().map_err(|e| {
+                DcaError::Generic(format!("Malformed ECB reference rates date '{time}': {e}"))
+            })?;
+            rates.entry(date).or_default();
+            day = Some(date);
+        } else if let (Some(currency), Some(rate)) =
+            (attribute(tag, "currency"), attribute(tag, "rate"))
+        {
+            let Some(date) = day else {
+                return Err(DcaError::Generic(format!(
+                    "ECB reference rate of {currency} outside of any day"
+                )));
+            };
+            let rate = rate.parse::<f64>().map_err(|e| {
+                DcaError::Generic(format!("Malformed ECB reference rate '{rate}': {e}"))
+            })?;
+
+            rates
+                .entry(date)
+                .or_default()
+                .insert(currency.to_lowercase(), rate);
+        }
+    }
+
+    Ok(rates)
+}
+
+/// Value of attribute `name` of an XML tag, either single or double quoted
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    tag.split_whitespace().find_map(|attr| {
+        let (key, value) = attr.split_once('=')?;
+        if key != name {
+            return None;
+        }
+
+        let value = value.trim_end_matches('/');
+        value
+            .strip_prefix('\'')
+            .and_then(|v| v.strip_suffix('\''))
+            .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+    })
+}
+
+/// English name of the currencies quoted by the ECB
+pub fn currency_name(id: &str) -> Option<&'static str> {
+    let name = match id {
+        "aud" => "Australian Dollar",
+        "bgn" => "Bulgarian Lev",
+        "brl" => "Brazilian Real",
+        "cad" => "Canadian Dollar",
+        "chf" => "Swiss franc",
+        "cny" => "Chinese Yuan Renminbi",
+        "czk" => "Czech Koruna",
+        "dkk" => "Danish Krone",
+        "eur" => "Euro",
+        "gbp" => "British Pound",
+        "hkd" => "Hong Kong Dollar",
+        "huf" => "Hungarian Forint",
+        "idr" => "Indonesian Rupiah",
+        "ils" => "Israeli Shekel",
+        "inr" => "Indian Rupee",
+        "isk" => "Icelandic Krona",
+        "jpy" => "Japanese Yen",
+        "krw" => "South Korean Won",
+        "mxn" => "Mexican Peso",
+        "myr" => "Malaysian Ringgit",
+        "nok" => "Norwegian Krone",
+        "nzd" => "New Zealand Dollar",
+        "php" => "Philippine Peso",
+        "pln" => "Polish Zloty",
+        "ron" => "Romanian Leu",
+        "sek" => "Swedish Krona",
+        "sgd" => "Singapore Dollar",
+        "thb" => "Thai Baht",
+        "try" => "Turkish Lira",
+        "usd" => "United States Dollar",
+        "zar" => "South African Rand",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::domain::entity::{Asset, Fiat};
+
+    const HIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+	<gesmes:subject>Reference rates</gesmes:subject>
+	<gesmes:Sender>
+		<gesmes:name>European Central Bank</gesmes:name>
+	</gesmes:Sender>
+	<Cube>
+		<Cube time='2025-07-14'>
+			<Cube currency='USD' rate='1.1681'/>
+			<Cube currency='GBP' rate='0.86820'/>
+		</Cube>
+		<Cube time="2025-07-11">
+			<Cube currency="USD" rate="1.1697" />
+			<Cube currency="GBP" rate="0.86645" />
+		</Cube>
+	</Cube>
+</gesmes:Envelope>"#;
+
+    fn market(base: &str, quote: &str) -> Market {
+        let fiat = |id: &str| Asset::Fiat(Fiat::new(id.to_string(), id.to_uppercase()));
+        Market::new(format!("{base}{quote}"), fiat(base), fiat(quote), None)
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_daily_cubes() {
+        let rates = parse_rates(HIST).unwrap();
+
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[&date("2025-07-14")]["usd"], 1.1681);
+        assert_eq!(rates[&date("2025-07-11")]["gbp"], 0.86645);
+        assert!(parse_rates("<Cube currency='USD' rate='1.1'/>").is_err());
+    }
+
+    #[test]
+    fn crosses_rates_through_euro() {
+        let rates = parse_rates(HIST).unwrap();
+
+        let on = |base, quote, d| cross_rate(&rates, &market(base, quote), date(d));
+        assert_eq!(on("eur", "usd", "2025-07-14"), Some(1.1681));
+        assert_eq!(on("usd", "eur", "2025-07-14"), Some(1. / 1.1681));
+        assert_eq!(on("gbp", "usd", "2025-07-11"), Some(1.1697 / 0.86645));
+        // Weekend gets last published rate
+        assert_eq!(on("eur", "usd", "2025-07-13"), Some(1.1697));
+        assert_eq!(on("eur", "usd", "2025-07-01"), None);
+        assert_eq!(on("eur", "usd", "2025-07-30"), None);
+        assert_eq!(on("eur", "jpy", "2025-07-14"), None);
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_download() {
+        let downloads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let app = axum::Router::new().fallback({
+            let downloads = downloads.clone();
+            move || async move {
+                downloads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                HIST
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut config: config::Providers = serde_json::from_value(serde_json::json!({
+            "priceProvider": "kraken",
+            "ipApiKey": "",
+        }))
+        .unwrap();
+        config.endpoints.ecb = format!("http://{addr}");
+        let ecb = EcbProvider::new(reqwest::Client::new(), &config);
+
+        let fetches = (0..8).map(|_| ecb.fetch_latest_rates());
+        for res in futures::future::join_all(fetches).await {
+            assert_eq!(res.unwrap().0, date("2025-07-14"));
+        }
+        assert_eq!(downloads.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
@@ -5,6 +5,7 @@ use crate::{config, error::Result};
 #[derive(Clone)]
 pub struct IpApi {
     http: reqwest::Client,
+    base_url: String,
     api_key: String,
 }
 
@@ -12,13 +13,14 @@ impl IpApi {
     pub fn new(http: reqwest::Client, config: &config::Providers) -> Self {
         Self {
             http,
+            base_url: config.endpoints.ipapi.clone(),
             api_key: config.ip_api_key.clone(),
         }
     }
 
     pub async fn fetch_geo(&self, ip: &str) -> Result<Option<GeoData>> {
-        let api_key = &self.api_key;
-        let url = format!("http://api.ipapi.com/{ip}?access_key={api_key}&format=1");
+        let (base_url, api_key) = (&self.base_url, &self.api_key);
+        let url = format!("{base_url}/{ip}?access_key={api_key}&format=1");
 
         let res = self.http.get(url).send().await?;
         if res.status().is_success() {
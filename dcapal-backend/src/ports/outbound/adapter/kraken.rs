@@ -1,8 +1,10 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
+    sync::Arc,
 };
 
+use chrono::NaiveDate;
 use failsafe::futures::CircuitBreaker;
 use futures::StreamExt;
 use itertools::Itertools;
@@ -11,11 +13,17 @@ use reqwest::StatusCode;
 use serde::{Deserialize, de::DeserializeOwned};
 use tracing::{debug, error, warn};
 
-use super::DefaultCircuitBreaker;
+use super::{BreakerState, DefaultCircuitBreaker, circuit_breaker};
 use crate::{
     DateTime,
-    app::domain::entity::{Asset, AssetId, Crypto, Fiat, Market, MarketId, OHLCFrequency},
-    config,
+    app::{
+        domain::entity::{
+            Asset, AssetId, Crypto, Fiat, Market, MarketId, MarketStatus, OHLCFrequency,
+        },
+        infra::{rate_limiter::TokenBucket, utils::day_start},
+        services::symbols::SymbolRegistry,
+    },
+    config::{self, PriceProvider},
     error::{DcaError, Result},
     ports::outbound::repository::market_data::MarketDataRepository,
 };
@@ -23,50 +31,70 @@ use crate::{
 #[derive(Clone)]
 pub struct KrakenProvider {
     http: reqwest::Client,
+    base_url: String,
+    cmc_base_url: String,
     cmc_api_key: Option<String>,
+    symbols: Arc<SymbolRegistry>,
+    rate_limiter: Arc<TokenBucket>,
     kraken_circuit_breaker: DefaultCircuitBreaker,
+    kraken_breaker_state: BreakerState,
     cmc_circuit_breaker: DefaultCircuitBreaker,
+    cmc_breaker_state: BreakerState,
 }
 
 impl KrakenProvider {
-    pub fn new(http: reqwest::Client, config: &config::Providers) -> Self {
-        let kraken_circuit_breaker = failsafe::Config::new().build();
-        let cmc_circuit_breaker = failsafe::Config::new().build();
+    pub fn new(
+        http: reqwest::Client,
+        config: &config::Providers,
+        symbols: Arc<SymbolRegistry>,
+    ) -> Self {
+        let (kraken_circuit_breaker, kraken_breaker_state) = circuit_breaker();
+        let (cmc_circuit_breaker, cmc_breaker_state) = circuit_breaker();
 
         Self {
             http,
+            base_url: config.endpoints.kraken.clone(),
+            cmc_base_url: config.endpoints.coinmarketcap.clone(),
             cmc_api_key: config.cmc_api_key.clone(),
+            symbols,
+            rate_limiter: Arc::new(TokenBucket::new(&config.rate_limit(PriceProvider::Kraken))),
             kraken_circuit_breaker,
+            kraken_breaker_state,
             cmc_circuit_breaker,
+            cmc_breaker_state,
         }
     }
 
+    /// Whether the circuit breaker of Kraken is not open
+    pub fn is_available(&self) -> bool {
+        !self.kraken_breaker_state.is_open()
+    }
+
+    /// Whether the circuit breaker of CoinMarketCap is not open
+    pub fn is_cmc_available(&self) -> bool {
+        !self.cmc_breaker_state.is_open()
+    }
+
+    /// Fetch assets and markets not known by `repo` yet, along with the
+    /// status of every market listed by Kraken
     pub async fn fetch_assets(
         &self,
         repo: &MarketDataRepository,
-    ) -> Result<(Vec<Asset>, Vec<Market>)> {
-        static URL: &str = "https://api.kraken.com/0/public/AssetPairs";
+    ) -> Result<(Vec<Asset>, Vec<Market>, HashMap<MarketId, MarketStatus>)> {
+        let listed = self.fetch_listings().await?;
 
-        // Fetch Kraken markets
-        debug!(url = URL, "Fetching markets from Kraken");
-        let Some(res) = self.fetch_kraken_api::<AssetPairsResponse>(URL).await? else {
-            return Err(DcaError::Generic("AssetPairs not found".to_string()));
-        };
-
-        if !res.error.is_empty() {
-            error!("Error occurred while fetching asset pairs: {:?}", res.error);
-            return Err(DcaError::Generic(format!("{:?}", res.error)));
-        }
-
-        // Filter online market symbols and rename Kraken specific pairs to standard
-        // names
-        let market_symbols = res
-            .result
-            .values()
-            .filter(|&p| (p.status == "online"))
-            .map(|p| normalize_symbol(&p.wsname))
+        // Only online markets are discovered
+        let market_symbols = listed
+            .iter()
+            .filter(|(_, status)| *status == MarketStatus::Active)
+            .map(|(symbol, _)| symbol.clone())
             .collect::<Vec<String>>();
 
+        let statuses = listed
+            .into_iter()
+            .map(|(symbol, status)| (symbol.replace('/', ""), status))
+            .collect();
+
         let (markets, assets) = if self.cmc_api_key.is_some() {
             // If CoinMarketCap API key is available, enrich assets data with human-friendly
             // info
@@ -74,47 +102,132 @@ impl KrakenProvider {
         } else {
             // If not, `Asset`s will have same `id` and `symbol`. No big deal, just less
             // fancy
-            resolve_assets_data_kraken_only(&market_symbols, repo).await
+            resolve_assets_data_basic(&market_symbols, repo).await
         };
 
         debug!("New assets: {}", serde_json::to_string(&assets).unwrap());
         debug!("New markets: {}", serde_json::to_string(&markets).unwrap());
 
-        Ok((assets, markets))
+        Ok((assets, markets, statuses))
+    }
+
+    /// Fetch the pairs listed by Kraken, named after normalized symbols
+    /// (e.g. `btc/eur`), along with their status
+    pub async fn fetch_listings(&self) -> Result<Vec<(String, MarketStatus)>> {
+        let url = format!("{}/0/public/AssetPairs", self.base_url);
+
+        // Fetch Kraken markets
+        debug!(url = url, "Fetching markets from Kraken");
+        let Some(res) = self.fetch_kraken_api::<AssetPairsResponse>(&url).await? else {
+            return Err(DcaError::Generic("AssetPairs not found".to_string()));
+        };
+
+        if !res.error.is_empty() {
+            error!("Error occurred while fetching asset pairs: {:?}", res.error);
+            return Err(DcaError::Generic(format!("{:?}", res.error)));
+        }
+
+        // Rename Kraken specific pairs to standard names. Pairs not `online`
+        // (e.g. `cancel_only`) are listed, but halted
+        let listed = res
+            .result
+            .values()
+            .filter_map(|p| {
+                let symbol = self.normalize_symbol(&p.wsname)?;
+                let status = match p.status.as_str() {
+                    "online" => MarketStatus::Active,
+                    _ => MarketStatus::Halted,
+                };
+                Some((symbol, status))
+            })
+            .collect();
+
+        Ok(listed)
     }
 
     pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
+        let pair = self.kraken_pair(mkt);
+
         if let Some(px) = self
-            .fetch_price(&mkt.id, OHLCFrequency::Minutes5, ts)
+            .fetch_price(&mkt.id, &pair, OHLCFrequency::Minutes5, ts)
             .await?
         {
             return Ok(Some(px));
         }
 
-        if let Some(px) = self.fetch_price(&mkt.id, OHLCFrequency::Daily, ts).await? {
+        if let Some(px) = self
+            .fetch_price(&mkt.id, &pair, OHLCFrequency::Daily, ts)
+            .await?
+        {
             return Ok(Some(px));
         }
 
         Ok(None)
     }
 
+    /// Fetch the close price of `mkt` daily candlestick opened on `date`.
+    /// Kraken serves the last 720 candlesticks only, so older dates are not
+    /// available
+    pub async fn fetch_daily_price(&self, mkt: &Market, date: NaiveDate) -> Result<Option<f64>> {
+        let pair = self.kraken_pair(mkt);
+        let day_start = day_start(date).timestamp();
+        let periods = get_kraken_api_periods(OHLCFrequency::Daily);
+
+        let Some(csticks) = self
+            .fetch_candlesticks(&mkt.id, &pair, periods, day_start - 1)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let Some(cstick) = csticks.0.iter().find(|c| c[0].as_i64() == Some(day_start)) else {
+            return Ok(None);
+        };
+
+        close_price(cstick).map(Some)
+    }
+
     async fn fetch_price(
         &self,
         id: &MarketId,
+        pair: &str,
         freq: OHLCFrequency,
         ts: DateTime,
     ) -> Result<Option<f64>> {
         let (r_lo, r_hi) = freq.ohlc_range(ts);
         let (after_ts, before_ts) = (r_lo.timestamp(), r_hi.timestamp());
         let periods = get_kraken_api_periods(freq);
+
+        debug!("Fetching {freq} OHLC candlestick for market '{id}' since {r_lo}");
+        let Some(csticks) = self.fetch_candlesticks(id, pair, periods, after_ts).await? else {
+            return Ok(None);
+        };
+
+        // Get most recent candlestick before `before_ts` or most recent
+        let cstick = csticks
+            .0
+            .iter()
+            .rev()
+            .find(|c| c[0].as_i64().unwrap_or(i64::MAX) <= before_ts)
+            .unwrap_or_else(|| csticks.0.iter().last().unwrap());
+
+        close_price(cstick).map(Some)
+    }
+
+    /// Fetch `periods` minutes OHLC candlesticks opened after `since`
+    async fn fetch_candlesticks(
+        &self,
+        id: &MarketId,
+        pair: &str,
+        periods: &str,
+        since: i64,
+    ) -> Result<Option<CandleSticks>> {
         let url = format!(
-            "https://api.kraken.com/0/public/OHLC?pair={id}&since={after_ts}&interval={periods}"
+            "{}/0/public/OHLC?pair={pair}&since={since}&interval={periods}",
+            self.base_url
         );
 
-        debug!(
-            url = url,
-            "Fetching {freq} OHLC candlestick for market '{id}' since {r_lo}"
-        );
+        debug!(url = url, "Fetching OHLC candlesticks for market '{id}'");
 
         let Some(mut res) = self.fetch_kraken_api::<OHLCResult>(&url).await? else {
             return Ok(None);
@@ -138,7 +251,7 @@ impl KrakenProvider {
         }
 
         result.remove("last"); // Drop fucking weird 'last' entry
-        let Payload::CandleSticks(csticks) = result.values().next().unwrap() else {
+        let Some(Payload::CandleSticks(csticks)) = result.values().next().cloned() else {
             return Err(DcaError::Generic(format!(
                 "Malformed response. Cannot find candlesticks: {res:?}"
             )));
@@ -150,22 +263,30 @@ impl KrakenProvider {
             )));
         }
 
-        // Get most recent candlestick before `before_ts` or most recent
-        let cstick = csticks
-            .0
-            .iter()
-            .rev()
-            .find(|c| c[0].as_i64().unwrap_or(i64::MAX) <= before_ts)
-            .unwrap_or_else(|| csticks.0.iter().last().unwrap());
+        Ok(Some(csticks))
+    }
 
-        let Some(close_px) = cstick[4].as_f64() else {
-            return Err(DcaError::Generic(format!(
-                "Cannot parse '{:?}' into f64",
-                cstick[4]
-            )));
+    /// Map a Kraken `wsname` (e.g. `XBT/EUR`) to a normalized market symbol
+    /// (e.g. `btc/eur`)
+    fn normalize_symbol(&self, wsname: &str) -> Option<String> {
+        let Some((base, quote)) = wsname.split_once('/') else {
+            error!("Malformed Kraken pair: {wsname}");
+            return None;
         };
 
-        Ok(Some(close_px))
+        let base = self.symbols.from_provider(base, PriceProvider::Kraken);
+        let quote = self.symbols.from_provider(quote, PriceProvider::Kraken);
+        Some(format!("{base}/{quote}"))
+    }
+
+    fn kraken_pair(&self, mkt: &Market) -> String {
+        let base = self
+            .symbols
+            .to_provider(mkt.base.id(), PriceProvider::Kraken);
+        let quote = self
+            .symbols
+            .to_provider(mkt.quote.id(), PriceProvider::Kraken);
+        format!("{base}{quote}")
     }
 
     async fn resolve_assets_data(
@@ -297,8 +418,10 @@ impl KrakenProvider {
             .filter_map(|c| (!CMC_ALIAS.contains_key(c.as_str())).then_some(c.as_str()))
             .join(",");
 
-        let url =
-            format!("https://pro-api.coinmarketcap.com/v2/cryptocurrency/info?symbol={symbols}");
+        let url = format!(
+            "{}/v2/cryptocurrency/info?symbol={symbols}",
+            self.cmc_base_url
+        );
 
         debug!(url = url, "Fetching assets data from CMC");
 
@@ -327,6 +450,7 @@ impl KrakenProvider {
     }
 
     async fn fetch_kraken_api<T: DeserializeOwned + Debug>(&self, url: &str) -> Result<Option<T>> {
+        self.rate_limiter.acquire().await;
         self.kraken_circuit_breaker
             .call(self.fetch_kraken_api_inner::<T>(url))
             .await
@@ -388,7 +512,9 @@ struct AssetPairsResponse {
     result: HashMap<String, Pair>,
 }
 
-async fn resolve_assets_data_kraken_only(
+/// Resolve new `market_symbols` into markets and assets, without any
+/// metadata: new crypto `Asset`s have the same `id` and `symbol`
+pub(super) async fn resolve_assets_data_basic(
     market_symbols: &[String],
     repo: &MarketDataRepository,
 ) -> (Vec<Market>, Vec<Asset>) {
@@ -423,13 +549,6 @@ async fn resolve_assets_data_kraken_only(
     (markets, assets)
 }
 
-fn normalize_symbol(wsname: &str) -> String {
-    wsname
-        .replace("XBT/", "BTC/")
-        .replace("/XBT", "/BTC")
-        .to_lowercase()
-}
-
 type MarketPair = (String, String);
 
 fn split_base_quote(market_symbols: &[String]) -> Vec<MarketPair> {
@@ -528,6 +647,12 @@ async fn is_new_market(id: MarketId, repo: &MarketDataRepository) -> bool {
     mkt.is_none()
 }
 
+fn close_price(cstick: &[IntOrStr; 8]) -> Result<f64> {
+    cstick[4]
+        .as_f64()
+        .ok_or_else(|| DcaError::Generic(format!("Cannot parse '{:?}' into f64", cstick[4])))
+}
+
 fn get_kraken_api_periods(freq: OHLCFrequency) -> &'static str {
     match freq {
         OHLCFrequency::Minutes5 => "5",
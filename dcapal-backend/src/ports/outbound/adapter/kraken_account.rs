@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::Utc;
+use reqwest::header;
+use ring::{digest, hmac};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::{
+    app::domain::connector::ApiCredentials,
+    config,
+    error::{DcaError, Result},
+};
+
+/// Kraken private REST API, authenticated by the API key of a user account.
+/// Only endpoints allowed to read-only keys are used
+pub struct KrakenAccountConnector {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl KrakenAccountConnector {
+    const BALANCE_PATH: &'static str = "/0/private/Balance";
+
+    pub fn new(http: reqwest::Client, config: &config::Providers) -> Self {
+        Self {
+            http,
+            base_url: config.endpoints.kraken.clone(),
+        }
+    }
+
+    /// Fetch the account balances, keyed by Kraken asset ticker (e.g. `XBT`).
+    /// Staked and earning balances are added to the ones of their asset
+    pub async fn fetch_balances(
+        &self,
+        credentials: &ApiCredentials,
+    ) -> Result<HashMap<String, Decimal>> {
+        let balances = self
+            .fetch_private_api::<HashMap<String, String>>(Self::BALANCE_PATH, credentials)
+            .await?;
+
+        let mut by_ticker = HashMap::new();
+        for (code, qty) in balances {
+            let qty = qty.parse::<Decimal>().map_err(|e| {
+                DcaError::Generic(format!("Malformed Kraken balance of {code} '{qty}': {e}"))
+            })?;
+            *by_ticker
+                .entry(balance_ticker(&code).to_string())
+                .or_insert(Decimal::ZERO) += qty;
+        }
+
+        Ok(by_ticker)
+    }
+
+    async fn fetch_private_api<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        credentials: &ApiCredentials,
+    ) -> Result<T> {
+        // Nonces must increase across requests signed by the same key
+        let nonce = Utc::now().timestamp_micros().to_string();
+        let body = format!("nonce={nonce}");
+        let signature = sign(path, &nonce, &body, &credentials.api_secret)?;
+
+        let url = format!("{}{path}", self.base_url);
+        debug!(url = url, "Fetching Kraken private API");
+        let res = self
+            .http
+            .post(&url)
+            .header("API-Key", &credentials.api_key)
+            .header("API-Sign", signature)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
+        }
+
+        let res = res.json::<PrivateResponse<T>>().await?;
+        match res.result {
+            Some(result) if res.error.is_empty() => Ok(result),
+            _ => Err(DcaError::Generic(format!(
+                "Kraken request {path} failed: {}",
+                res.error.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Kraken assets historically listed under a four letters code, prefixed by
+/// `X` for crypto and `Z` for fiat currencies
+const LEGACY_CODES: [&str; 17] = [
+    "XXBT", "XETH", "XETC", "XLTC", "XMLN", "XREP", "XXDG", "XXLM", "XXMR", "XXRP", "XZEC", "ZAUD",
+    "ZCAD", "ZEUR", "ZGBP", "ZJPY", "ZUSD",
+];
+
+/// Ticker of the asset of a balance code, stripping legacy prefixes (`XXBT`)
+/// and staking or earning suffixes (`DOT.S`, `USDC.M`, `XBT.F`)
+fn balance_ticker(code: &str) -> &str {
+    let code = code.split_once('.').map_or(code, |(ticker, _)| ticker);
+    if LEGACY_CODES.contains(&code) {
+        &code[1..]
+    } else {
+        code
+    }
+}
+
+/// `API-Sign` of a private request: the HMAC-SHA512 of the URI path followed
+/// by the SHA256 of nonce and POST data, keyed by the decoded API secret
+fn sign(path: &str, nonce: &str, body: &str, secret: &str) -> Result<String> {
+    let secret = BASE64
+        .decode(secret)
+        .map_err(|_| DcaError::BadRequest("Malformed Kraken API secret".to_string()))?;
+
+    let payload = digest::digest(&digest::SHA256, format!("{nonce}{body}").as_bytes());
+    let mut message = path.as_bytes().to_vec();
+    message.extend_from_slice(payload.as_ref());
+
+    let key = hmac::Key::new(hmac::HMAC_SHA512, &secret);
+    Ok(BASE64.encode(hmac::sign(&key, &message)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PrivateResponse<T> {
+    error: Vec<String>,
+    result: Option<T>,
+}
+
+/// Local stand-in for the Kraken private API, verifying request signatures
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use axum::{Json, Router, extract::State, http::HeaderMap, routing::post};
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    struct Account {
+        api_key: String,
+        api_secret: String,
+        balances: Value,
+    }
+
+    /// Serve an account holding `balances` on a random local port, returning
+    /// its base URL
+    pub(crate) async fn start(api_key: &str, api_secret: &str, balances: Value) -> String {
+        let account = Arc::new(Account {
+            api_key: api_key.to_string(),
+            api_secret: api_secret.to_string(),
+            balances,
+        });
+
+        let app = Router::new()
+            .route(KrakenAccountConnector::BALANCE_PATH, post(balance))
+            .with_state(account);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        format!("http://{addr}")
+    }
+
+    async fn balance(
+        State(account): State<Arc<Account>>,
+        headers: HeaderMap,
+        body: String,
+    ) -> Json<Value> {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+
+        if header("API-Key") != Some(account.api_key.as_str()) {
+            return Json(json!({ "error": ["EAPI:Invalid key"] }));
+        }
+
+        let nonce = body
+            .split('&')
+            .find_map(|kv| kv.strip_prefix("nonce="))
+            .unwrap_or_default();
+        let expected = sign(
+            KrakenAccountConnector::BALANCE_PATH,
+            nonce,
+            &body,
+            &account.api_secret,
+        )
+        .ok();
+        if nonce.is_empty() || header("API-Sign") != expected.as_deref() {
+            return Json(json!({ "error": ["EAPI:Invalid signature"] }));
+        }
+
+        Json(json!({ "error": [], "result": account.balances }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const SECRET: &str =
+        "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==";
+
+    fn connector(base_url: String) -> KrakenAccountConnector {
+        KrakenAccountConnector {
+            http: reqwest::Client::new(),
+            base_url,
+        }
+    }
+
+    fn credentials(api_secret: &str) -> ApiCredentials {
+        ApiCredentials {
+            api_key: "key".to_string(),
+            api_secret: api_secret.to_string(),
+        }
+    }
+
+    #[test]
+    fn signs_requests_as_documented() {
+        let nonce = "1616492376594";
+        let body =
+            format!("nonce={nonce}&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25");
+
+        assert_eq!(
+            sign("/0/private/AddOrder", nonce, &body, SECRET).unwrap(),
+            "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ=="
+        );
+    }
+
+    #[test]
+    fn maps_balance_codes_to_tickers() {
+        assert_eq!(balance_ticker("XXBT"), "XBT");
+        assert_eq!(balance_ticker("ZEUR"), "EUR");
+        assert_eq!(balance_ticker("ETH2.S"), "ETH2");
+        assert_eq!(balance_ticker("XBT.F"), "XBT");
+        assert_eq!(balance_ticker("USDC"), "USDC");
+        assert_eq!(balance_ticker("XTZ"), "XTZ");
+    }
+
+    #[tokio::test]
+    async fn fetches_aggregated_balances() {
+        let base_url = mock::start(
+            "key",
+            SECRET,
+            json!({
+                "XXBT": "0.5000000000",
+                "XBT.F": "0.25",
+                "ZEUR": "120.3400",
+                "DOT.S": "10.0",
+            }),
+        )
+        .await;
+
+        let balances = connector(base_url.clone())
+            .fetch_balances(&credentials(SECRET))
+            .await
+            .unwrap();
+        assert_eq!(balances.len(), 3);
+        assert_eq!(balances["XBT"], Decimal::new(75, 2));
+        assert_eq!(balances["EUR"], Decimal::new(12034, 2));
+        assert_eq!(balances["DOT"], Decimal::new(10, 0));
+
+        let wrong_secret = BASE64.encode(b"wrong secret");
+        let err = connector(base_url)
+            .fetch_balances(&credentials(&wrong_secret))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("EAPI:Invalid signature"));
+    }
+}
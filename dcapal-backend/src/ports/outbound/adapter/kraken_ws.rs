@@ -0,0 +1,189 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, de::IgnoredAny};
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
+use tracing::{debug, error, warn};
+
+use crate::{
+    app::{
+        domain::entity::{Market, MarketId},
+        services::symbols::SymbolRegistry,
+    },
+    config::PriceProvider,
+    error::{DcaError, Result},
+};
+
+/// Client for Kraken ticker WebSocket feed
+#[derive(Clone)]
+pub struct KrakenTickerFeed {
+    url: String,
+    symbols: Arc<SymbolRegistry>,
+}
+
+/// Last trade price of a market, as pushed by [`KrakenTickerFeed`]
+#[derive(Debug, Clone)]
+pub struct Tick {
+    pub market: MarketId,
+    pub price: f64,
+}
+
+impl KrakenTickerFeed {
+    pub fn new(url: String, symbols: Arc<SymbolRegistry>) -> Self {
+        Self { url, symbols }
+    }
+
+    /// Open a new connection to the ticker feed
+    pub async fn connect(&self) -> Result<KrakenTickerConnection> {
+        debug!(url = self.url, "Connecting to Kraken ticker feed");
+        let (ws, _) = tokio_tungstenite::connect_async(&self.url).await?;
+
+        Ok(KrakenTickerConnection {
+            ws,
+            symbols: self.symbols.clone(),
+            pairs: HashMap::new(),
+        })
+    }
+}
+
+pub struct KrakenTickerConnection {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    symbols: Arc<SymbolRegistry>,
+    pairs: HashMap<String, MarketId>,
+}
+
+impl KrakenTickerConnection {
+    /// Subscribe to ticker updates of `markets` not subscribed yet
+    pub async fn subscribe(&mut self, markets: &[Market]) -> Result<()> {
+        let new_pairs = markets
+            .iter()
+            .map(|m| (self.ws_pair(m), m.id.clone()))
+            .filter(|(pair, _)| !self.pairs.contains_key(pair))
+            .collect::<Vec<_>>();
+
+        if new_pairs.is_empty() {
+            return Ok(());
+        }
+
+        let msg = json!({
+            "event": "subscribe",
+            "pair": new_pairs.iter().map(|(p, _)| p).collect::<Vec<_>>(),
+            "subscription": { "name": "ticker" }
+        });
+        self.ws.send(Message::text(msg.to_string())).await?;
+
+        debug!("Subscribed to {} Kraken tickers", new_pairs.len());
+        self.pairs.extend(new_pairs);
+
+        Ok(())
+    }
+
+    /// Wait for the next ticker update. Returns `None` once the connection is
+    /// closed
+    pub async fn next_tick(&mut self) -> Option<Result<Tick>> {
+        while let Some(msg) = self.ws.next().await {
+            let text = match msg {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(frame)) => {
+                    warn!("Kraken ticker feed closed: {frame:?}");
+                    return None;
+                }
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            match serde_json::from_str::<FeedMessage>(&text) {
+                Ok(FeedMessage::Ticker(_, ticker, _, pair)) => {
+                    let Some(market) = self.pairs.get(&pair) else {
+                        warn!("Received ticker for unknown Kraken pair '{pair}'");
+                        continue;
+                    };
+
+                    let Some(price) = ticker.last_price() else {
+                        error!("Malformed Kraken ticker for '{pair}': {text}");
+                        continue;
+                    };
+
+                    return Some(Ok(Tick {
+                        market: market.clone(),
+                        price,
+                    }));
+                }
+                Ok(FeedMessage::Event(event)) => {
+                    if event.status.as_deref() == Some("error") {
+                        error!("Kraken ticker feed '{}' error: {text}", event.event);
+                    }
+                }
+                Err(e) => {
+                    let err = DcaError::JsonDeserializationFailure(
+                        text.to_string(),
+                        std::any::type_name::<FeedMessage>().to_string(),
+                        e,
+                    );
+                    error!("{:?}", err);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn ws_pair(&self, mkt: &Market) -> String {
+        let base = self
+            .symbols
+            .to_provider(mkt.base.id(), PriceProvider::Kraken);
+        let quote = self
+            .symbols
+            .to_provider(mkt.quote.id(), PriceProvider::Kraken);
+        format!("{}/{}", base.to_uppercase(), quote.to_uppercase())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FeedMessage {
+    /// `[channel id, ticker data, channel name, pair]`
+    Ticker(IgnoredAny, TickerData, IgnoredAny, String),
+    Event(EventData),
+}
+
+#[derive(Debug, Deserialize)]
+struct TickerData {
+    /// Last trade closed: `[price, lot volume]`
+    c: [String; 2],
+}
+
+impl TickerData {
+    fn last_price(&self) -> Option<f64> {
+        self.c[0].parse().ok().filter(|px: &f64| px.is_normal())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EventData {
+    event: String,
+    status: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_feed_messages() {
+        let ticker = r#"[340,{"a":["5525.4","1","1.0"],"b":["5525.1","1","1.0"],"c":["5525.3","0.0039"],"v":["2634.1","3000.2"],"p":["5631.4","5653.6"],"t":[11493,16267],"l":["5505.0","5505.0"],"h":["5783.0","5783.0"],"o":["5760.7","5763.4"]},"ticker","XBT/USD"]"#;
+        let Ok(FeedMessage::Ticker(_, data, _, pair)) = serde_json::from_str(ticker) else {
+            panic!("Failed to parse ticker message");
+        };
+        assert_eq!(pair, "XBT/USD");
+        assert_eq!(data.last_price(), Some(5525.3));
+
+        let heartbeat = r#"{"event":"heartbeat"}"#;
+        assert!(matches!(
+            serde_json::from_str(heartbeat),
+            Ok(FeedMessage::Event(_))
+        ));
+    }
+}
@@ -1,31 +1,114 @@
 //! The [`adapter`](self) module contains adapters to third-party services
 
+mod binance;
 mod cw;
+mod ecb;
 mod ipapi;
 mod kraken;
+mod kraken_account;
+mod kraken_ws;
+mod stub;
 mod yahoo;
 
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
+pub use binance::*;
 pub use cw::*;
+pub use ecb::*;
 use failsafe::{
-    StateMachine,
+    Instrument, StateMachine,
     backoff::EqualJittered,
     failure_policy::{ConsecutiveFailures, OrElse, SuccessRateOverTimeWindow},
 };
 pub use ipapi::*;
 pub use kraken::*;
+pub use kraken_account::*;
+pub use kraken_ws::*;
+pub use stub::*;
 pub use yahoo::*;
 
 type DefaultCircuitBreaker = StateMachine<
     OrElse<SuccessRateOverTimeWindow<EqualJittered>, ConsecutiveFailures<EqualJittered>>,
-    (),
+    BreakerState,
 >;
 
+/// Circuit breaker with its [`BreakerState`]
+fn circuit_breaker() -> (DefaultCircuitBreaker, BreakerState) {
+    let state = BreakerState::default();
+    let breaker = failsafe::Config::new().instrument(state.clone()).build();
+    (breaker, state)
+}
+
+/// Whether a circuit breaker is open, tracked from its transitions. Unlike
+/// requesting a call permit, reading it never moves an open breaker to
+/// half-open
+#[derive(Clone, Default)]
+struct BreakerState(Arc<AtomicBool>);
+
+impl BreakerState {
+    fn is_open(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Instrument for BreakerState {
+    fn on_call_rejected(&self) {}
+
+    fn on_open(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn on_half_open(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    fn on_closed(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone)]
 pub struct PriceProviders {
-    pub cw: Arc<CryptoWatchProvider>,
+    pub binance: Arc<BinanceProvider>,
+    /// Only available if CryptoWatch API key is configured
+    pub cw: Option<Arc<CryptoWatchProvider>>,
+    pub ecb: Arc<EcbProvider>,
     pub kraken: Arc<KrakenProvider>,
     pub yahoo: Arc<YahooProvider>,
     pub ipapi: Arc<IpApi>,
 }
+
+impl PriceProviders {
+    /// Whether the circuit breaker of each provider guarded by one is not
+    /// open
+    pub fn circuit_breakers(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("binance", self.binance.is_available()),
+            ("coinmarketcap", self.kraken.is_cmc_available()),
+            ("kraken", self.kraken.is_available()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_state_follows_transitions() {
+        let (breaker, state) = circuit_breaker();
+        assert!(!state.is_open());
+
+        for _ in 0..10 {
+            breaker.on_error();
+        }
+        assert!(state.is_open());
+        assert!(!breaker.is_call_permitted());
+
+        breaker.reset();
+        assert!(!state.is_open());
+    }
+}
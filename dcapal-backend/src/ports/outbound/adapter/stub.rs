@@ -0,0 +1,232 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tracing::{debug, error, info, warn};
+
+use crate::error::{DcaError, Result};
+
+/// Local HTTP server standing in for third-party APIs, replying with the
+/// responses recorded under a fixtures directory.
+///
+/// A request to `/<provider>/<path>?<query>` is served with the first existing
+/// file among:
+/// - `<fixtures>/<provider>/<path>/<query value>.json`, for each query value
+/// - `<fixtures>/<provider>/<path>.json`
+/// - `<fixtures>/<provider>/<path>`, for non-JSON documents (e.g. XML)
+///
+/// so that, for instance, Kraken OHLC candlesticks can be recorded per pair
+/// (`kraken/0/public/OHLC/xbteur.json`)
+pub struct StubServer;
+
+impl StubServer {
+    /// Serve `fixtures_path` on a random local port, returning the bound
+    /// address
+    pub async fn start(fixtures_path: &str) -> Result<SocketAddr> {
+        let root = PathBuf::from(fixtures_path);
+        if !root.is_dir() {
+            return Err(DcaError::StartupFailure(
+                format!("Invalid stub fixtures path: {fixtures_path}"),
+                anyhow::anyhow!("Not a directory"),
+            ));
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|e| {
+            DcaError::StartupFailure("Failed to start stub server".into(), e.into())
+        })?;
+        let addr = listener.local_addr().map_err(|e| {
+            DcaError::StartupFailure("Failed to start stub server".into(), e.into())
+        })?;
+
+        let app = Router::new()
+            .fallback(serve_fixture)
+            .with_state(Arc::new(root));
+
+        info!("Serving stub fixtures from '{fixtures_path}' at {addr}");
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("Stub server died: {e:?}");
+            }
+        });
+
+        Ok(addr)
+    }
+}
+
+async fn serve_fixture(State(root): State<Arc<PathBuf>>, uri: Uri) -> Response {
+    for path in fixture_candidates(&root, uri.path(), uri.query()) {
+        if let Ok(body) = tokio::fs::read(&path).await {
+            debug!("Serving '{uri}' with fixture {path:?}");
+            let content_type = match path.extension().and_then(|e| e.to_str()) {
+                Some("xml") => "application/xml",
+                _ => "application/json",
+            };
+            return ([(header::CONTENT_TYPE, content_type)], body).into_response();
+        }
+    }
+
+    warn!("No stub fixture found for '{uri}'");
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("No fixture found for {}", uri.path()) })),
+    )
+        .into_response()
+}
+
+/// Fixture files able to serve a request, most specific first. Paths escaping
+/// `root` are never returned
+fn fixture_candidates(root: &Path, path: &str, query: Option<&str>) -> Vec<PathBuf> {
+    let is_safe = |s: &str| !s.is_empty() && s != "." && s != ".." && !s.contains('\\');
+
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    if segments.is_empty() || !segments.iter().all(|s| is_safe(s)) {
+        return vec![];
+    }
+
+    let base = segments.iter().fold(root.to_path_buf(), |p, s| p.join(s));
+
+    let mut candidates = query
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|kv| kv.split_once('=').map(|(_, v)| v))
+        .filter(|v| is_safe(v) && !v.contains('/'))
+        .map(|v| base.join(format!("{v}.json")))
+        .collect::<Vec<_>>();
+
+    let mut file = base.clone().into_os_string();
+    file.push(".json");
+    candidates.push(file.into());
+    candidates.push(base);
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    use super::*;
+    use crate::{
+        app::{
+            domain::{
+                conversion::MarketGraph,
+                entity::{Asset, Crypto, Fiat, Market, MarketStatus, Price},
+            },
+            services::symbols::SymbolRegistry,
+        },
+        config::{Endpoints, Providers},
+        ports::outbound::{adapter::KrakenProvider, repository::symbol::SymbolRepository},
+    };
+
+    #[tokio::test]
+    async fn providers_run_against_bundled_fixtures() {
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/config/dcapal/fixtures");
+        let addr = StubServer::start(fixtures).await.unwrap();
+
+        let mut config: Providers = serde_json::from_value(json!({
+            "priceProvider": "kraken",
+            "ipApiKey": "",
+        }))
+        .unwrap();
+        config.endpoints = Endpoints::stub(addr);
+
+        // Symbol storage is unreachable: bundled mappings are served
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let symbols = Arc::new(SymbolRegistry::new(Arc::new(SymbolRepository {
+            db_conn: db,
+        })));
+        symbols.import(None).await.unwrap();
+
+        let kraken = KrakenProvider::new(reqwest::Client::new(), &config, symbols);
+
+        // Discovery
+        let listed = kraken.fetch_listings().await.unwrap();
+        assert!(listed.contains(&("btc/eur".to_string(), MarketStatus::Active)));
+        assert!(listed.contains(&("rep/eur".to_string(), MarketStatus::Halted)));
+
+        // Price update
+        let eur = Asset::Fiat(Fiat::new("eur".into(), "Euro".into()));
+        let usd = Asset::Fiat(Fiat::new("usd".into(), "US Dollar".into()));
+        let eth = Asset::Crypto(Crypto::new_with_id("eth".into()));
+        let mut markets = vec![
+            Market::new("etheur".into(), eth, eur.clone(), None),
+            Market::new("eurusd".into(), eur, usd, None),
+        ];
+        for m in &mut markets {
+            let px = kraken.fetch_market_price(m, Utc::now()).await.unwrap();
+            m.set_price(Price::new(px.unwrap(), Utc::now()));
+        }
+
+        // Conversion through the euro, as no eth/usd market is known
+        let graph = MarketGraph::new(markets.iter());
+        let conversion = graph
+            .find_rate(&"eth".into(), &"usd".into(), 2, |id| {
+                markets
+                    .iter()
+                    .find(|m| &m.id == id)
+                    .and_then(|m| *m.price())
+            })
+            .unwrap();
+
+        let (etheur, eurusd) = (markets[0].price().unwrap(), markets[1].price().unwrap());
+        assert_eq!(conversion.markets, vec!["etheur", "eurusd"]);
+        assert_eq!(conversion.price.price, etheur.price * eurusd.price);
+    }
+
+    #[test]
+    fn query_values_take_precedence() {
+        let root = Path::new("fixtures");
+
+        assert_eq!(
+            fixture_candidates(
+                root,
+                "/kraken/0/public/OHLC",
+                Some("pair=xbteur&since=1&interval=5")
+            ),
+            vec![
+                root.join("kraken/0/public/OHLC/xbteur.json"),
+                root.join("kraken/0/public/OHLC/1.json"),
+                root.join("kraken/0/public/OHLC/5.json"),
+                root.join("kraken/0/public/OHLC.json"),
+                root.join("kraken/0/public/OHLC"),
+            ]
+        );
+        assert_eq!(
+            fixture_candidates(root, "/yahoo/v8/finance/chart/VWCE.DE", None),
+            vec![
+                root.join("yahoo/v8/finance/chart/VWCE.DE.json"),
+                root.join("yahoo/v8/finance/chart/VWCE.DE"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_paths_escaping_root() {
+        let root = Path::new("fixtures");
+
+        assert!(fixture_candidates(root, "/kraken/../../etc/passwd", None).is_empty());
+        assert!(fixture_candidates(root, "/", None).is_empty());
+        assert_eq!(
+            fixture_candidates(root, "/ipapi/1.2.3.4", Some("access_key=..&format=1")),
+            vec![
+                root.join("ipapi/1.2.3.4/1.json"),
+                root.join("ipapi/1.2.3.4.json"),
+                root.join("ipapi/1.2.3.4"),
+            ]
+        );
+    }
+}
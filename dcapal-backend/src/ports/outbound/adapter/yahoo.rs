@@ -1,25 +1,47 @@
-use std::collections::HashMap;
+use std::sync::Arc;
 
-use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
-use tracing::{debug, error, warn};
+use chrono::{NaiveDate, TimeZone, Utc};
+use tracing::{debug, warn};
 
 use crate::{
     DateTime,
-    app::domain::entity::{Market, OHLCFrequency},
+    app::{
+        domain::{
+            chart::{Candle, Chart, ChartResolution, SymbolInfo, SymbolSource},
+            corporate_action::{CorporateAction, CorporateActionKind},
+            entity::{Asset, Market, OHLCFrequency},
+        },
+        infra::{rate_limiter::TokenBucket, utils::day_start},
+        services::symbols::SymbolRegistry,
+    },
+    config::{self, PriceProvider},
     error::{DcaError, Result},
 };
 
 #[derive(Clone)]
 pub struct YahooProvider {
     http: rquest::Client,
+    chart_url: String,
+    search_url: String,
+    symbols: Arc<SymbolRegistry>,
+    rate_limiter: Arc<TokenBucket>,
+    search_rate_limiter: Arc<TokenBucket>,
 }
 
 impl YahooProvider {
-    pub fn new(http: rquest::Client) -> Self {
-        Self { http }
+    pub fn new(
+        http: rquest::Client,
+        config: &config::Providers,
+        symbols: Arc<SymbolRegistry>,
+    ) -> Self {
+        Self {
+            http,
+            chart_url: format!("{}/v8/finance/chart", config.endpoints.yahoo_chart),
+            search_url: format!("{}/v1/finance/search", config.endpoints.yahoo_search),
+            symbols,
+            rate_limiter: Arc::new(TokenBucket::new(&config.rate_limit(PriceProvider::Yahoo))),
+            search_rate_limiter: Arc::new(TokenBucket::new(&config.search_rate_limit)),
+        }
     }
 
     pub async fn fetch_market_price(&self, mkt: &Market, ts: DateTime) -> Result<Option<f64>> {
@@ -38,18 +60,39 @@ impl YahooProvider {
         }
     }
 
+    /// Fetch the close price of `mkt` on `date`
+    pub async fn fetch_daily_price(&self, mkt: &Market, date: NaiveDate) -> Result<Option<f64>> {
+        let day_start = day_start(date);
+        let day_end = day_start + chrono::Duration::days(1);
+
+        self.fetch_range_price(mkt, OHLCFrequency::Daily, day_start, day_end)
+            .await
+    }
+
     async fn fetch_price(
         &self,
         mkt: &Market,
         freq: OHLCFrequency,
         ts: DateTime,
     ) -> Result<Option<f64>> {
-        let symbol = mkt.as_yahoo();
-        let interval = get_api_interval(freq);
         let (r_lo, r_hi) = freq.ohlc_range(ts);
+        self.fetch_range_price(mkt, freq, r_lo, r_hi).await
+    }
+
+    /// Fetch the last close price of `mkt` in range [`r_lo`, `r_hi`]
+    async fn fetch_range_price(
+        &self,
+        mkt: &Market,
+        freq: OHLCFrequency,
+        r_lo: DateTime,
+        r_hi: DateTime,
+    ) -> Result<Option<f64>> {
+        let symbol = mkt.as_yahoo(&self.symbols);
+        let interval = get_api_interval(freq);
         let (period_1, period_2) = (r_lo.timestamp(), r_hi.timestamp());
         let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{symbol}?period1={period_1}&period2={period_2}&interval={interval}"
+            "{}/{symbol}?period1={period_1}&period2={period_2}&interval={interval}",
+            self.chart_url
         );
 
         debug!(
@@ -61,6 +104,7 @@ impl YahooProvider {
             r_hi
         );
 
+        self.rate_limiter.acquire().await;
         let res = self.http.get(&url).send().await?;
         if !res.status().is_success() {
             if res.status() == reqwest::StatusCode::NOT_FOUND {
@@ -117,36 +161,180 @@ impl YahooProvider {
         }
     }
 
-    pub async fn search(&self, request_param: String) -> Response {
-        let url = format!("https://query2.finance.yahoo.com/v1/finance/search?q={request_param}");
-        self.forward(url).await
+    /// Search symbols matching `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<SymbolInfo>> {
+        let url = &self.search_url;
+
+        debug!(url = url, "Searching symbols matching '{query}'");
+        self.search_rate_limiter.acquire().await;
+        let res = self.http.get(url).query(&[("q", query)]).send().await?;
+        if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
+        }
+
+        let res = res.json::<search::SearchResponse>().await?;
+        let results = res
+            .quotes
+            .into_iter()
+            .filter_map(|q| {
+                Some(SymbolInfo {
+                    name: q.longname.or(q.shortname).unwrap_or_default(),
+                    symbol: q.symbol,
+                    quote_type: q.quote_type?,
+                    exchange: q.exchange,
+                    exchange_name: q.exch_disp,
+                    isin: None,
+                    source: SymbolSource::Yahoo,
+                })
+            })
+            .collect();
+
+        Ok(results)
     }
 
-    pub async fn chart(&self, symbol: String, start_period: i64, end_period: i64) -> Response {
+    /// Fetch `symbol` OHLC series in range [`start`, `end`]. Returns `None`
+    /// if the symbol is unknown
+    pub async fn fetch_chart(
+        &self,
+        symbol: &str,
+        resolution: ChartResolution,
+        start: i64,
+        end: i64,
+    ) -> Result<Option<Chart>> {
+        let interval = match resolution {
+            ChartResolution::Minutes5 => "5m",
+            ChartResolution::Hour1 => "1h",
+            ChartResolution::Daily => "1d",
+            ChartResolution::Weekly => "1wk",
+        };
         let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{symbol}?period1={start_period}&period2={end_period}&interval=5m&close=adjusted"
+            "{}/{symbol}?period1={start}&period2={end}&interval={interval}",
+            self.chart_url
         );
-        self.forward(url).await
-    }
 
-    async fn forward(&self, url: String) -> Response {
-        match self.http.get(url).send().await {
-            Ok(res) => {
-                let mut response = Response::builder().status(res.status());
+        debug!(url = url, "Fetching {resolution} chart of '{symbol}'");
+        self.rate_limiter.acquire().await;
+        let res = self.http.get(&url).send().await?;
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
+        }
 
-                // Copy headers
-                for (key, value) in res.headers() {
-                    response = response.header(key, value);
-                }
+        let res = res.json::<chart::ChartResponse>().await?;
+        if let Some(e) = res.chart.error {
+            warn!(
+                url = url,
+                "Unsuccessful request. Code: {}. Description: {}", e.code, e.description
+            );
+            return Ok(None);
+        }
 
-                let body = axum::body::Body::from_stream(res.bytes_stream());
-                response.body(body).unwrap().into_response()
-            }
-            Err(e) => {
-                error!("Proxy error: {e:?}");
-                StatusCode::BAD_GATEWAY.into_response()
-            }
+        let Some(result) = res.chart.result.and_then(|r| r.into_iter().next()) else {
+            return Ok(None);
+        };
+
+        let candles = match result.indicators.quote.first() {
+            Some(chart::QuotesKind::Quotes(q)) => result
+                .timestamp
+                .iter()
+                .enumerate()
+                .filter_map(|(i, ts)| {
+                    let close = q.close.get(i).copied().flatten()?;
+                    let at = |v: &Vec<Option<f64>>| v.get(i).copied().flatten().unwrap_or(close);
+                    Some(Candle {
+                        ts: *ts,
+                        open: at(&q.open),
+                        high: at(&q.high),
+                        low: at(&q.low),
+                        close,
+                        volume: q.volume.get(i).copied().flatten(),
+                    })
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let meta = result.meta;
+        Ok(Some(Chart {
+            symbol: meta.symbol.unwrap_or_else(|| symbol.to_uppercase()),
+            name: meta.long_name.or(meta.short_name),
+            currency: meta.currency,
+            exchange: meta.exchange_name,
+            timezone: meta.exchange_timezone_name,
+            instrument_type: meta.instrument_type,
+            resolution,
+            candles,
+        }))
+    }
+    /// Fetch `symbol` splits and dividends with ex-date in range [`start`,
+    /// `end`]
+    pub async fn fetch_corporate_actions(
+        &self,
+        symbol: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CorporateAction>> {
+        let (period_1, period_2) = (day_start(start).timestamp(), day_start(end).timestamp());
+        let url = format!(
+            "{}/{symbol}?period1={period_1}&period2={period_2}&interval=1d&events=div,splits",
+            self.chart_url
+        );
+
+        debug!(url = url, "Fetching corporate actions of '{symbol}'");
+        self.rate_limiter.acquire().await;
+        let res = self.http.get(&url).send().await?;
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(vec![]);
         }
+        if !res.status().is_success() {
+            return Err(res.error_for_status().unwrap_err().into());
+        }
+
+        let res = res.json::<chart::ChartResponse>().await?;
+        if let Some(e) = res.chart.error {
+            warn!(
+                url = url,
+                "Unsuccessful request. Code: {}. Description: {}", e.code, e.description
+            );
+            return Ok(vec![]);
+        }
+
+        let Some(result) = res.chart.result.and_then(|r| r.into_iter().next()) else {
+            return Ok(vec![]);
+        };
+        let Some(events) = result.events else {
+            return Ok(vec![]);
+        };
+
+        let ex_date = |ts: i64| Utc.timestamp_opt(ts, 0).single().map(|d| d.date_naive());
+        let symbol = symbol.to_uppercase();
+        let splits = events.splits.into_values().filter_map(|s| {
+            Some(CorporateAction {
+                symbol: symbol.clone(),
+                ex_date: ex_date(s.date)?,
+                kind: CorporateActionKind::Split {
+                    numerator: s.numerator,
+                    denominator: s.denominator,
+                },
+            })
+        });
+        let dividends = events.dividends.into_values().filter_map(|d| {
+            Some(CorporateAction {
+                symbol: symbol.clone(),
+                ex_date: ex_date(d.date)?,
+                kind: CorporateActionKind::Dividend {
+                    amount: d.amount,
+                    currency: result.meta.currency.clone(),
+                },
+            })
+        });
+
+        let mut actions = splits.chain(dividends).collect::<Vec<_>>();
+        actions.sort_by_key(|a| a.ex_date);
+
+        Ok(actions)
     }
 }
 
@@ -158,15 +346,14 @@ fn get_api_interval(freq: OHLCFrequency) -> &'static str {
 }
 
 pub trait AsYahooMarket {
-    fn as_yahoo(&self) -> String;
+    fn as_yahoo(&self, symbols: &SymbolRegistry) -> String;
 }
 
 impl AsYahooMarket for Market {
-    fn as_yahoo(&self) -> String {
-        lazy_static::lazy_static! {
-            static ref MAPPINGS: HashMap<&'static str, &'static str> = {
-                [("luna", ("luna1"))].into_iter().collect()
-            };
+    fn as_yahoo(&self, symbols: &SymbolRegistry) -> String {
+        // Equities are quoted in their listing currency
+        if let Asset::Equity(e) = &self.base {
+            return e.symbol.clone();
         }
 
         if self.is_fiat() {
@@ -177,21 +364,16 @@ impl AsYahooMarket for Market {
             }
         }
 
-        let base = MAPPINGS
-            .get(self.base.id().as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| self.base.id().clone());
-
-        let quote = MAPPINGS
-            .get(self.quote.id().as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| self.quote.id().clone());
+        let base = symbols.to_provider(self.base.id(), PriceProvider::Yahoo);
+        let quote = symbols.to_provider(self.quote.id(), PriceProvider::Yahoo);
 
         format!("{}-{}", base, quote)
     }
 }
 
 mod chart {
+    use std::collections::HashMap;
+
     use serde::Deserialize;
 
     #[derive(Debug, Clone, Deserialize)]
@@ -213,7 +395,45 @@ mod chart {
 
     #[derive(Debug, Clone, Deserialize)]
     pub struct Candlestick {
+        #[serde(default)]
+        pub meta: Meta,
+        #[serde(default)]
+        pub timestamp: Vec<i64>,
         pub indicators: Indicators,
+        pub events: Option<Events>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct Events {
+        #[serde(default)]
+        pub dividends: HashMap<String, Dividend>,
+        #[serde(default)]
+        pub splits: HashMap<String, Split>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Dividend {
+        pub amount: f64,
+        pub date: i64,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Split {
+        pub date: i64,
+        pub numerator: f64,
+        pub denominator: f64,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Meta {
+        pub symbol: Option<String>,
+        pub currency: Option<String>,
+        pub exchange_name: Option<String>,
+        pub exchange_timezone_name: Option<String>,
+        pub instrument_type: Option<String>,
+        pub long_name: Option<String>,
+        pub short_name: Option<String>,
     }
 
     #[derive(Debug, Clone, Deserialize)]
@@ -231,5 +451,34 @@ mod chart {
     #[derive(Debug, Clone, Deserialize)]
     pub struct Quotes {
         pub close: Vec<Option<f64>>,
+        #[serde(default)]
+        pub open: Vec<Option<f64>>,
+        #[serde(default)]
+        pub high: Vec<Option<f64>>,
+        #[serde(default)]
+        pub low: Vec<Option<f64>>,
+        #[serde(default)]
+        pub volume: Vec<Option<f64>>,
+    }
+}
+
+mod search {
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct SearchResponse {
+        #[serde(default)]
+        pub quotes: Vec<Quote>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Quote {
+        pub symbol: String,
+        pub shortname: Option<String>,
+        pub longname: Option<String>,
+        pub quote_type: Option<String>,
+        pub exchange: Option<String>,
+        pub exch_disp: Option<String>,
     }
 }
@@ -0,0 +1,99 @@
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, SqlxPostgresConnector, entity::*, prelude::DateTimeWithTimeZone, sqlx,
+};
+use uuid::Uuid;
+
+use crate::{
+    app::domain::db::{exchange_connection, portfolios},
+    error::Result,
+};
+
+/// Exchange accounts linked to user portfolios. API credentials are stored
+/// encrypted by the caller
+pub struct ConnectionRepository {
+    pub db_conn: DatabaseConnection,
+}
+
+impl ConnectionRepository {
+    pub fn new(postgres: sqlx::PgPool) -> Self {
+        let db_conn = SqlxPostgresConnector::from_sqlx_postgres_pool(postgres);
+        Self { db_conn }
+    }
+
+    pub async fn insert(
+        &self,
+        connection: exchange_connection::ActiveModel,
+    ) -> Result<exchange_connection::Model> {
+        Ok(connection.insert(&self.db_conn).await?)
+    }
+
+    pub async fn find(
+        &self,
+        user_id: Uuid,
+        connection_id: Uuid,
+    ) -> Result<Option<exchange_connection::Model>> {
+        let connection = exchange_connection::Entity::find_by_id(connection_id)
+            .filter(exchange_connection::Column::UserId.eq(user_id))
+            .one(&self.db_conn)
+            .await?;
+
+        Ok(connection)
+    }
+
+    /// Connections of `user_id`, oldest first
+    pub async fn find_by_user(&self, user_id: Uuid) -> Result<Vec<exchange_connection::Model>> {
+        let connections = exchange_connection::Entity::find()
+            .filter(exchange_connection::Column::UserId.eq(user_id))
+            .order_by_asc(exchange_connection::Column::CreatedAt)
+            .all(&self.db_conn)
+            .await?;
+
+        Ok(connections)
+    }
+
+    /// Connections linked to portfolios not deleted
+    pub async fn find_active(&self) -> Result<Vec<exchange_connection::Model>> {
+        let connections = exchange_connection::Entity::find()
+            .join(
+                JoinType::InnerJoin,
+                exchange_connection::Relation::Portfolios.def(),
+            )
+            .filter(portfolios::Column::Deleted.eq(false))
+            .all(&self.db_conn)
+            .await?;
+
+        Ok(connections)
+    }
+
+    /// Delete `user_id` connection. Returns `false` if the user has no such
+    /// connection
+    pub async fn delete(&self, user_id: Uuid, connection_id: Uuid) -> Result<bool> {
+        let res = exchange_connection::Entity::delete_many()
+            .filter(exchange_connection::Column::Id.eq(connection_id))
+            .filter(exchange_connection::Column::UserId.eq(user_id))
+            .exec(&self.db_conn)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Record the outcome of a sync: successful syncs clear the last error
+    pub async fn record_sync(
+        &self,
+        connection: exchange_connection::Model,
+        error: Option<String>,
+    ) -> Result<exchange_connection::Model> {
+        let now: DateTimeWithTimeZone = Utc::now().into();
+
+        let mut model = connection.into_active_model();
+        if error.is_none() {
+            model.last_synced_at = Set(Some(now));
+        }
+        model.last_error = Set(error);
+        model.updated_at = Set(now);
+
+        Ok(model.update(&self.db_conn).await?)
+    }
+}
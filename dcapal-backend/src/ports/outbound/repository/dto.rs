@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::app::domain::entity::{AssetId, Market, MarketId, Price};
+use crate::{
+    DateTime,
+    app::domain::entity::{AssetId, Market, MarketId, MarketStatus, Price},
+    config::PriceProvider,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MarketDto {
@@ -8,6 +12,19 @@ pub struct MarketDto {
     pub pair: String,
     pub base: AssetId,
     pub quote: AssetId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<PriceProvider>,
+    #[serde(default)]
+    pub status: MarketStatus,
+    #[serde(
+        rename = "statusSince",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrono::serde::ts_seconds_option"
+    )]
+    pub status_since: Option<DateTime>,
     #[serde(flatten)]
     pub price: Option<Price>,
 }
@@ -23,6 +40,10 @@ impl From<Market> for MarketDto {
             pair: m.pair,
             base,
             quote,
+            exchange: m.exchange,
+            provider: m.provider,
+            status: m.status,
+            status_since: m.status_since,
             price,
         }
     }
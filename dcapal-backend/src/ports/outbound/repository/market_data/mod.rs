@@ -1,9 +1,21 @@
+mod redis_action;
 mod redis_asset;
+mod redis_history;
 mod redis_market;
+mod redis_tracked;
 
-use self::{redis_asset::RedisAsset, redis_market::RedisMarket};
+use chrono::NaiveDate;
+
+use self::{
+    redis_action::RedisCorporateAction, redis_asset::RedisAsset, redis_history::RedisPriceHistory,
+    redis_market::RedisMarket, redis_tracked::RedisTrackedMarket,
+};
 use crate::{
-    app::domain::entity::{Asset, AssetId, AssetKind, Market, MarketId},
+    DateTime,
+    app::domain::{
+        corporate_action::CorporateAction,
+        entity::{Asset, AssetId, AssetKind, Market, MarketId, Price, TrackedMarket},
+    },
     error::{DcaError, Result},
 };
 
@@ -23,6 +35,12 @@ impl MarketDataRepository {
         Asset::find_by_id(id, &mut redis).await
     }
 
+    pub async fn find_assets(&self, ids: &[&AssetId]) -> Result<Vec<Option<Asset>>> {
+        let mut redis = self.redis.get().await?;
+
+        Asset::find_by_ids(ids, &mut redis).await
+    }
+
     pub async fn load_assets_by_type(&self, kind: AssetKind) -> Result<Vec<Asset>> {
         let mut redis = self.redis.get().await?;
 
@@ -37,6 +55,12 @@ impl MarketDataRepository {
         Ok(())
     }
 
+    pub async fn delete_asset(&self, asset: &Asset) -> Result<bool> {
+        let mut redis = self.redis.get().await?;
+
+        asset.delete(&mut redis).await
+    }
+
     pub async fn find_market(&self, id: &MarketId) -> Result<Option<Market>> {
         let mut redis = self.redis.get().await?;
 
@@ -46,7 +70,7 @@ impl MarketDataRepository {
     pub async fn find_markets(&self, ids: &[&MarketId]) -> Result<Vec<Option<Market>>> {
         let mut redis = self.redis.get().await?;
 
-        Market::find_by_ids(ids, &mut redis, self).await
+        Market::find_by_ids(ids, &mut redis).await
     }
 
     pub async fn store_market(&self, market: &Market) -> Result<()> {
@@ -71,9 +95,86 @@ impl MarketDataRepository {
         Ok(())
     }
 
+    /// Update `market` lifecycle status only, preserving its stored price.
+    /// Returns `false` if the market no longer exists
+    pub async fn update_market_status(&self, market: &Market) -> Result<bool> {
+        let mut redis = self.redis.get().await?;
+
+        Market::store_status(&market.id, market.status, market.status_since, &mut redis).await
+    }
+
     pub async fn load_markets(&self) -> Result<Vec<Market>> {
         let mut redis = self.redis.get().await?;
 
         Market::load_all(&mut redis, self).await
     }
+
+    pub async fn delete_market(&self, id: &MarketId) -> Result<bool> {
+        let mut redis = self.redis.get().await?;
+
+        Market::delete(id, &mut redis).await
+    }
+
+    /// Daily close price of market `id` on `date`, if stored
+    pub async fn find_daily_price(&self, id: &MarketId, date: NaiveDate) -> Result<Option<Price>> {
+        let mut redis = self.redis.get().await?;
+
+        Price::find_daily(id, date, &mut redis).await
+    }
+
+    pub async fn store_daily_price(
+        &self,
+        id: &MarketId,
+        date: NaiveDate,
+        price: &Price,
+    ) -> Result<()> {
+        let mut redis = self.redis.get().await?;
+
+        price.store_daily(id, date, &mut redis).await
+    }
+
+    /// Rescale the daily prices of market `id` stored before `date` by
+    /// `factor`
+    pub async fn adjust_daily_prices(
+        &self,
+        id: &MarketId,
+        date: NaiveDate,
+        factor: f64,
+    ) -> Result<u32> {
+        let mut redis = self.redis.get().await?;
+
+        Price::adjust_before(id, date, factor, &mut redis).await
+    }
+
+    /// Store a corporate action, if not stored yet. Returns whether it was new
+    pub async fn store_corporate_action(&self, action: &CorporateAction) -> Result<bool> {
+        let mut redis = self.redis.get().await?;
+
+        action.store(&mut redis).await
+    }
+
+    pub async fn load_corporate_actions(&self, symbol: &str) -> Result<Vec<CorporateAction>> {
+        let mut redis = self.redis.get().await?;
+
+        CorporateAction::load_by_symbol(symbol, &mut redis).await
+    }
+
+    /// Mark markets as referenced at `ts`, tracking them if not yet tracked
+    pub async fn touch_tracked_markets(&self, ids: &[&MarketId], ts: DateTime) -> Result<()> {
+        let mut redis = self.redis.get().await?;
+
+        TrackedMarket::touch(ids, ts, &mut redis).await
+    }
+
+    pub async fn load_tracked_markets(&self) -> Result<Vec<TrackedMarket>> {
+        let mut redis = self.redis.get().await?;
+
+        TrackedMarket::load_all(&mut redis).await
+    }
+
+    pub async fn untrack_markets(&self, ids: &[&MarketId]) -> Result<u32> {
+        let mut redis = self.redis.get().await?;
+
+        TrackedMarket::untrack(ids, &mut redis).await
+    }
 }
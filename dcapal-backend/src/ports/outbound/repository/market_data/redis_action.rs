@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use tracing::{debug, error};
+
+use crate::{
+    app::domain::corporate_action::CorporateAction,
+    error::{DcaError, Result},
+    ports::outbound::repository::REDIS_BASE,
+};
+
+const ACTION_KEY: &str = concatcp!(REDIS_BASE, ':', "action");
+
+/// Corporate actions of an equity, stored as a hash of action keys to JSON
+#[async_trait]
+pub trait RedisCorporateAction {
+    /// Store the action, if not stored yet. Returns whether it was new
+    async fn store(&self, conn: &mut impl redis::AsyncCommands) -> Result<bool>;
+
+    async fn load_by_symbol(
+        symbol: &str,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Vec<CorporateAction>>;
+}
+
+#[async_trait]
+impl RedisCorporateAction for CorporateAction {
+    async fn store(&self, conn: &mut impl redis::AsyncCommands) -> Result<bool> {
+        let key = action_key(&self.symbol);
+        let json = serde_json::to_string(self).unwrap();
+        let is_new: bool = conn.hset_nx(&key, self.key(), &json).await?;
+
+        if is_new {
+            debug!("Successfully stored '{key} {}': {json}", self.key());
+        }
+        Ok(is_new)
+    }
+
+    async fn load_by_symbol(
+        symbol: &str,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Vec<Self>> {
+        let jsons: Vec<String> = conn.hvals(action_key(symbol)).await?;
+
+        let mut actions = jsons
+            .into_iter()
+            .filter_map(|json| match serde_json::from_str::<Self>(&json) {
+                Ok(a) => Some(a),
+                Err(e) => {
+                    let err = DcaError::JsonDeserializationFailure(
+                        json,
+                        std::any::type_name::<CorporateAction>().to_string(),
+                        e,
+                    );
+                    error!("{:?}", err);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        actions.sort_by_key(|a| a.ex_date);
+
+        Ok(actions)
+    }
+}
+
+fn action_key(symbol: &str) -> String {
+    format!("{ACTION_KEY}:{}", symbol.to_uppercase())
+}
@@ -19,10 +19,17 @@ pub trait RedisAsset {
         conn: &mut impl redis::AsyncCommands,
     ) -> Result<Option<Asset>>;
 
+    async fn find_by_ids(
+        ids: &[&AssetId],
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Vec<Option<Asset>>>;
+
     async fn load_by_type(
         kind: AssetKind,
         conn: &mut impl redis::AsyncCommands,
     ) -> Result<Vec<Asset>>;
+
+    async fn delete(&self, conn: &mut impl redis::AsyncCommands) -> Result<bool>;
 }
 
 #[async_trait]
@@ -66,6 +73,42 @@ impl RedisAsset for Asset {
         Ok(Some(asset))
     }
 
+    async fn find_by_ids(
+        ids: &[&AssetId],
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Vec<Option<Self>>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let jsons: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(ASSET_KEY)
+            .arg(ids)
+            .query_async(conn)
+            .await?;
+
+        let assets = jsons
+            .into_iter()
+            .map(|json| {
+                let json = json?;
+                match serde_json::from_str(&json) {
+                    Ok(a) => Some(a),
+                    Err(e) => {
+                        let err = DcaError::JsonDeserializationFailure(
+                            json,
+                            std::any::type_name::<Asset>().to_string(),
+                            e,
+                        );
+                        error!("{:?}", err);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(assets)
+    }
+
     async fn load_by_type(
         kind: AssetKind,
         conn: &mut impl redis::AsyncCommands,
@@ -102,6 +145,17 @@ impl RedisAsset for Asset {
 
         Ok(assets)
     }
+
+    async fn delete(&self, conn: &mut impl redis::AsyncCommands) -> Result<bool> {
+        let (n_deleted, _): (u32, u32) = redis::pipe()
+            .atomic()
+            .hdel(ASSET_KEY, self.id())
+            .zrem(self.kind().as_index(), self.id())
+            .query_async(conn)
+            .await?;
+
+        Ok(n_deleted > 0)
+    }
 }
 
 trait AsRedisIndex {
@@ -113,6 +167,7 @@ impl AsRedisIndex for AssetKind {
         match self {
             AssetKind::Crypto => concatcp!(ASSET_INDEX_TYPE, ':', "crypto"),
             AssetKind::Fiat => concatcp!(ASSET_INDEX_TYPE, ':', "fiat"),
+            AssetKind::Equity => concatcp!(ASSET_INDEX_TYPE, ':', "equity"),
         }
     }
 }
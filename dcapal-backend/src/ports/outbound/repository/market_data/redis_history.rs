@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use tracing::debug;
+
+use crate::{
+    app::{
+        domain::entity::{MarketId, Price},
+        infra::utils::day_start,
+    },
+    error::{DcaError, Result},
+    ports::outbound::repository::REDIS_BASE,
+};
+
+const HISTORY_KEY: &str = concatcp!(REDIS_BASE, ':', "history");
+
+/// Attempts to adjust a price history stored concurrently by other writers
+const MAX_ADJUST_ATTEMPTS: usize = 5;
+
+/// Daily close prices of a market, stored as a hash of `YYYY-MM-DD` dates to
+/// prices. A second hash maps each date to the day its price is split-adjusted
+/// as of, since providers already adjust historical closes for the splits
+/// known when fetched
+#[async_trait]
+pub trait RedisPriceHistory {
+    async fn store_daily(
+        &self,
+        id: &MarketId,
+        date: NaiveDate,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<()>;
+
+    async fn find_daily(
+        id: &MarketId,
+        date: NaiveDate,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Option<Price>>;
+
+    /// Rescale the prices stored before `date` by `factor`, e.g. to express
+    /// them in post-split shares. Prices already adjusted as of `date` are
+    /// left untouched. Returns the number of prices adjusted
+    async fn adjust_before(
+        id: &MarketId,
+        date: NaiveDate,
+        factor: f64,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<u32>;
+}
+
+#[async_trait]
+impl RedisPriceHistory for Price {
+    async fn store_daily(
+        &self,
+        id: &MarketId,
+        date: NaiveDate,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<()> {
+        let key = history_key(id);
+        let today = Utc::now().date_naive().to_string();
+        let _: () = redis::pipe()
+            .atomic()
+            .hset(&key, date.to_string(), self.price)
+            .ignore()
+            .hset(adjusted_key(id), date.to_string(), today)
+            .ignore()
+            .query_async(conn)
+            .await?;
+
+        debug!("Successfully stored '{key} {date}': {}", self.price);
+        Ok(())
+    }
+
+    async fn find_daily(
+        id: &MarketId,
+        date: NaiveDate,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<Option<Self>> {
+        let price: Option<f64> = conn.hget(history_key(id), date.to_string()).await?;
+
+        Ok(price.map(|px| Price::new(px, day_start(date))))
+    }
+
+    async fn adjust_before(
+        id: &MarketId,
+        date: NaiveDate,
+        factor: f64,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<u32> {
+        let key = history_key(id);
+        let as_of_key = adjusted_key(id);
+
+        // Optimistic locking: the transaction aborts if any price is stored
+        // in the meantime
+        for _ in 0..MAX_ADJUST_ATTEMPTS {
+            let _: () = redis::cmd("WATCH")
+                .arg(&key)
+                .arg(&as_of_key)
+                .query_async(conn)
+                .await?;
+
+            let prices: HashMap<String, f64> = conn.hgetall(&key).await?;
+            let as_of: HashMap<String, String> = conn.hgetall(&as_of_key).await?;
+
+            // Dates are ISO formatted, so they compare as strings
+            let date = date.to_string();
+            let adjusted = prices
+                .into_iter()
+                .filter(|(d, _)| *d < date)
+                .filter(|(d, _)| as_of.get(d).is_none_or(|a| *a < date))
+                .map(|(d, px)| (d, px * factor))
+                .collect::<Vec<_>>();
+            if adjusted.is_empty() {
+                let _: () = redis::cmd("UNWATCH").query_async(conn).await?;
+                return Ok(0);
+            }
+
+            let as_of = adjusted
+                .iter()
+                .map(|(d, _)| (d.as_str(), date.as_str()))
+                .collect::<Vec<_>>();
+            let res: Option<()> = redis::pipe()
+                .atomic()
+                .hset_multiple(&key, &adjusted)
+                .ignore()
+                .hset_multiple(&as_of_key, &as_of)
+                .ignore()
+                .query_async(conn)
+                .await?;
+            if res.is_some() {
+                debug!(
+                    "Adjusted {} prices of '{key}' before {date} by {factor}",
+                    adjusted.len()
+                );
+                return Ok(adjusted.len() as u32);
+            }
+        }
+
+        Err(DcaError::RepositoryStoreFailure(key))
+    }
+}
+
+fn history_key(id: &MarketId) -> String {
+    format!("{HISTORY_KEY}:{id}")
+}
+
+fn adjusted_key(id: &MarketId) -> String {
+    format!("{HISTORY_KEY}:{id}:adjusted")
+}
@@ -1,10 +1,13 @@
+use std::collections::{HashMap, HashSet};
+
 use async_trait::async_trait;
 use futures::{StreamExt, TryStreamExt};
 use tracing::{debug, error};
 
-use super::MarketDataRepository;
+use super::{MarketDataRepository, redis_asset::RedisAsset};
 use crate::{
-    app::domain::entity::{Market, MarketId},
+    DateTime,
+    app::domain::entity::{Asset, AssetId, Market, MarketId, MarketStatus},
     error::{DcaError, Result},
     ports::outbound::repository::{REDIS_BASE, dto::MarketDto},
 };
@@ -24,15 +27,26 @@ pub trait RedisMarket {
     async fn find_by_ids(
         ids: &[&MarketId],
         conn: &mut impl redis::AsyncCommands,
-        repo: &MarketDataRepository,
     ) -> Result<Vec<Option<Market>>>;
 
     async fn load_all(
         conn: &mut impl redis::AsyncCommands,
         repo: &MarketDataRepository,
     ) -> Result<Vec<Market>>;
+
+    async fn store_status(
+        id: &MarketId,
+        status: MarketStatus,
+        since: Option<DateTime>,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<bool>;
+
+    async fn delete(id: &MarketId, conn: &mut impl redis::AsyncCommands) -> Result<bool>;
 }
 
+/// Attempts to update a market stored concurrently by other writers
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+
 #[async_trait]
 impl RedisMarket for Market {
     async fn store(&self, conn: &mut impl redis::AsyncCommands) -> Result<bool> {
@@ -68,45 +82,73 @@ impl RedisMarket for Market {
     async fn find_by_ids(
         ids: &[&MarketId],
         conn: &mut impl redis::AsyncCommands,
-        repo: &MarketDataRepository,
     ) -> Result<Vec<Option<Market>>> {
-        let jsons: Vec<Option<String>> = redis::cmd("HMGET").arg(ids).query_async(conn).await?;
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let jsons: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(MARKET_KEY)
+            .arg(ids)
+            .query_async(conn)
+            .await?;
 
         let dtos: Vec<Option<MarketDto>> = jsons
             .into_iter()
-            .flat_map(|json| {
-                json.map(|j| {
-                    let dto: Result<MarketDto> = serde_json::from_str(&j).map_err(|e| {
-                        DcaError::JsonDeserializationFailure(
-                            j,
+            .map(|json| {
+                let json = json?;
+                match serde_json::from_str(&json) {
+                    Ok(dto) => Some(dto),
+                    Err(e) => {
+                        let err = DcaError::JsonDeserializationFailure(
+                            json,
                             std::any::type_name::<MarketDto>().to_string(),
                             e,
-                        )
-                    });
-
-                    match dto {
-                        Ok(dto) => Some(dto),
-                        Err(e) => {
-                            error!("{:?}", e);
-                            None
-                        }
+                        );
+                        error!("{:?}", err);
+                        None
                     }
-                })
+                }
             })
             .collect();
 
-        let markets = futures::stream::iter(dtos)
-            .then(|m| async move {
-                if let Some(m) = m {
-                    resolve_market(m, repo).await
-                } else {
-                    Ok(None)
+        // Resolve base and quote assets of all markets at once
+        let asset_ids: Vec<&AssetId> = dtos
+            .iter()
+            .flatten()
+            .flat_map(|m| [&m.base, &m.quote])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let assets: HashMap<AssetId, Asset> = Asset::find_by_ids(&asset_ids, conn)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|a| (a.id().clone(), a))
+            .collect();
+
+        let markets = dtos
+            .into_iter()
+            .map(|m| {
+                let m = m?;
+                match (assets.get(&m.base), assets.get(&m.quote)) {
+                    (None, _) => {
+                        error!(mkt = m.id, "Base asset not found: {}", &m.base);
+                        None
+                    }
+                    (_, None) => {
+                        error!(mkt = m.id, "Quote asset not found: {}", &m.quote);
+                        None
+                    }
+                    (Some(b), Some(q)) => Some(
+                        Market::new(m.id, b.clone(), q.clone(), m.price)
+                            .with_exchange(m.exchange)
+                            .with_provider(m.provider)
+                            .with_status(m.status, m.status_since),
+                    ),
                 }
             })
-            .inspect_err(|e| error!("Failed to resolve MarketDto: {}", e))
-            .filter_map(|m| async move { m.ok() })
-            .collect()
-            .await;
+            .collect();
 
         Ok(markets)
     }
@@ -146,6 +188,60 @@ impl RedisMarket for Market {
 
         Ok(markets)
     }
+
+    async fn store_status(
+        id: &MarketId,
+        status: MarketStatus,
+        since: Option<DateTime>,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<bool> {
+        // Optimistic locking: the transaction aborts if any market is stored
+        // in the meantime, e.g. on price updates
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let _: () = redis::cmd("WATCH")
+                .arg(MARKET_KEY)
+                .query_async(conn)
+                .await?;
+
+            let json: Option<String> = conn.hget(MARKET_KEY, id).await?;
+            let Some(json) = json else {
+                let _: () = redis::cmd("UNWATCH").query_async(conn).await?;
+                return Ok(false);
+            };
+
+            let mut dto: MarketDto = serde_json::from_str(&json).map_err(|e| {
+                DcaError::JsonDeserializationFailure(
+                    json,
+                    std::any::type_name::<MarketDto>().to_string(),
+                    e,
+                )
+            })?;
+            dto.status = status;
+            dto.status_since = since;
+            let json = serde_json::to_string(&dto).unwrap();
+
+            let res: Option<()> = redis::pipe()
+                .atomic()
+                .hset(MARKET_KEY, id, &json)
+                .ignore()
+                .query_async(conn)
+                .await?;
+            if res.is_some() {
+                debug!(
+                    "Successfully updated '{} {}' status: {}",
+                    MARKET_KEY, id, json
+                );
+                return Ok(true);
+            }
+        }
+
+        Err(DcaError::RepositoryStoreFailure(id.clone()))
+    }
+
+    async fn delete(id: &MarketId, conn: &mut impl redis::AsyncCommands) -> Result<bool> {
+        let n_deleted: u32 = conn.hdel(MARKET_KEY, id).await?;
+        Ok(n_deleted > 0)
+    }
 }
 
 async fn resolve_market(market: MarketDto, repo: &MarketDataRepository) -> Result<Option<Market>> {
@@ -163,6 +259,11 @@ async fn resolve_market(market: MarketDto, repo: &MarketDataRepository) -> Resul
             error!(mkt = market.id, "Quote asset not found: {}", &market.quote);
             Ok(None)
         }
-        (Some(b), Some(q)) => Ok(Some(Market::new(market.id, b, q, market.price))),
+        (Some(b), Some(q)) => Ok(Some(
+            Market::new(market.id, b, q, market.price)
+                .with_exchange(market.exchange)
+                .with_provider(market.provider)
+                .with_status(market.status, market.status_since),
+        )),
     }
 }
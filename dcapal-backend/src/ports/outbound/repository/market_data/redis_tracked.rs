@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use tracing::debug;
+
+use crate::{
+    DateTime,
+    app::domain::entity::{MarketId, TrackedMarket},
+    error::Result,
+    ports::outbound::repository::REDIS_BASE,
+};
+
+const TRACKED_KEY: &str = concatcp!(REDIS_BASE, ':', "tracked");
+
+#[async_trait]
+pub trait RedisTrackedMarket {
+    async fn touch(
+        ids: &[&MarketId],
+        ts: DateTime,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<()>;
+
+    async fn load_all(conn: &mut impl redis::AsyncCommands) -> Result<Vec<TrackedMarket>>;
+
+    async fn untrack(ids: &[&MarketId], conn: &mut impl redis::AsyncCommands) -> Result<u32>;
+}
+
+#[async_trait]
+impl RedisTrackedMarket for TrackedMarket {
+    async fn touch(
+        ids: &[&MarketId],
+        ts: DateTime,
+        conn: &mut impl redis::AsyncCommands,
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let items: Vec<(&MarketId, i64)> = ids.iter().map(|id| (*id, ts.timestamp())).collect();
+        let _: () = conn.hset_multiple(TRACKED_KEY, &items).await?;
+
+        debug!("Tracked {} markets (referenced_at={ts})", ids.len());
+        Ok(())
+    }
+
+    async fn load_all(conn: &mut impl redis::AsyncCommands) -> Result<Vec<TrackedMarket>> {
+        let entries: Vec<(MarketId, i64)> = conn.hgetall(TRACKED_KEY).await?;
+
+        let tracked = entries
+            .into_iter()
+            .filter_map(|(id, ts)| {
+                Some(TrackedMarket {
+                    id,
+                    referenced_at: Utc.timestamp_opt(ts, 0).single()?,
+                })
+            })
+            .collect();
+
+        Ok(tracked)
+    }
+
+    async fn untrack(ids: &[&MarketId], conn: &mut impl redis::AsyncCommands) -> Result<u32> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let n_deleted: u32 = conn.hdel(TRACKED_KEY, ids).await?;
+        Ok(n_deleted)
+    }
+}
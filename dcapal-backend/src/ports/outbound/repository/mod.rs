@@ -1,16 +1,23 @@
 //! The [`repository`](self) module contains interfaces to persistent storage
 //! services, like Redis.
 
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use chrono::{TimeZone, Utc};
 use redis::AsyncCommands;
+use serde::{Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
-use crate::{DateTime, app::services::ip2location::GeoData, error::Result};
+use crate::{
+    DateTime,
+    app::services::ip2location::GeoData,
+    error::{DcaError, Result},
+};
+pub mod connection;
 pub mod dto;
 pub mod market_data;
 pub mod portfolio;
+pub mod symbol;
 pub mod user;
 
 const REDIS_BASE: &str = "dcapal:be";
@@ -124,6 +131,52 @@ impl StatsRepository {
     }
 }
 
+/// Short-lived cache of JSON values, e.g. third-party API responses
+#[derive(Clone)]
+pub struct CacheRepository {
+    redis: deadpool_redis::Pool,
+}
+
+impl CacheRepository {
+    const CACHE: &'static str = concatcp!(REDIS_BASE, ':', "cache");
+
+    pub fn new(redis: deadpool_redis::Pool) -> Self {
+        Self { redis }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let mut redis = self.redis.get().await?;
+
+        let json: Option<String> = redis.get(Self::redis_cache_key(key)).await?;
+        let Some(json) = json else {
+            return Ok(None);
+        };
+
+        let value = serde_json::from_str(&json).map_err(|e| {
+            DcaError::JsonDeserializationFailure(json, std::any::type_name::<T>().to_string(), e)
+        })?;
+
+        Ok(Some(value))
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) -> Result<()> {
+        let mut redis = self.redis.get().await?;
+
+        let json = serde_json::to_string(value).map_err(|e| {
+            DcaError::JsonSerializationFailure(std::any::type_name::<T>().to_string(), e)
+        })?;
+        let _: () = redis
+            .set_ex(Self::redis_cache_key(key), json, ttl.as_secs().max(1))
+            .await?;
+
+        Ok(())
+    }
+
+    fn redis_cache_key(key: &str) -> String {
+        format!("{}:{}", Self::CACHE, key)
+    }
+}
+
 #[derive(Clone)]
 pub struct ImportedRepository {
     redis: deadpool_redis::Pool,
@@ -1,12 +1,23 @@
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, Utc};
 use rust_decimal::Decimal;
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter,
-    SqlxPostgresConnector, TransactionTrait, entity::*, sqlx,
+    ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, SqlxPostgresConnector, TransactionTrait,
+    entity::*,
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, OnConflict},
+    sqlx,
 };
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
-    app::domain::db::{portfolio_asset, portfolios},
+    app::{
+        domain::db::{portfolio_asset, portfolio_cash_flow, portfolios},
+        infra::utils::day_start,
+    },
     error::{DcaError, Result},
     ports::inbound::rest::{
         FeeStructure,
@@ -18,6 +29,35 @@ pub struct PortfolioRepository {
     pub db_conn: DatabaseConnection,
 }
 
+/// Asset held by at least one user portfolio
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PortfolioSymbol {
+    pub symbol: String,
+    pub currency: String,
+    pub provider: String,
+    pub asset_class: String,
+}
+
+impl PortfolioSymbol {
+    /// Provider of portfolio assets priced by Yahoo Finance
+    const YAHOO_PROVIDER: &'static str = "YF";
+    const EQUITY_CLASS: &'static str = "EQUITY";
+
+    /// Whether the asset is an equity listed on Yahoo Finance
+    pub fn is_yahoo_equity(&self) -> bool {
+        self.provider == Self::YAHOO_PROVIDER && self.asset_class == Self::EQUITY_CLASS
+    }
+}
+
+/// Balance of an asset held on an exchange account linked to a portfolio
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeHolding {
+    pub symbol: String,
+    pub name: String,
+    pub asset_class: String,
+    pub quantity: Decimal,
+}
+
 #[derive(Default)]
 struct FeeFields {
     max_fee_impact: ActiveValue<Option<Decimal>>,
@@ -29,6 +69,13 @@ struct FeeFields {
 }
 
 impl PortfolioRepository {
+    /// Kind of the cash flows paid by dividends
+    pub const DIVIDEND: &'static str = "DIVIDEND";
+    /// Provider of portfolio assets priced by DCA-Pal markets
+    pub const DCAPAL_PROVIDER: &'static str = "DCAPal";
+    pub const CRYPTO_CLASS: &'static str = "CRYPTO";
+    pub const CURRENCY_CLASS: &'static str = "CURRENCY";
+
     pub fn new(postgres: sqlx::PgPool) -> Self {
         let db_conn = SqlxPostgresConnector::from_sqlx_postgres_pool(postgres);
         Self { db_conn }
@@ -47,11 +94,64 @@ impl PortfolioRepository {
         Ok(portfolios_with_assets)
     }
 
-    pub async fn soft_delete(&self, portfolio_id: Uuid) -> Result<()> {
-        if let Some(portfolio_db) = portfolios::Entity::find_by_id(portfolio_id)
-            .one(&self.db_conn)
+    /// Distinct assets held by portfolios not deleted
+    pub async fn find_portfolio_symbols(&self) -> Result<Vec<PortfolioSymbol>> {
+        let symbols = portfolio_asset::Entity::find()
+            .select_only()
+            .columns([
+                portfolio_asset::Column::Symbol,
+                portfolio_asset::Column::Currency,
+                portfolio_asset::Column::Provider,
+                portfolio_asset::Column::AssetClass,
+            ])
+            .distinct()
+            .join(
+                JoinType::InnerJoin,
+                portfolio_asset::Relation::Portfolios.def(),
+            )
+            .filter(portfolios::Column::Deleted.eq(false))
+            .into_tuple::<(String, String, String, String)>()
+            .all(&self.db_conn)
             .await?
-        {
+            .into_iter()
+            .map(
+                |(symbol, currency, provider, asset_class)| PortfolioSymbol {
+                    symbol,
+                    currency,
+                    provider,
+                    asset_class,
+                },
+            )
+            .collect();
+
+        Ok(symbols)
+    }
+
+    /// Ids of the portfolios among `portfolio_ids` owned by another user
+    /// than `user_id`
+    pub async fn find_foreign_portfolios(
+        &self,
+        user_id: Uuid,
+        portfolio_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>> {
+        if portfolio_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let foreign = portfolios::Entity::find()
+            .filter(portfolios::Column::Id.is_in(portfolio_ids.iter().copied()))
+            .filter(portfolios::Column::UserId.ne(user_id))
+            .all(&self.db_conn)
+            .await?
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+
+        Ok(foreign)
+    }
+
+    pub async fn soft_delete(&self, user_id: Uuid, portfolio_id: Uuid) -> Result<()> {
+        if let Some(portfolio_db) = Self::find_owned(&self.db_conn, user_id, portfolio_id).await? {
             let mut portfolio: portfolios::ActiveModel = portfolio_db.into();
             portfolio.deleted = Set(true);
             portfolio.update(&self.db_conn).await?;
@@ -72,9 +172,8 @@ impl PortfolioRepository {
             .db_conn
             .transaction::<_, (portfolios::Model, Vec<portfolio_asset::Model>), DcaError>(|txn| {
                 Box::pin(async move {
-                    let existing_portfolio = portfolios::Entity::find_by_id(portfolio_req.id)
-                        .one(txn)
-                        .await?;
+                    let existing_portfolio =
+                        Self::find_owned(txn, user_id, portfolio_req.id).await?;
 
                     let mut portfolio_model = if let Some(existing) = existing_portfolio.clone() {
                         existing.into_active_model()
@@ -109,6 +208,7 @@ impl PortfolioRepository {
                         txn,
                         portfolio_req.id,
                         portfolio_req.assets,
+                        portfolio_req.last_updated_at.into(),
                     )
                     .await?;
 
@@ -124,6 +224,7 @@ impl PortfolioRepository {
         txn: &DatabaseTransaction,
         portfolio_id: Uuid,
         assets: Vec<PortfolioAssetRequest>,
+        as_of: DateTimeWithTimeZone,
     ) -> Result<Vec<portfolio_asset::Model>> {
         let mut updated_assets = Vec::new();
 
@@ -154,6 +255,9 @@ impl PortfolioRepository {
             asset_model.asset_class = Set(asset.aclass.clone());
             asset_model.currency = Set(asset.base_ccy.clone());
             asset_model.provider = Set(asset.provider.clone());
+            if existing_asset.is_none_or(|a| a.quantity != asset.qty) {
+                asset_model.quantity_as_of = Set(as_of);
+            }
             asset_model.quantity = Set(asset.qty);
             asset_model.target_weight = Set(asset.target_weight);
             asset_model.price = Set(asset.price);
@@ -185,6 +289,286 @@ impl PortfolioRepository {
         Ok(updated_assets)
     }
 
+    /// Multiply by `ratio` the quantity of `symbol` holdings last set before
+    /// `ex_date`, dividing their price accordingly. Portfolios adjusted are
+    /// marked as updated, so that clients pull them on next sync. Returns the
+    /// number of holdings adjusted
+    pub async fn apply_split(
+        &self,
+        symbol: &str,
+        ex_date: NaiveDate,
+        ratio: Decimal,
+    ) -> Result<u64> {
+        let symbol = symbol.to_string();
+        let ex_ts: DateTimeWithTimeZone = day_start(ex_date).into();
+
+        let adjusted = self
+            .db_conn
+            .transaction::<_, u64, DcaError>(|txn| {
+                Box::pin(async move {
+                    let holdings = Self::find_holdings(txn, &symbol)
+                        .await?
+                        .into_iter()
+                        .filter(|h| h.quantity_as_of < ex_ts)
+                        .collect::<Vec<_>>();
+
+                    let mut portfolio_ids = HashSet::new();
+                    for holding in &holdings {
+                        let mut model = holding.clone().into_active_model();
+                        model.quantity = Set(holding.quantity * ratio);
+                        model.price = Set(holding.price / ratio);
+                        model.quantity_as_of = Set(ex_ts);
+                        model.update(txn).await?;
+
+                        portfolio_ids.insert(holding.portfolio_id);
+                    }
+
+                    if !portfolio_ids.is_empty() {
+                        let now: DateTimeWithTimeZone = Utc::now().into();
+                        portfolios::Entity::update_many()
+                            .col_expr(portfolios::Column::LastUpdatedAt, Expr::value(now))
+                            .filter(portfolios::Column::Id.is_in(portfolio_ids))
+                            .exec(txn)
+                            .await?;
+                    }
+
+                    Ok(holdings.len() as u64)
+                })
+            })
+            .await?;
+
+        Ok(adjusted)
+    }
+
+    /// Record a dividend of `amount` per share of `symbol` as a cash flow of
+    /// each portfolio holding it since before `ex_date`. Quantities set after
+    /// `ex_date` are converted back to the shares held on `ex_date` through
+    /// `later_splits`, the `(ex_date, ratio)` of the splits following the
+    /// dividend. Dividends already recorded are skipped. Returns the number of
+    /// cash flows recorded
+    pub async fn record_dividend(
+        &self,
+        symbol: &str,
+        ex_date: NaiveDate,
+        amount: Decimal,
+        currency: Option<&str>,
+        later_splits: &[(NaiveDate, Decimal)],
+    ) -> Result<u64> {
+        let ex_ts: DateTimeWithTimeZone = day_start(ex_date).into();
+        let cash_flows = Self::find_holdings(&self.db_conn, symbol)
+            .await?
+            .into_iter()
+            .filter(|h| h.created_at < ex_ts)
+            .filter_map(|h| {
+                let quantity = h.quantity / split_factor(&h, ex_date, later_splits);
+                (quantity > Decimal::ZERO).then_some((h, quantity))
+            })
+            .map(|(h, quantity)| portfolio_cash_flow::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                portfolio_id: Set(h.portfolio_id),
+                symbol: Set(h.symbol),
+                kind: Set(Self::DIVIDEND.to_string()),
+                amount: Set(quantity * amount),
+                currency: Set(currency.map_or(h.currency, str::to_string)),
+                ex_date: Set(ex_date),
+                reinvested: Set(false),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        if cash_flows.is_empty() {
+            return Ok(0);
+        }
+
+        let recorded = portfolio_cash_flow::Entity::insert_many(cash_flows)
+            .on_conflict(
+                OnConflict::columns([
+                    portfolio_cash_flow::Column::PortfolioId,
+                    portfolio_cash_flow::Column::Symbol,
+                    portfolio_cash_flow::Column::Kind,
+                    portfolio_cash_flow::Column::ExDate,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db_conn)
+            .await?;
+
+        Ok(recorded)
+    }
+
+    /// `user_id` portfolio, unless deleted
+    pub async fn find_user_portfolio(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Option<portfolios::Model>> {
+        let portfolio = portfolios::Entity::find_by_id(portfolio_id)
+            .filter(portfolios::Column::UserId.eq(user_id))
+            .filter(portfolios::Column::Deleted.eq(false))
+            .one(&self.db_conn)
+            .await?;
+
+        Ok(portfolio)
+    }
+
+    /// Set the quantities of `user_id` portfolio assets to the balances of a
+    /// linked exchange account. Assets not held yet are added with no
+    /// target weight, while assets missing from `holdings` are left
+    /// untouched, as are the assets of the same symbol not added by a
+    /// connector or of another asset class. The portfolio is marked as updated
+    /// if any quantity changed, so that clients pull it on next sync.
+    /// Returns the number of assets updated
+    pub async fn sync_holdings(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+        holdings: Vec<ExchangeHolding>,
+    ) -> Result<u64> {
+        let updated = self
+            .db_conn
+            .transaction::<_, u64, DcaError>(|txn| {
+                Box::pin(async move {
+                    if Self::find_owned(txn, user_id, portfolio_id)
+                        .await?
+                        .is_none_or(|p| p.deleted)
+                    {
+                        return Err(DcaError::PortfolioNotFound(portfolio_id));
+                    }
+
+                    let now: DateTimeWithTimeZone = Utc::now().into();
+                    let existing_assets = portfolio_asset::Entity::find()
+                        .filter(portfolio_asset::Column::PortfolioId.eq(portfolio_id))
+                        .all(txn)
+                        .await?;
+
+                    let mut updated = 0;
+                    for holding in holdings {
+                        match existing_assets.iter().find(|a| a.symbol == holding.symbol) {
+                            // Leave alone assets added by the user, e.g. an
+                            // equity sharing the ticker
+                            Some(existing)
+                                if existing.provider != Self::DCAPAL_PROVIDER
+                                    || existing.asset_class != holding.asset_class =>
+                            {
+                                warn!(
+                                    "Skipping holding '{}' of portfolio '{portfolio_id}': held as {} asset of {}",
+                                    holding.symbol, existing.asset_class, existing.provider
+                                );
+                                continue;
+                            }
+                            Some(existing) if existing.quantity == holding.quantity => continue,
+                            Some(existing) => {
+                                let mut model = existing.clone().into_active_model();
+                                model.quantity = Set(holding.quantity);
+                                model.quantity_as_of = Set(now);
+                                model.update(txn).await?;
+                            }
+                            None if holding.quantity.is_zero() => continue,
+                            None => {
+                                let fee_fields = Self::extract_fee_fields(None);
+                                portfolio_asset::ActiveModel {
+                                    id: Set(Uuid::new_v4()),
+                                    portfolio_id: Set(portfolio_id),
+                                    symbol: Set(holding.symbol.clone()),
+                                    name: Set(holding.name),
+                                    asset_class: Set(holding.asset_class),
+                                    currency: Set(holding.symbol),
+                                    provider: Set(Self::DCAPAL_PROVIDER.to_string()),
+                                    quantity: Set(holding.quantity),
+                                    quantity_as_of: Set(now),
+                                    target_weight: Set(Decimal::ZERO),
+                                    price: Set(Decimal::ZERO),
+                                    max_fee_impact: fee_fields.max_fee_impact,
+                                    fee_type: fee_fields.fee_type,
+                                    fee_amount: fee_fields.fee_amount,
+                                    fee_rate: fee_fields.fee_rate,
+                                    min_fee: fee_fields.min_fee,
+                                    max_fee: fee_fields.max_fee,
+                                    ..Default::default()
+                                }
+                                .insert(txn)
+                                .await?;
+                            }
+                        }
+                        updated += 1;
+                    }
+
+                    if updated > 0 {
+                        portfolios::Entity::update_many()
+                            .col_expr(portfolios::Column::LastUpdatedAt, Expr::value(now))
+                            .filter(portfolios::Column::Id.eq(portfolio_id))
+                            .exec(txn)
+                            .await?;
+                    }
+
+                    Ok(updated)
+                })
+            })
+            .await?;
+
+        Ok(updated)
+    }
+
+    /// Cash flows of `user_id` portfolio, newest first. Returns `None` if the
+    /// user has no such portfolio
+    pub async fn find_cash_flows(
+        &self,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Option<Vec<portfolio_cash_flow::Model>>> {
+        if self
+            .find_user_portfolio(user_id, portfolio_id)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let cash_flows = portfolio_cash_flow::Entity::find()
+            .filter(portfolio_cash_flow::Column::PortfolioId.eq(portfolio_id))
+            .order_by_desc(portfolio_cash_flow::Column::ExDate)
+            .all(&self.db_conn)
+            .await?;
+
+        Ok(Some(cash_flows))
+    }
+
+    /// Portfolio `portfolio_id`, if any. Fails if owned by another user than
+    /// `user_id`
+    async fn find_owned(
+        db: &impl sea_orm::ConnectionTrait,
+        user_id: Uuid,
+        portfolio_id: Uuid,
+    ) -> Result<Option<portfolios::Model>> {
+        let portfolio = portfolios::Entity::find_by_id(portfolio_id).one(db).await?;
+        match portfolio {
+            Some(p) if p.user_id != user_id => Err(DcaError::PortfolioForbidden(portfolio_id)),
+            p => Ok(p),
+        }
+    }
+
+    /// Holdings of the Yahoo Finance equity `symbol` in portfolios not
+    /// deleted
+    async fn find_holdings(
+        db: &impl sea_orm::ConnectionTrait,
+        symbol: &str,
+    ) -> Result<Vec<portfolio_asset::Model>> {
+        let holdings = portfolio_asset::Entity::find()
+            .join(
+                JoinType::InnerJoin,
+                portfolio_asset::Relation::Portfolios.def(),
+            )
+            .filter(portfolios::Column::Deleted.eq(false))
+            .filter(portfolio_asset::Column::Symbol.eq(symbol))
+            .filter(portfolio_asset::Column::Provider.eq(PortfolioSymbol::YAHOO_PROVIDER))
+            .filter(portfolio_asset::Column::AssetClass.eq(PortfolioSymbol::EQUITY_CLASS))
+            .all(db)
+            .await?;
+
+        Ok(holdings)
+    }
+
     fn extract_fee_fields(fees: Option<TransactionFeesRequest>) -> FeeFields {
         if let Some(fees) = fees {
             match fees.fee_structure {
@@ -229,3 +613,209 @@ impl PortfolioRepository {
         }
     }
 }
+
+/// Cumulative ratio of the splits following a dividend of `ex_date` that are
+/// already reflected in the `holding` quantity, i.e. effective by the time it
+/// was last set
+fn split_factor(
+    holding: &portfolio_asset::Model,
+    ex_date: NaiveDate,
+    later_splits: &[(NaiveDate, Decimal)],
+) -> Decimal {
+    later_splits
+        .iter()
+        .filter(|(date, _)| *date > ex_date)
+        .filter(|(date, _)| DateTimeWithTimeZone::from(day_start(*date)) <= holding.quantity_as_of)
+        .map(|(_, ratio)| *ratio)
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    use super::*;
+
+    fn portfolio(id: Uuid, user_id: Uuid) -> portfolios::Model {
+        portfolios::Model {
+            id,
+            user_id,
+            name: "Portfolio".to_string(),
+            currency: "eur".to_string(),
+            deleted: false,
+            last_updated_at: Default::default(),
+            max_fee_impact: None,
+            fee_type: None,
+            fee_amount: None,
+            fee_rate: None,
+            min_fee: None,
+            max_fee: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        }
+    }
+
+    fn asset(
+        portfolio_id: Uuid,
+        symbol: &str,
+        provider: &str,
+        asset_class: &str,
+    ) -> portfolio_asset::Model {
+        portfolio_asset::Model {
+            id: Uuid::new_v4(),
+            symbol: symbol.to_string(),
+            portfolio_id,
+            name: symbol.to_uppercase(),
+            asset_class: asset_class.to_string(),
+            currency: "usd".to_string(),
+            provider: provider.to_string(),
+            quantity: Decimal::ONE,
+            target_weight: Decimal::ZERO,
+            price: Decimal::ZERO,
+            max_fee_impact: None,
+            fee_type: None,
+            fee_amount: None,
+            fee_rate: None,
+            min_fee: None,
+            max_fee: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            quantity_as_of: Default::default(),
+        }
+    }
+
+    fn portfolio_request(id: Uuid) -> PortfolioRequest {
+        PortfolioRequest {
+            id,
+            name: "Portfolio".to_string(),
+            quote_ccy: "eur".to_string(),
+            fees: None,
+            assets: vec![],
+            last_updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_rejects_foreign_portfolio() {
+        let (user_id, owner_id, portfolio_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![portfolio(portfolio_id, owner_id)]])
+            .into_connection();
+        let repo = PortfolioRepository { db_conn: db };
+
+        let res = repo.soft_delete(user_id, portfolio_id).await;
+        assert!(matches!(res, Err(DcaError::PortfolioForbidden(id)) if id == portfolio_id));
+
+        // Only the lookup ran, nothing was updated
+        let log = repo.db_conn.into_transaction_log();
+        assert_eq!(log.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_updates_owned_portfolio() {
+        let (user_id, portfolio_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let deleted = portfolios::Model {
+            deleted: true,
+            ..portfolio(portfolio_id, user_id)
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![portfolio(portfolio_id, user_id)]])
+            .append_query_results(vec![vec![deleted]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let repo = PortfolioRepository { db_conn: db };
+
+        repo.soft_delete(user_id, portfolio_id).await.unwrap();
+
+        let log = repo.db_conn.into_transaction_log();
+        assert_eq!(log.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_rejects_foreign_portfolio() {
+        let (user_id, owner_id, portfolio_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![portfolio(portfolio_id, owner_id)]])
+            .into_connection();
+        let repo = PortfolioRepository { db_conn: db };
+
+        let res = repo.upsert(user_id, portfolio_request(portfolio_id)).await;
+        assert!(matches!(res, Err(DcaError::PortfolioForbidden(id)) if id == portfolio_id));
+    }
+
+    #[tokio::test]
+    async fn test_sync_holdings_rejects_foreign_portfolio() {
+        let (user_id, owner_id, portfolio_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![portfolio(portfolio_id, owner_id)]])
+            .into_connection();
+        let repo = PortfolioRepository { db_conn: db };
+
+        let res = repo.sync_holdings(user_id, portfolio_id, vec![]).await;
+        assert!(matches!(res, Err(DcaError::PortfolioForbidden(id)) if id == portfolio_id));
+    }
+
+    #[tokio::test]
+    async fn test_sync_holdings_skips_assets_not_added_by_connectors() {
+        let (user_id, portfolio_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![portfolio(portfolio_id, user_id)]])
+            .append_query_results(vec![vec![
+                asset(portfolio_id, "eth", "YF", "EQUITY"),
+                asset(
+                    portfolio_id,
+                    "btc",
+                    PortfolioRepository::DCAPAL_PROVIDER,
+                    "CURRENCY",
+                ),
+            ]])
+            .into_connection();
+        let repo = PortfolioRepository { db_conn: db };
+
+        let holding = |symbol: &str| ExchangeHolding {
+            symbol: symbol.to_string(),
+            name: symbol.to_uppercase(),
+            asset_class: PortfolioRepository::CRYPTO_CLASS.to_string(),
+            quantity: Decimal::TEN,
+        };
+        let updated = repo
+            .sync_holdings(user_id, portfolio_id, vec![holding("eth"), holding("btc")])
+            .await
+            .unwrap();
+        assert_eq!(updated, 0);
+
+        // Only the lookups ran, nothing was updated
+        let log = repo.db_conn.into_transaction_log();
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn test_split_factor_converts_to_shares_held_on_ex_date() {
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let holding = |as_of: &str| portfolio_asset::Model {
+            quantity: Decimal::from(40),
+            quantity_as_of: day_start(date(as_of)).into(),
+            ..asset(Uuid::new_v4(), "AAPL", "YF", "EQUITY")
+        };
+        let splits = [
+            (date("2020-08-31"), Decimal::from(4)),
+            (date("2022-06-01"), Decimal::from(2)),
+        ];
+
+        // Set before the dividend, no split to revert
+        let f = split_factor(&holding("2020-08-01"), date("2020-08-07"), &splits);
+        assert_eq!(f, Decimal::ONE);
+        // Set after the first split only
+        let f = split_factor(&holding("2020-09-15"), date("2020-08-07"), &splits);
+        assert_eq!(f, Decimal::from(4));
+        // Adjusted by both splits
+        let f = split_factor(&holding("2022-06-01"), date("2020-08-07"), &splits);
+        assert_eq!(f, Decimal::from(8));
+        // Splits preceding the dividend are already reflected in its amount
+        let f = split_factor(&holding("2022-06-01"), date("2021-02-05"), &splits);
+        assert_eq!(f, Decimal::from(2));
+    }
+}
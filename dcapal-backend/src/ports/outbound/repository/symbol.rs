@@ -0,0 +1,182 @@
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, SqlxPostgresConnector, entity::*,
+    sea_query::OnConflict, sqlx,
+};
+use tracing::error;
+
+use crate::{
+    app::domain::{
+        db::symbol_mapping,
+        entity::{AssetId, SymbolMapping},
+    },
+    error::{DcaError, Result},
+};
+
+/// Symbol mappings edited by operators. Deleted mappings are kept as
+/// tombstones, so that seeding never restores them
+pub struct SymbolRepository {
+    pub db_conn: DatabaseConnection,
+}
+
+impl SymbolRepository {
+    pub fn new(postgres: sqlx::PgPool) -> Self {
+        let db_conn = SqlxPostgresConnector::from_sqlx_postgres_pool(postgres);
+        Self { db_conn }
+    }
+
+    /// Mappings not deleted
+    pub async fn load_all(&self) -> Result<Vec<SymbolMapping>> {
+        let models = symbol_mapping::Entity::find()
+            .filter(symbol_mapping::Column::DeletedAt.is_null())
+            .all(&self.db_conn)
+            .await?;
+
+        let mappings = models
+            .into_iter()
+            .filter_map(|m| match serde_json::from_value(m.mapping.clone()) {
+                Ok(mapping) => Some(mapping),
+                Err(e) => {
+                    let err = DcaError::JsonDeserializationFailure(
+                        m.mapping.to_string(),
+                        std::any::type_name::<SymbolMapping>().to_string(),
+                        e,
+                    );
+                    error!("{:?}", err);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(mappings)
+    }
+
+    /// Insert `mappings` whose id was never stored, deleted ones included.
+    /// Returns the number of mappings inserted
+    pub async fn seed(&self, mappings: &[SymbolMapping]) -> Result<u64> {
+        if mappings.is_empty() {
+            return Ok(0);
+        }
+
+        let models = mappings
+            .iter()
+            .map(active_model)
+            .collect::<Result<Vec<_>>>()?;
+
+        let inserted = symbol_mapping::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(symbol_mapping::Column::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db_conn)
+            .await?;
+
+        Ok(inserted)
+    }
+
+    /// Store `mapping`, replacing (or restoring) any mapping with the same id
+    pub async fn store(&self, mapping: &SymbolMapping) -> Result<()> {
+        symbol_mapping::Entity::insert(active_model(mapping)?)
+            .on_conflict(
+                OnConflict::column(symbol_mapping::Column::Id)
+                    .update_columns([
+                        symbol_mapping::Column::Mapping,
+                        symbol_mapping::Column::DeletedAt,
+                        symbol_mapping::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db_conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mark `id` mapping as deleted. Returns `false` if there is no such
+    /// mapping
+    pub async fn delete(&self, id: &AssetId) -> Result<bool> {
+        let res = symbol_mapping::Entity::update_many()
+            .col_expr(
+                symbol_mapping::Column::DeletedAt,
+                Utc::now().fixed_offset().into(),
+            )
+            .filter(symbol_mapping::Column::Id.eq(id))
+            .filter(symbol_mapping::Column::DeletedAt.is_null())
+            .exec(&self.db_conn)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+}
+
+fn active_model(mapping: &SymbolMapping) -> Result<symbol_mapping::ActiveModel> {
+    let json = serde_json::to_value(mapping).map_err(|e| {
+        DcaError::JsonSerializationFailure(std::any::type_name::<SymbolMapping>().to_string(), e)
+    })?;
+
+    Ok(symbol_mapping::ActiveModel {
+        id: Set(mapping.id.clone()),
+        mapping: Set(json),
+        deleted_at: Set(None),
+        updated_at: Set(Utc::now().into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    use super::*;
+
+    fn mapping(id: &str) -> SymbolMapping {
+        SymbolMapping {
+            id: id.to_string(),
+            alias_of: None,
+            tickers: HashMap::new(),
+            isin: None,
+            cusip: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seed_skips_stored_and_deleted_ids() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let repo = SymbolRepository { db_conn: db };
+
+        let inserted = repo.seed(&[mapping("eth2.s"), mapping("luna")]).await;
+        assert_eq!(inserted.unwrap(), 1);
+
+        let log = repo.db_conn.into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let sql = log[0].statements()[0].sql.clone();
+        assert!(sql.contains(r#"ON CONFLICT ("id") DO NOTHING"#), "{sql}");
+    }
+
+    #[tokio::test]
+    async fn test_delete_keeps_tombstone() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let repo = SymbolRepository { db_conn: db };
+
+        assert!(repo.delete(&"luna".to_string()).await.unwrap());
+
+        let log = repo.db_conn.into_transaction_log();
+        let sql = log[0].statements()[0].sql.clone();
+        assert!(
+            sql.starts_with(r#"UPDATE "symbol_mapping" SET "deleted_at""#),
+            "{sql}"
+        );
+    }
+}
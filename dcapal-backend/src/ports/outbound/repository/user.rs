@@ -26,9 +26,9 @@ impl UserRepository {
             // Create the active model with new values
             let user = users::ActiveModel {
                 id: Set(claims.sub),
-                username: Set(claims.user_metadata.full_name.clone()),
-                email: Set(claims.user_metadata.email.clone()),
-                role: Set(claims.role.clone()),
+                username: Set(claims.full_name.clone()),
+                email: Set(claims.email.clone()),
+                role: Set(claims.role.clone().unwrap_or_default()),
                 created_at: NotSet,
                 updated_at: Set(chrono::Utc::now().into()),
             };
@@ -44,9 +44,9 @@ impl UserRepository {
             // User doesn't exist, create new
             let user = users::ActiveModel {
                 id: Set(claims.sub),
-                username: Set(claims.user_metadata.full_name.clone()),
-                email: Set(claims.user_metadata.email.clone()),
-                role: Set(claims.role.clone()),
+                username: Set(claims.full_name.clone()),
+                email: Set(claims.email.clone()),
+                role: Set(claims.role.clone().unwrap_or_default()),
                 created_at: Set(chrono::Utc::now().into()),
                 updated_at: Set(chrono::Utc::now().into()),
             };
@@ -66,16 +66,11 @@ mod tests {
     async fn test_save_user_if_not_present_updates_existing_user() {
         let user_id = uuid::Uuid::new_v4();
         let claims = Claims {
-            iat: 0,
-            exp: 0,
             sub: user_id,
-            user_metadata: crate::app::infra::claim::UserMetadataClaim {
-                email: "updated-email@example.com".to_string(),
-                full_name: Some("Test User".to_string()),
-            },
-            role: "user".to_string(),
-            session_id: Default::default(),
-            aud: "".to_string(),
+            session_id: None,
+            role: Some("user".to_string()),
+            email: Some("updated-email@example.com".to_string()),
+            full_name: Some("Test User".to_string()),
         };
 
         let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -83,7 +78,7 @@ mod tests {
             .append_query_results(vec![vec![users::Model {
                 id: user_id,
                 username: Some("Existing User".to_string()),
-                email: "old-email@example.com".to_string(),
+                email: Some("old-email@example.com".to_string()),
                 role: "user".to_string(),
                 created_at: Default::default(),
                 updated_at: Default::default(),
@@ -92,7 +87,7 @@ mod tests {
             .append_query_results(vec![vec![users::Model {
                 id: user_id,
                 username: Some("Existing User".to_string()),
-                email: "updated-email@example.com".to_string(),
+                email: Some("updated-email@example.com".to_string()),
                 role: "user".to_string(),
                 created_at: Default::default(),
                 updated_at: Default::default(),
@@ -106,6 +101,9 @@ mod tests {
         let repo = UserRepository { db_conn: db };
 
         let updated_user = repo.save_user_if_not_present(&claims).await.unwrap();
-        assert_eq!(updated_user.email, "updated-email@example.com");
+        assert_eq!(
+            updated_user.email.as_deref(),
+            Some("updated-email@example.com")
+        );
     }
 }
@@ -60,19 +60,7 @@ export const fetchPriceYF = async (symbol, quote, validCcys, token) => {
       return null;
     }
 
-    // On error, log and exit
-    if (!response.data.chart || response.data.chart?.error) {
-      console.error(response.data.chart?.error);
-      return FetchError.BAD_DATA;
-    }
-
-    const result = response.data.chart.result;
-    if (!Array.isArray(result) || result.length < 1) {
-      console.error("Empty YF price result:", response.data, url);
-      return FetchError.BAD_DATA;
-    }
-
-    const base = result[0].meta?.currency?.toLowerCase();
+    const base = response.data.currency?.toLowerCase();
     if (!base) {
       console.error("Missing base currency:", response.data, url);
       return FetchError.BAD_DATA;
@@ -84,22 +72,13 @@ export const fetchPriceYF = async (symbol, quote, validCcys, token) => {
       return FetchError.BAD_DATA;
     }
 
-    const quotes = result[0].indicators?.quote;
-    if (!Array.isArray(quotes) || quotes.length < 1) {
-      console.error("Empty quotes:", response.data, url);
-      return FetchError.BAD_DATA;
-    }
-
-    const closePrices = quotes[0].close;
-    if (!Array.isArray(closePrices) || closePrices.length < 1) {
-      console.error("Empty close prices:", response.data, url);
+    const candles = response.data.candles;
+    if (!Array.isArray(candles) || candles.length < 1) {
+      console.error("Empty candles:", response.data, url);
       return FetchError.BAD_DATA;
     }
 
-    const price = closePrices
-      .slice()
-      .reverse()
-      .find((p) => p);
+    const price = candles[candles.length - 1].close;
 
     if (base === quote) {
       return [price, base];
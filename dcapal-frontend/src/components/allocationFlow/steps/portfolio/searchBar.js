@@ -21,7 +21,7 @@ import { useTranslation } from "react-i18next";
 let searchId = undefined;
 
 const fetchAssetsYF = async (query) => {
-  const url = `${DCAPAL_API}/assets/search?name=${query}`;
+  const url = `${DCAPAL_API}/assets/search?name=${query}&aclass=equity`;
   try {
     const response = await api.get(url);
 
@@ -32,16 +32,16 @@ const fetchAssetsYF = async (query) => {
       return [];
     }
 
-    return response.data.quotes
-      .filter((quote) => {
-        const type = quote.quoteType.toUpperCase();
+    return response.data.results
+      .filter((res) => {
+        const type = res.quoteType.toUpperCase();
         return type === "EQUITY" || type === "ETF" || type === "MUTUALFUND";
       })
-      .map((quote) => ({
-        name: quote.longname || quote.shortname || "",
-        symbol: quote.symbol,
-        type: quote.quoteType,
-        exchange: quote.exchange,
+      .map((res) => ({
+        name: res.name,
+        symbol: res.symbol,
+        type: res.quoteType,
+        exchange: res.exchange,
         aclass: ACLASS.EQUITY,
       }));
   } catch (error) {
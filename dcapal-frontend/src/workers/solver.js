@@ -7,7 +7,9 @@ const buildProblemInput = (
   pfolioCcy,
   fees,
   isBuyOnly,
-  useAllBudget
+  useAllBudget,
+  dividends,
+  reinvestPolicy
 ) => {
   return {
     type: "advanced",
@@ -19,11 +21,22 @@ const buildProblemInput = (
     is_buy_only: isBuyOnly,
     use_all_budget: useAllBudget,
     fees: fees,
+    dividends: dividends ?? {},
+    reinvest_policy: reinvestPolicy ?? "sameAsset",
   };
 };
 
 expose({
-  async makeAndSolve(budget, assets, pfolioCcy, fees, isBuyOnly, useAllBudget) {
+  async makeAndSolve(
+    budget,
+    assets,
+    pfolioCcy,
+    fees,
+    isBuyOnly,
+    useAllBudget,
+    dividends,
+    reinvestPolicy
+  ) {
     if (Number.isNaN(budget) || budget < 0) return null;
     if (!assets || Object.keys(assets).length === 0) return null;
 
@@ -33,7 +46,9 @@ expose({
       pfolioCcy,
       fees,
       isBuyOnly,
-      useAllBudget
+      useAllBudget,
+      dividends,
+      reinvestPolicy
     );
 
     const handle = Solver.build_problem(input);
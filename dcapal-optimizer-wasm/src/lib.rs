@@ -5,7 +5,7 @@ use std::{collections::HashMap, sync::Mutex};
 
 use optimize::{
     FeeStructure, FeeStructureFixed, FeeStructureVariable, TransactionFees,
-    advanced::{self, TheoreticalAllocation},
+    advanced::{self, ReinvestPolicy, TheoreticalAllocation},
     basic,
 };
 use rand::{Rng, distr};
@@ -155,11 +155,29 @@ impl Solver {
             .filter_map(|(aid, v)| v.theo_alloc.clone().map(|t| (aid.clone(), t.into())))
             .collect();
 
+        let mut cash_allocations: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        let mut cash_left: HashMap<String, f64> = HashMap::new();
+        for alloc in &solution.cash_allocations {
+            let amount = alloc.amount.to_f64().unwrap();
+            match &alloc.asset {
+                Some(aid) => {
+                    *cash_allocations
+                        .entry(alloc.source.clone())
+                        .or_default()
+                        .entry(aid.clone())
+                        .or_default() += amount
+                }
+                None => *cash_left.entry(alloc.source.clone()).or_default() += amount,
+            }
+        }
+
         let js_solution = JsAdvancedSolution {
             budget_left,
             amounts,
             shares,
             theo_allocs,
+            cash_allocations,
+            cash_left,
         };
         Ok(serde_wasm_bindgen::to_value(&js_solution).unwrap())
     }
@@ -230,6 +248,10 @@ pub struct JsAdvancedSolution {
     pub amounts: HashMap<String, f64>,
     pub shares: HashMap<String, f64>,
     pub theo_allocs: HashMap<String, JsTheoreticalAllocation>,
+    /// Amount of each cash source spent on each asset
+    pub cash_allocations: HashMap<String, HashMap<String, f64>>,
+    /// Amount of each cash source left unallocated
+    pub cash_left: HashMap<String, f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -266,6 +288,20 @@ pub struct JsAdvancedOptions {
     pub is_buy_only: bool,
     #[serde(default)]
     pub use_all_budget: bool,
+    #[serde(default)]
+    pub dividends: HashMap<String, JsDividendCash>,
+    #[serde(default)]
+    pub reinvest_policy: ReinvestPolicy,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsDividendCash {
+    /// Asset paying the dividend
+    pub symbol: String,
+    pub amount: f64,
+    pub currency: String,
+    /// Conversion rate to portfolio currency, if paid in another currency
+    pub fx_rate: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -371,6 +407,14 @@ impl TryFrom<JsAdvancedOptions> for advanced::ProblemOptions {
             .transpose()?
             .unwrap_or_default();
 
+        let dividends = options
+            .dividends
+            .into_iter()
+            .map(|(id, d)| {
+                advanced::DividendCash::try_from((d, options.pfolio_ccy.as_str())).map(|d| (id, d))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
         Ok(advanced::ProblemOptions {
             pfolio_ccy: options.pfolio_ccy,
             current_pfolio_amount: current_total,
@@ -379,6 +423,50 @@ impl TryFrom<JsAdvancedOptions> for advanced::ProblemOptions {
             fees,
             is_buy_only: options.is_buy_only,
             use_all_budget: options.use_all_budget,
+            dividends,
+            reinvest_policy: options.reinvest_policy,
+        })
+    }
+}
+
+impl TryFrom<(JsDividendCash, &str)> for advanced::DividendCash {
+    type Error = String;
+
+    fn try_from((dividend, pfolio_ccy): (JsDividendCash, &str)) -> Result<Self, Self::Error> {
+        let JsDividendCash {
+            symbol,
+            amount,
+            currency,
+            fx_rate,
+        } = dividend;
+
+        if symbol.is_empty() {
+            return Err("Invalid dividend symbol. Must not be empty".to_string());
+        }
+
+        if amount < 0. {
+            return Err(format!(
+                "Invalid dividend amount ({}). Must be zero or positive",
+                amount
+            ));
+        }
+
+        let fx_rate = if currency.eq_ignore_ascii_case(pfolio_ccy) {
+            1.
+        } else {
+            match fx_rate {
+                Some(rate) if rate > 0. => rate,
+                _ => {
+                    return Err(format!(
+                        "Invalid {symbol} dividend. Missing {currency}/{pfolio_ccy} rate"
+                    ));
+                }
+            }
+        };
+
+        Ok(advanced::DividendCash {
+            symbol,
+            amount: parse_amount(amount * fx_rate),
         })
     }
 }
@@ -3,6 +3,7 @@ use std::{cmp::Ordering, collections::HashMap};
 use log::debug;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 
 use super::TransactionFees;
 use crate::{AMOUNT_DECIMALS, PERCENTAGE_DECIMALS, SHARES_DECIMALS};
@@ -16,6 +17,32 @@ pub struct ProblemOptions {
     pub fees: TransactionFees,
     pub is_buy_only: bool,
     pub use_all_budget: bool,
+    /// Dividend cash to reinvest, by cash source id
+    pub dividends: HashMap<String, DividendCash>,
+    pub reinvest_policy: ReinvestPolicy,
+}
+
+/// Cash source id of the regular contribution
+pub const BUDGET_SOURCE: &str = "budget";
+
+/// Dividend cash paid by an asset, in portfolio currency
+#[derive(Debug, Clone)]
+pub struct DividendCash {
+    pub symbol: String,
+    pub amount: Decimal,
+}
+
+/// Where dividend cash is reinvested
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReinvestPolicy {
+    /// Buy more of the asset paying the dividend
+    #[default]
+    SameAsset,
+    /// Buy the assets farthest below their target allocation
+    MostUnderweight,
+    /// Add dividends to the budget, allocated together
+    Pooled,
 }
 
 #[derive(Debug, Clone)]
@@ -130,16 +157,35 @@ pub struct Problem {
     pub(crate) options: ProblemOptions,
 }
 
+/// Amount of a cash source spent on an asset, or left unallocated if `asset`
+/// is `None`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashAllocation {
+    pub source: String,
+    pub asset: Option<String>,
+    pub amount: Decimal,
+}
+
 #[derive(Debug, Clone)]
 pub struct Solution {
     pub is_solved: bool,
     pub assets: HashMap<String, Asset>,
     pub budget_left: Decimal,
+    pub cash_allocations: Vec<CashAllocation>,
 }
 
 impl Solution {
     pub fn new(options: ProblemOptions) -> Self {
-        let (pfolio_amount, budget) = (options.current_pfolio_amount, options.budget);
+        // Targets account for the whole cash invested, dividends included
+        let (pfolio_amount, budget) = (
+            options.current_pfolio_amount,
+            options.budget
+                + options
+                    .dividends
+                    .values()
+                    .map(|d| d.amount)
+                    .sum::<Decimal>(),
+        );
 
         let assets = options
             .assets
@@ -151,6 +197,7 @@ impl Solution {
             is_solved: false,
             assets,
             budget_left: Decimal::ZERO,
+            cash_allocations: Vec::new(),
         }
     }
 }
@@ -166,7 +213,18 @@ impl Problem {
         let mut solution = Solution::new(self.options.clone());
 
         // New portfolio amount
-        let pfolio_amount = self.options.current_pfolio_amount + self.options.budget;
+        let dividends_amount: Decimal = self.options.dividends.values().map(|d| d.amount).sum();
+        let pfolio_amount =
+            self.options.current_pfolio_amount + self.options.budget + dividends_amount;
+
+        // Reinvest dividends first, pooling what is left with the budget
+        let (mut pooled_sources, reinvested) =
+            reinvest_dividends(&mut solution, &self.options, pfolio_amount);
+        pooled_sources.insert(0, (BUDGET_SOURCE.to_string(), self.options.budget));
+        let pool = pooled_sources
+            .iter()
+            .map(|(_, amount)| *amount)
+            .sum::<Decimal>();
 
         let sold_amount = if self.options.is_buy_only {
             close_fully_allocated_assets(&mut solution.assets);
@@ -176,7 +234,7 @@ impl Problem {
         };
 
         // Budget available to allocate
-        let mut budget_left = self.options.budget + sold_amount;
+        let mut budget_left = pool + sold_amount;
 
         debug!(
             "[Init] solution={solution:?} pfolio_amount={pfolio_amount} sold_amount={sold_amount} budget_left={budget_left}"
@@ -201,6 +259,10 @@ impl Problem {
 
         solution.is_solved = true;
         solution.budget_left = budget_left;
+        solution.cash_allocations = reinvested;
+        solution
+            .cash_allocations
+            .extend(attribute_pool(&solution, &pooled_sources, pool));
 
         debug!("[Solution] solution={solution:?}");
 
@@ -423,6 +485,171 @@ impl Problem {
     }
 }
 
+/// Spend dividend cash according to the reinvest policy, as if bought before
+/// allocating the budget. Purchases pay their transaction fees out of the
+/// dividend and are skipped if the fee impact is too high. Unless buying only,
+/// assets are not bought past their target. Returns the cash left of each
+/// dividend, to be pooled with the budget, and the amounts reinvested (fees
+/// included)
+fn reinvest_dividends(
+    solution: &mut Solution,
+    options: &ProblemOptions,
+    pfolio_amount: Decimal,
+) -> (Vec<(String, Decimal)>, Vec<CashAllocation>) {
+    let policy = options.reinvest_policy;
+    let mut sources = options.dividends.iter().collect::<Vec<_>>();
+    sources.sort_by_key(|(id, _)| *id);
+
+    let mut left = Vec::new();
+    let mut reinvested = Vec::new();
+    for (source, dividend) in sources {
+        let mut cash = dividend.amount;
+
+        let mut candidates = match policy {
+            ReinvestPolicy::Pooled => vec![],
+            ReinvestPolicy::SameAsset => solution
+                .assets
+                .values_mut()
+                .filter(|a| a.symbol == dividend.symbol)
+                .collect::<Vec<_>>(),
+            ReinvestPolicy::MostUnderweight => {
+                let mut candidates = solution
+                    .assets
+                    .values_mut()
+                    .filter(|a| a.target_amount > a.current_amount)
+                    .collect::<Vec<_>>();
+
+                // Order by distance from target allocation, descending
+                candidates.sort_by(|a, b| {
+                    (a.target_amount - a.current_amount)
+                        .cmp(&(b.target_amount - b.current_amount))
+                        .reverse()
+                        .then_with(|| a.symbol.cmp(&b.symbol))
+                });
+                candidates
+            }
+        };
+
+        for asset in &mut candidates {
+            let below_target = asset.target_amount - asset.current_amount;
+            let amount = if policy == ReinvestPolicy::MostUnderweight || !options.is_buy_only {
+                Decimal::min(below_target, cash)
+            } else {
+                cash
+            };
+
+            let Some((bought_shares, bought, fee)) =
+                shares_to_reinvest(asset, amount, &options.fees)
+            else {
+                continue;
+            };
+
+            asset.current_shares += bought_shares;
+            asset.current_amount += bought;
+            asset.shares = asset.current_shares;
+            asset.amount = asset.current_amount;
+            asset.current_weight =
+                (asset.current_amount / pfolio_amount).round_dp(PERCENTAGE_DECIMALS);
+            asset.weight = asset.current_weight;
+            cash -= bought + fee;
+
+            reinvested.push(CashAllocation {
+                source: source.clone(),
+                asset: Some(asset.symbol.clone()),
+                amount: bought + fee,
+            });
+        }
+
+        debug!("[Reinvest] source={source} dividend={dividend:?} left={cash}");
+        left.push((source.clone(), cash));
+    }
+
+    (left, reinvested)
+}
+
+/// Shares of `asset` bought with at most `amount`, fees included, along with
+/// their cost and fee. Returns `None` if no share can be bought or the fee
+/// impact is too high
+fn shares_to_reinvest(
+    asset: &Asset,
+    amount: Decimal,
+    general_fees: &TransactionFees,
+) -> Option<(Decimal, Decimal, Decimal)> {
+    const MAX_ATTEMPTS: usize = 10;
+
+    // Shrink the spending until the rounded cost and its fee fit in `amount`
+    let mut spending = amount;
+    for _ in 0..MAX_ATTEMPTS {
+        if spending <= Decimal::ZERO {
+            return None;
+        }
+
+        let shares = shares_to_allocate(asset, spending);
+        if shares <= Decimal::ZERO {
+            return None;
+        }
+
+        let bought = (shares * asset.price).round_dp(AMOUNT_DECIMALS);
+        if bought <= Decimal::ZERO {
+            return None;
+        }
+
+        let fee = asset.compute_fee(&bought, general_fees);
+        let excess = bought + fee - amount;
+        if excess <= Decimal::ZERO {
+            let impact = (fee / bought).round_dp(PERCENTAGE_DECIMALS);
+            if impact > asset.get_max_fee_impact(general_fees) {
+                debug!("[Reinvest] Fee impact too high: asset={asset:?} fee={fee}");
+                return None;
+            }
+
+            return Some((shares, bought, fee));
+        }
+
+        spending -= Decimal::max(excess, Decimal::new(1, AMOUNT_DECIMALS));
+    }
+
+    None
+}
+
+/// Split the pooled allocations across their cash sources, proportionally to
+/// each source contribution to the pool
+fn attribute_pool(
+    solution: &Solution,
+    sources: &[(String, Decimal)],
+    pool: Decimal,
+) -> Vec<CashAllocation> {
+    if pool <= Decimal::ZERO {
+        return vec![];
+    }
+
+    let mut assets = solution
+        .assets
+        .values()
+        .filter(|a| a.get_allocated_amount() > Decimal::ZERO)
+        .map(|a| (Some(a.symbol.clone()), a.get_allocated_amount()))
+        .collect::<Vec<_>>();
+    assets.sort_by(|(a, _), (b, _)| a.cmp(b));
+    assets.push((None, solution.budget_left));
+
+    let mut allocations = Vec::new();
+    for (source, contribution) in sources.iter().filter(|(_, c)| *c > Decimal::ZERO) {
+        let share = *contribution / pool;
+        for (asset, amount) in &assets {
+            let amount = (*amount * share).round_dp(AMOUNT_DECIMALS);
+            if amount > Decimal::ZERO {
+                allocations.push(CashAllocation {
+                    source: source.clone(),
+                    asset: asset.clone(),
+                    amount,
+                });
+            }
+        }
+    }
+
+    allocations
+}
+
 fn is_negligible(budget: &Decimal) -> bool {
     *budget < dec!(0.01)
 }
@@ -624,6 +851,7 @@ mod tests {
     use rust_decimal_macros::dec;
 
     use super::*;
+    use crate::optimize::{FeeStructure, FeeStructureFixed};
 
     #[test_log::test]
     fn it_solves_60_40_portfolio_buy_only() {
@@ -755,6 +983,213 @@ mod tests {
         assert_eq!(solution.budget_left, dec!(0.58));
     }
 
+    #[test_log::test]
+    fn it_reinvests_dividends_in_same_asset() {
+        // Given
+        let problem = build_dividend_portfolio(ReinvestPolicy::SameAsset);
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(13));
+        assert_eq!(solution.assets["B"].shares, dec!(7));
+        assert_eq!(
+            solution.cash_allocations,
+            vec![
+                cash_allocation("div-a", Some("A"), dec!(30)),
+                cash_allocation(BUDGET_SOURCE, Some("B"), dec!(20)),
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn it_reinvests_dividends_in_most_underweight_asset() {
+        // Given
+        let problem = build_dividend_portfolio(ReinvestPolicy::MostUnderweight);
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(10));
+        assert_eq!(solution.assets["B"].shares, dec!(10));
+        assert_eq!(
+            solution.cash_allocations,
+            vec![
+                cash_allocation("div-a", Some("B"), dec!(30)),
+                cash_allocation(BUDGET_SOURCE, Some("B"), dec!(20)),
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn it_pools_dividends_with_budget() {
+        // Given
+        let problem = build_dividend_portfolio(ReinvestPolicy::Pooled);
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(10));
+        assert_eq!(solution.assets["B"].shares, dec!(10));
+        assert_eq!(solution.budget_left, Decimal::ZERO);
+        assert_eq!(
+            solution.cash_allocations,
+            vec![
+                cash_allocation(BUDGET_SOURCE, Some("B"), dec!(20)),
+                cash_allocation("div-a", Some("B"), dec!(30)),
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn it_pays_reinvestment_fees_out_of_dividends() {
+        // Given
+        let fees = TransactionFees {
+            max_fee_impact: dec!(0.1),
+            fee_structure: FeeStructure::Fixed(FeeStructureFixed {
+                fee_amount: dec!(1),
+            }),
+        };
+        let problem = build_dividend_portfolio_with(ReinvestPolicy::SameAsset, true, fees);
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(12.9));
+        assert_eq!(
+            solution.cash_allocations[0],
+            cash_allocation("div-a", Some("A"), dec!(30))
+        );
+    }
+
+    #[test_log::test]
+    fn it_skips_reinvestment_with_too_high_fee_impact() {
+        // Given
+        let fees = TransactionFees {
+            max_fee_impact: dec!(0.01),
+            fee_structure: FeeStructure::Fixed(FeeStructureFixed {
+                fee_amount: dec!(1),
+            }),
+        };
+        let problem = build_dividend_portfolio_with(ReinvestPolicy::SameAsset, true, fees);
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(10));
+        assert!(
+            solution
+                .cash_allocations
+                .iter()
+                .all(|a| a.asset.as_deref() != Some("A"))
+        );
+    }
+
+    #[test_log::test]
+    fn it_does_not_reinvest_past_target_when_rebalancing() {
+        // Given
+        let problem =
+            build_dividend_portfolio_with(ReinvestPolicy::SameAsset, false, Default::default());
+
+        // When
+        let solution = problem.solve();
+
+        // Expect
+        assert!(solution.is_solved);
+        assert_eq!(solution.assets["A"].shares, dec!(10));
+        assert_eq!(solution.assets["B"].shares, dec!(10));
+        assert_eq!(
+            solution.cash_allocations,
+            vec![
+                cash_allocation(BUDGET_SOURCE, Some("B"), dec!(20)),
+                cash_allocation("div-a", Some("B"), dec!(30)),
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn it_never_reinvests_more_than_the_dividend() {
+        // Given
+        let asset = ProblemAsset {
+            symbol: "A".to_string(),
+            shares: dec!(1),
+            price: dec!(30000),
+            target_weight: dec!(1),
+            is_whole_shares: false,
+            fees: None,
+        };
+        let asset = Asset::new(asset, dec!(30000), dec!(20));
+
+        // When
+        let (shares, bought, fee) =
+            shares_to_reinvest(&asset, dec!(20), &TransactionFees::default()).unwrap();
+
+        // Expect: 20 / 30000 rounds up to 0.00066667 shares, worth 20.0001
+        assert_eq!(shares, dec!(0.00066666));
+        assert_eq!(bought, dec!(19.9998));
+        assert_eq!(fee, Decimal::ZERO);
+    }
+
+    fn cash_allocation(source: &str, asset: Option<&str>, amount: Decimal) -> CashAllocation {
+        CashAllocation {
+            source: source.to_string(),
+            asset: asset.map(str::to_string),
+            amount,
+        }
+    }
+
+    fn build_dividend_portfolio(reinvest_policy: ReinvestPolicy) -> Problem {
+        build_dividend_portfolio_with(reinvest_policy, true, TransactionFees::default())
+    }
+
+    fn build_dividend_portfolio_with(
+        reinvest_policy: ReinvestPolicy,
+        is_buy_only: bool,
+        fees: TransactionFees,
+    ) -> Problem {
+        let asset = |symbol: &str, shares| ProblemAsset {
+            symbol: symbol.to_string(),
+            shares,
+            price: dec!(10),
+            target_weight: dec!(0.5),
+            is_whole_shares: false,
+            fees: None,
+        };
+
+        let options = ProblemOptions {
+            pfolio_ccy: "eur".into(),
+            current_pfolio_amount: dec!(150),
+            assets: HashMap::from([
+                ("A".to_string(), asset("A", dec!(10))),
+                ("B".to_string(), asset("B", dec!(5))),
+            ]),
+            budget: dec!(20),
+            fees,
+            is_buy_only,
+            use_all_budget: false,
+            dividends: HashMap::from([(
+                "div-a".to_string(),
+                DividendCash {
+                    symbol: "A".to_string(),
+                    amount: dec!(30),
+                },
+            )]),
+            reinvest_policy,
+        };
+
+        Problem::new(options)
+    }
+
     fn build_60_40_portfolio_no_allocation(
         is_buy_only: bool,
         use_all_budget: bool,
@@ -800,6 +1235,8 @@ mod tests {
             fees: TransactionFees::default(),
             is_buy_only,
             use_all_budget,
+            dividends: HashMap::new(),
+            reinvest_policy: ReinvestPolicy::default(),
         };
 
         (Problem::new(options), vec![vwce, aggh])
@@ -858,6 +1295,8 @@ mod tests {
             fees: TransactionFees::default(),
             is_buy_only,
             use_all_budget,
+            dividends: HashMap::new(),
+            reinvest_policy: ReinvestPolicy::default(),
         };
 
         (Problem::new(options), vec![vwce, aggh])
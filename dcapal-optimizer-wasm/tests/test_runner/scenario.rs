@@ -40,6 +40,8 @@ impl Scenario {
                     fees: self.portfolio.fees,
                     is_buy_only: self.is_buy_only,
                     use_all_budget: self.use_all_budget,
+                    dividends: Default::default(),
+                    reinvest_policy: Default::default(),
                 })
             }
         };